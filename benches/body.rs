@@ -0,0 +1,31 @@
+//! Times each of the snake's body storage back ends, best of a few runs. Only meaningful as
+//! `cargo bench`, in a release build.
+
+use graphics::bench::{Backend, BenchSnake, LENGTH};
+use std::time::{Duration, Instant};
+
+const UPDATES: u32 = 100_000;
+const RUNS: u32 = 5;
+
+fn main() {
+    println!("{UPDATES} updates of a {LENGTH}-cell snake, best of {RUNS}");
+    for backend in Backend::ALL {
+        let best = (0..RUNS)
+            .map(|_| {
+                let mut snake = BenchSnake::new(backend);
+                let started = Instant::now();
+                for _ in 0..UPDATES {
+                    snake.update();
+                }
+                started.elapsed()
+            })
+            .min()
+            .unwrap_or(Duration::ZERO);
+        println!(
+            "{:<10} {:>8.2} ms  ({:.0} ns/update)",
+            format!("{backend:?}"),
+            best.as_secs_f64() * 1e3,
+            best.as_nanos() as f64 / UPDATES as f64
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! A very patient autopilot. It walks a fixed Hamiltonian cycle that visits every cell of the
+//! grid, so it never runs into itself and is guaranteed to fill the board eventually.
+//!
+//! There's also a plain shortest path search, for hinting at a way to the food.
+
+use crate::{Direction, GridPosition, WrapMode, GRID_SIZE};
+use std::collections::VecDeque;
+
+/// The direction the autopilot takes from `head`, or `None` if the grid has no Hamiltonian
+/// cycle (both sides odd).
+pub fn next_direction(head: GridPosition) -> Option<Direction> {
+    cycle_direction(head, GRID_SIZE)
+}
+
+/// The cycle snakes along the rows from column 1 onwards and comes back up along column 0,
+/// which needs an even number of rows. With an odd number of rows we use the transposed
+/// cycle instead.
+fn cycle_direction(pos: GridPosition, (width, height): (i16, i16)) -> Option<Direction> {
+    if height % 2 == 1 {
+        if width % 2 == 1 {
+            return None;
+        }
+        let transposed = cycle_direction(GridPosition::new(pos.y, pos.x), (height, width))?;
+        return Some(match transposed {
+            Direction::Up => Direction::Left,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Up,
+            Direction::Right => Direction::Down,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            diagonal => diagonal,
+        });
+    }
+
+    let dir = if pos.x == 0 {
+        if pos.y == 0 {
+            Direction::Right
+        } else {
+            Direction::Up
+        }
+    } else if pos.y % 2 == 0 {
+        if pos.x == width - 1 {
+            Direction::Down
+        } else {
+            Direction::Right
+        }
+    } else if pos.x > 1 || pos.y == height - 1 {
+        Direction::Left
+    } else {
+        Direction::Down
+    };
+    Some(dir)
+}
+
+/// The shortest way from `start` to `goal` in straight steps without touching a `blocked`
+/// cell, found breadth first. The path leaves out `start` and ends on `goal`.
+pub fn path_to(
+    start: GridPosition,
+    goal: GridPosition,
+    wrap_mode: WrapMode,
+    blocked: impl Fn(GridPosition) -> bool,
+) -> Option<Vec<GridPosition>> {
+    let index = |pos: GridPosition| (pos.y * GRID_SIZE.0 + pos.x) as usize;
+    let mut came_from: Vec<Option<GridPosition>> =
+        vec![None; GRID_SIZE.0 as usize * GRID_SIZE.1 as usize];
+    came_from[index(start)] = Some(start);
+    let mut frontier = VecDeque::from([start]);
+    while let Some(pos) = frontier.pop_front() {
+        if pos == goal {
+            let mut path = vec![goal];
+            let mut at = goal;
+            while let Some(prev) = came_from[index(at)].filter(|&prev| prev != start) {
+                path.push(prev);
+                at = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for dir in Direction::CARDINAL {
+            let Some(next) = GridPosition::moved(pos, dir, wrap_mode) else {
+                continue;
+            };
+            if came_from[index(next)].is_none() && !blocked(next) {
+                came_from[index(next)] = Some(pos);
+                frontier.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,107 @@
+//! `--attract SECS`: left alone on the game over screen for `SECS`, the game plays itself
+//! like an arcade cabinet does, until a key or a click brings the game over screen back.
+//!
+//! The demo is a whole game of its own, with the autopilot steering, so nothing it does
+//! touches the player's game, the scoreboard or the history.
+
+use crate::config::Config;
+use crate::text::{self, TextStyle};
+use crate::{GameState, SCREEN_SIZE};
+use ggez::event::EventHandler;
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color};
+use ggez::{Context, GameResult};
+use std::time::Duration;
+
+/// The longest a demo plays before going back to the game over screen for a while.
+const DEMO_LENGTH: Duration = Duration::from_secs(60);
+
+pub struct Attract {
+    /// Idle time before a demo starts.
+    after: Duration,
+    /// The player's own settings, with the autopilot on and nothing saved.
+    config: Config,
+    /// The last input on the game over screen, or when it showed up.
+    idle_since: Option<Duration>,
+    demo: Option<(Box<GameState>, Duration)>,
+}
+
+impl Attract {
+    pub fn new(after: Duration, config: Config) -> Self {
+        Attract {
+            after,
+            config: Config {
+                autopilot: true,
+                record: None,
+                hashlog: None,
+                record_gif: None,
+                replay: None,
+                practice: false,
+                resume: false,
+                edit: None,
+                splits: None,
+                host: None,
+                join: None,
+                attract: None,
+                ..config
+            },
+            idle_since: None,
+            demo: None,
+        }
+    }
+
+    /// Runs the demo while the game over screen is up, starting one once it's been idle long
+    /// enough. Returns whether a demo is playing.
+    pub fn update(&mut self, ctx: &mut Context, text_style: &TextStyle) -> GameResult<bool> {
+        let now = ctx.time.time_since_start();
+        let idle_since = *self.idle_since.get_or_insert(now);
+        if let Some((_, started)) = &self.demo {
+            if now - *started >= DEMO_LENGTH {
+                self.stop(now);
+            }
+        } else if now - idle_since >= self.after {
+            let mut demo = GameState::new_headless(self.config.clone())?;
+            demo.text_style = text_style.clone();
+            log::info!("event=attract_demo seed={}", demo.seed);
+            self.demo = Some((Box::new(demo), now));
+        }
+        let Some((demo, _)) = &mut self.demo else {
+            return Ok(false);
+        };
+        demo.update(ctx)?;
+        Ok(true)
+    }
+
+    /// Input on the game over screen: stops the demo if one's playing, returning whether it
+    /// was, and starts the idle time over.
+    pub fn interrupt(&mut self, now: Duration) -> bool {
+        let stopped = self.demo.is_some();
+        self.stop(now);
+        stopped
+    }
+
+    fn stop(&mut self, now: Duration) {
+        self.demo = None;
+        self.idle_since = Some(now);
+    }
+
+    pub fn demo_mut(&mut self) -> Option<&mut GameState> {
+        self.demo.as_mut().map(|(demo, _)| &mut **demo)
+    }
+
+    /// The banner over the demo, in window coordinates.
+    pub fn draw_banner(canvas: &mut graphics::Canvas, style: &TextStyle, screen: graphics::Rect) {
+        canvas.set_screen_coordinates(screen);
+        let mut banner = style.text("DEMO - press any key", text::DEFAULT_SIZE * 1.5);
+        banner.set_layout(graphics::TextLayout {
+            h_align: graphics::TextAlign::Middle,
+            v_align: graphics::TextAlign::End,
+        });
+        canvas.draw(
+            &banner,
+            graphics::DrawParam::new()
+                .dest(Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 - 16.0))
+                .color(Color::WHITE),
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! What `cargo bench` times the snake's body storage back ends with, see `benches/body.rs`.
+//! Each one drives a long snake around the autopilot's cycle, which exercises the push/pop at
+//! the ends as well as the collision scans over the whole body.
+
+use crate::body::{Body, RingBuffer};
+use crate::{ai, Direction, Food, GridPosition, Rules, Segment, Snake, WrapMode, GRID_SIZE};
+use std::collections::{HashSet, VecDeque};
+use std::hint::black_box;
+
+/// How long the snake grows before the timing starts, leaving some room on the grid.
+pub const LENGTH: usize = (GRID_SIZE.0 as usize * GRID_SIZE.1 as usize) * 5 / 6;
+
+/// Which way the snake's body is stored.
+#[derive(Clone, Copy, Debug)]
+pub enum Backend {
+    VecDeque,
+    RingBuffer,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::VecDeque, Backend::RingBuffer];
+}
+
+enum Grown {
+    VecDeque(Snake<VecDeque<Segment>>),
+    RingBuffer(Snake<RingBuffer>),
+}
+
+/// A snake grown to `LENGTH` and ready to be timed.
+pub struct BenchSnake {
+    snake: Grown,
+    obstacles: HashSet<GridPosition>,
+}
+
+impl BenchSnake {
+    pub fn new(backend: Backend) -> Self {
+        let snake = match backend {
+            Backend::VecDeque => Grown::VecDeque(grown()),
+            Backend::RingBuffer => Grown::RingBuffer(grown()),
+        };
+        BenchSnake {
+            snake,
+            obstacles: HashSet::new(),
+        }
+    }
+
+    /// One headless update, steering along the autopilot's cycle.
+    pub fn update(&mut self) {
+        match &mut self.snake {
+            Grown::VecDeque(snake) => update(snake, &self.obstacles),
+            Grown::RingBuffer(snake) => update(snake, &self.obstacles),
+        }
+    }
+}
+
+fn steer<B: Body>(snake: &mut Snake<B>) {
+    if let Some(dir) = ai::next_direction(snake.head.pos) {
+        snake.steer(dir, Rules::default());
+    }
+}
+
+fn grown<B: Body>() -> Snake<B> {
+    let obstacles = HashSet::new();
+    let mut snake = Snake::new(
+        GridPosition::new(1, 0),
+        Direction::Right,
+        WrapMode::Wrap,
+        None,
+    );
+    // Grow by putting the food right in front of the head every tick.
+    while snake.len() < LENGTH {
+        steer(&mut snake);
+        let ahead = GridPosition::new_from_move(snake.head.pos, snake.dir);
+        snake.update(
+            Some(&Food::new(ahead, 0, 0)),
+            &obstacles,
+            Rules::default(),
+            false,
+        );
+    }
+    snake
+}
+
+fn update<B: Body>(snake: &mut Snake<B>, obstacles: &HashSet<GridPosition>) {
+    steer(snake);
+    snake.update(None, obstacles, Rules::default(), false);
+    black_box(snake.ate);
+    assert!(snake.ate.is_none(), "the benchmark snake crashed");
+}
@@ -0,0 +1,101 @@
+//! Storage for the snake's body. Every tick pushes the old head onto the front and, unless the
+//! snake grew, drops the tail, so both ends need to be cheap.
+
+use crate::{GridPosition, Segment};
+use std::collections::VecDeque;
+
+pub trait Body: Clone {
+    /// An empty body with room for at least `capacity` segments.
+    fn with_capacity(capacity: usize) -> Self;
+    fn push_front(&mut self, seg: Segment);
+    fn pop_front(&mut self) -> Option<Segment>;
+    fn pop_back(&mut self) -> Option<Segment>;
+    fn len(&self) -> usize;
+    /// From just behind the head to the tail.
+    fn iter(&self) -> impl Iterator<Item = &Segment>;
+}
+
+impl Body for VecDeque<Segment> {
+    fn with_capacity(capacity: usize) -> Self {
+        VecDeque::with_capacity(capacity)
+    }
+
+    fn push_front(&mut self, seg: Segment) {
+        VecDeque::push_front(self, seg);
+    }
+
+    fn pop_front(&mut self) -> Option<Segment> {
+        VecDeque::pop_front(self)
+    }
+
+    fn pop_back(&mut self) -> Option<Segment> {
+        VecDeque::pop_back(self)
+    }
+
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Segment> {
+        VecDeque::iter(self)
+    }
+}
+
+/// A fixed-capacity ring buffer. The front moves backwards through the slots as the snake
+/// advances, so nothing is ever shifted or reallocated once it's created. It's what snakes
+/// use unless told otherwise, having come out ahead of `VecDeque` in `cargo bench`.
+#[derive(Clone)]
+pub struct RingBuffer {
+    /// Slots outside the live range hold stale segments that are never read.
+    slots: Vec<Segment>,
+    front: usize,
+    len: usize,
+}
+
+impl Body for RingBuffer {
+    fn with_capacity(capacity: usize) -> Self {
+        RingBuffer {
+            slots: vec![Segment::new(GridPosition::new(0, 0)); capacity.max(1)],
+            front: 0,
+            len: 0,
+        }
+    }
+
+    /// Panics when full; the capacity is meant to cover every cell of the grid.
+    fn push_front(&mut self, seg: Segment) {
+        assert!(self.len < self.slots.len(), "the ring buffer is full");
+        self.front = (self.front + self.slots.len() - 1) % self.slots.len();
+        self.slots[self.front] = seg;
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<Segment> {
+        if self.len == 0 {
+            return None;
+        }
+        let seg = self.slots[self.front];
+        self.front = (self.front + 1) % self.slots.len();
+        self.len -= 1;
+        Some(seg)
+    }
+
+    fn pop_back(&mut self) -> Option<Segment> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.slots[(self.front + self.len) % self.slots.len()])
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Segment> {
+        // The live slots run from `front` to the end of the buffer, then wrap around to the start.
+        let first = self.len.min(self.slots.len() - self.front);
+        self.slots[self.front..self.front + first]
+            .iter()
+            .chain(&self.slots[..self.len - first])
+    }
+}
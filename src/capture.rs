@@ -0,0 +1,135 @@
+//! `--record-gif FILE`: captures the game as it's played and writes it out as an animated
+//! GIF when the window closes.
+//!
+//! Every `FRAME_INTERVAL` the scene is drawn a second time into a small offscreen image.
+//! Its pixels are read back on the next frame, once the GPU has got round to drawing it, and
+//! handed to a thread of its own that does the slow part, picking each frame's palette and
+//! compressing it, so recording doesn't make the game stutter.
+
+use ggez::graphics::{self, Image, ImageFormat};
+use ggez::{Context, GameError, GameResult};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The size frames are captured at, a fraction of the window to keep the file small. Read
+/// backs need rows that are a multiple of 256 bytes, so the width is a multiple of 64.
+const FRAME_SIZE: (u32, u32) = (384, 256);
+/// Time between captured frames, a step of the GIF's hundredths of a second.
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+/// Frames past this many aren't captured, a minute at `FRAME_INTERVAL`.
+const MAX_FRAMES: u32 = 600;
+/// Frames waiting for the encoder. If it falls this far behind, new frames are dropped.
+const QUEUE_LENGTH: usize = 16;
+/// How hard the encoder works on each frame's palette, from 1 (best) to 30 (fastest).
+const QUANTIZE_SPEED: i32 = 10;
+
+pub struct GifRecorder {
+    target: Image,
+    frames: Option<SyncSender<Vec<u8>>>,
+    encoder: Option<JoinHandle<()>>,
+    last_capture: Option<Duration>,
+    /// Set once a frame has been drawn into `target` and not read back yet.
+    pending: bool,
+    captured: u32,
+}
+
+impl GifRecorder {
+    /// Creates `path` and starts the thread that writes frames to it.
+    pub fn new(ctx: &Context, path: &Path) -> GameResult<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let (width, height) = FRAME_SIZE;
+        let mut encoder =
+            gif::Encoder::new(file, width as u16, height as u16, &[]).map_err(write_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(write_error)?;
+        let (frames, queue) = mpsc::sync_channel::<Vec<u8>>(QUEUE_LENGTH);
+        let encoder = thread::spawn(move || {
+            for mut pixels in queue {
+                let mut frame = gif::Frame::from_rgba_speed(
+                    width as u16,
+                    height as u16,
+                    &mut pixels,
+                    QUANTIZE_SPEED,
+                );
+                frame.delay = (FRAME_INTERVAL.as_millis() / 10) as u16;
+                if let Err(e) = encoder.write_frame(&frame) {
+                    log::warn!("event=gif_write_failed error={e}");
+                    return;
+                }
+            }
+        });
+        Ok(GifRecorder {
+            target: Image::new_canvas_image(ctx, ImageFormat::Rgba8UnormSrgb, width, height, 1),
+            frames: Some(frames),
+            encoder: Some(encoder),
+            last_capture: None,
+            pending: false,
+            captured: 0,
+        })
+    }
+
+    /// Hands the frame drawn into the target last time over to the encoder.
+    pub fn collect(&mut self, ctx: &Context) -> GameResult {
+        if !std::mem::take(&mut self.pending) {
+            return Ok(());
+        }
+        let Some(frames) = &self.frames else {
+            return Ok(());
+        };
+        match frames.try_send(self.target.to_pixels(ctx)?) {
+            Ok(()) => self.captured += 1,
+            Err(TrySendError::Full(_)) => log::debug!("event=gif_frame_dropped"),
+            Err(TrySendError::Disconnected(_)) => self.frames = None,
+        }
+        if self.captured == MAX_FRAMES {
+            log::warn!("event=gif_limit_reached frames={MAX_FRAMES}");
+            eprintln!(
+                "The GIF has reached its limit of {MAX_FRAMES} frames, the rest of the game \
+                 won't be in it."
+            );
+            self.frames = None;
+        }
+        Ok(())
+    }
+
+    /// A canvas drawing into the next frame, if it's time to capture one.
+    pub fn capture(&mut self, ctx: &Context, now: Duration) -> Option<graphics::Canvas> {
+        if self.frames.is_none()
+            || self
+                .last_capture
+                .is_some_and(|at| now - at < FRAME_INTERVAL)
+        {
+            return None;
+        }
+        self.last_capture = Some(now);
+        self.pending = true;
+        Some(graphics::Canvas::from_image(
+            ctx,
+            self.target.clone(),
+            graphics::Color::BLACK,
+        ))
+    }
+
+    /// Collects the last frame and waits for the encoder to write everything out.
+    pub fn finish(&mut self, ctx: &Context) {
+        if let Err(e) = self.collect(ctx) {
+            log::warn!("event=gif_capture_failed error={e}");
+        }
+        self.frames = None;
+        if let Some(encoder) = self.encoder.take() {
+            if encoder.join().is_err() {
+                log::warn!("event=gif_write_failed");
+            }
+        }
+        log::info!("event=gif_saved frames={}", self.captured);
+    }
+}
+
+fn write_error(e: gif::EncodingError) -> GameError {
+    GameError::CustomError(format!("can't write the GIF: {e}"))
+}
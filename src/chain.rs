@@ -0,0 +1,72 @@
+//! `--food-chain N`: the food comes in numbered chains of `N` pellets, all on the board at
+//! once, that have to be eaten in order.
+//!
+//! The lowest numbered pellet is the game's ordinary food, so it moves, decays and scores as
+//! usual. The rest wait their turn here. Eating all of a chain is worth a bonus, and a new
+//! chain is laid out. Running into a pellet before its turn costs points and breaks the
+//! chain, which is laid out again from scratch.
+
+use crate::text::{self, TextStyle};
+use crate::{CellPainter, GridPosition};
+use ggez::graphics::{self, Color};
+use std::collections::VecDeque;
+
+/// Points for finishing a chain, for each pellet in it.
+pub const BONUS_PER_PELLET: u32 = 2;
+/// Points lost for eating out of order.
+pub const PENALTY: u32 = 3;
+
+#[derive(Clone, Debug)]
+pub struct FoodChain {
+    /// Pellets in a full chain.
+    pub length: u32,
+    /// The number of the pellet that's the food right now, from 1.
+    pub number: u32,
+    /// Where the pellets after it are, in order.
+    pub upcoming: VecDeque<GridPosition>,
+}
+
+impl FoodChain {
+    pub fn new(length: u32) -> Self {
+        FoodChain {
+            length,
+            number: 1,
+            upcoming: VecDeque::new(),
+        }
+    }
+
+    pub fn bonus(&self) -> u32 {
+        self.length * BONUS_PER_PELLET
+    }
+
+    /// The pellets waiting their turn, dimmed, then every pellet's number, the food's
+    /// included.
+    pub fn draw(
+        &self,
+        canvas: &mut graphics::Canvas,
+        painter: &CellPainter,
+        style: &TextStyle,
+        food: Option<GridPosition>,
+    ) {
+        for &pos in &self.upcoming {
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(painter.inset(pos.into()))
+                    .color([0.0, 0.0, 1.0, 0.45]),
+            );
+        }
+        let numbered = food.into_iter().chain(self.upcoming.iter().copied());
+        for (i, pos) in numbered.enumerate() {
+            let mut label = style.text((self.number + i as u32).to_string(), text::DEFAULT_SIZE);
+            label.set_layout(graphics::TextLayout::center());
+            let rect: graphics::Rect = pos.into();
+            canvas.draw(
+                &label,
+                graphics::DrawParam::new()
+                    .dest(rect.center())
+                    .color(Color::WHITE),
+            );
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! `--combo SECS`: food eaten within `SECS` of the last piece is worth more, one more time
+//! its usual points for each piece in the streak, up to `MAX_MULTIPLIER`. Taking too long
+//! starts the streak over.
+//!
+//! Like gravity, the window is counted in ticks, `SECS` at the starting speed, so replays and
+//! saves see the same streaks.
+
+/// The most a streak multiplies the points by.
+pub const MAX_MULTIPLIER: u32 = 5;
+/// How much higher the eating sound goes for each step up in the multiplier.
+const PITCH_PER_STEP: f32 = 0.08;
+
+#[derive(Clone, Debug)]
+pub struct Combo {
+    /// Ticks after eating that the next piece keeps the streak going.
+    pub window: u32,
+    pub ticks_left: u32,
+    /// Pieces eaten in a row within the window, not counting the first.
+    pub streak: u32,
+}
+
+impl Combo {
+    pub fn new(window: u32) -> Self {
+        Combo {
+            window,
+            ticks_left: 0,
+            streak: 0,
+        }
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak).min(MAX_MULTIPLIER)
+    }
+
+    /// Whether a streak is going, with something to lose.
+    pub fn active(&self) -> bool {
+        self.streak > 0 && self.ticks_left > 0
+    }
+
+    /// Counts down a tick, ending the streak once the window lapses.
+    pub fn tick(&mut self) {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        if self.ticks_left == 0 {
+            self.streak = 0;
+        }
+    }
+
+    /// A piece of food was eaten: keeps the streak going if it was in time, opens the window
+    /// for the next piece, and returns what its points are multiplied by.
+    pub fn eat(&mut self) -> u32 {
+        if self.ticks_left > 0 {
+            self.streak += 1;
+        }
+        self.ticks_left = self.window;
+        self.multiplier()
+    }
+
+    /// How much of the window is left, from `1.0` right after eating to `0.0`.
+    pub fn fraction_left(&self) -> f32 {
+        self.ticks_left as f32 / self.window as f32
+    }
+
+    /// The pitch for the eating sound, rising with the multiplier.
+    pub fn pitch(&self) -> f32 {
+        1.0 + (self.multiplier() - 1) as f32 * PITCH_PER_STEP
+    }
+}
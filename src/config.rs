@@ -0,0 +1,894 @@
+use crate::daily::Date;
+use crate::easing::Easing;
+use crate::render::{self, RenderStyle};
+use crate::sound::{EatCue, SoundOverlap};
+use crate::swarm;
+use crate::transition::TransitionStyle;
+use crate::{
+    Direction, EventPriority, FoodBias, InputMode, Scoring, SpeedCurve, TurnDrain, WrapMode,
+    DESIRED_FPS, MAX_UPDATES_PER_FRAME,
+};
+use ggez::{GameError, GameResult};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// A window size in logical pixels, written `WIDTHxHEIGHT`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl FromStr for WindowSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.parse::<f32>().ok()?, h.parse::<f32>().ok()?)));
+        match parsed {
+            Some((width, height)) if width >= 1.0 && height >= 1.0 => {
+                Ok(WindowSize { width, height })
+            }
+            _ => Err(format!(
+                "invalid window size `{s}`, expected e.g. `1280x720`"
+            )),
+        }
+    }
+}
+
+/// Tunable settings for a game, read from the command line.
+/// Every option defaults to the classic behavior.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Logical updates per second.
+    pub speed: u32,
+    /// How the speed picks up as the snake grows, starting from `speed`.
+    pub speed_curve: SpeedCurve,
+    /// Slow down as the snake fills the board, on top of the speed curve.
+    pub assist_slow: bool,
+    /// The most logical updates run in a single frame when catching up after a hitch.
+    pub max_updates_per_frame: u32,
+    /// Whether the snake wraps around the edges of the grid or dies on them.
+    pub wrap_mode: WrapMode,
+    /// In walls mode, turn along a wall instead of running into it.
+    pub wall_assist: bool,
+    /// In wrap mode, ticks after wrapping around an edge during which wrapping again is as
+    /// deadly as a wall. `0` never stops the snake from wrapping.
+    pub wrap_cooldown: u32,
+    /// Which way the snake faces at the start. Without one it faces right, unless that doesn't
+    /// leave it enough room.
+    pub start_direction: Option<Direction>,
+    /// Moves after eating during which the snake can pass through itself. `0` never lets it.
+    pub grace_period: u32,
+    /// Turn "down" a quarter turn every this many seconds, pulling the snake that way whenever
+    /// it's left alone for a moment.
+    pub gravity: Option<f32>,
+    /// Multiply the points for food eaten within this many seconds of the last piece.
+    pub combo: Option<f32>,
+    /// Turn the board along with gravity, so down is always the bottom of the window.
+    pub rotate_view: bool,
+    /// How many times the snake can die, coming back somewhere safe each time but the last.
+    pub lives: Option<u32>,
+    /// Running into the body costs this many points instead of the game, as long as there are
+    /// enough to pay.
+    pub self_penalty: Option<u32>,
+    /// How many segments come off the tail along with the points, with `self_penalty`.
+    pub self_penalty_segments: u32,
+    /// Steer this many snakes at once, all turning together.
+    pub swarm: Option<usize>,
+    /// Don't ignore turning straight back: the snake runs into its own neck and dies.
+    pub reverse_kills: bool,
+    /// Whether eating or running into the body wins when a move does both at once.
+    pub event_priority: EventPriority,
+    /// Let the snake turn around with `R`, swapping its head and tail.
+    pub reversible: bool,
+    /// Let the snake bite this many segments off its tail with `T`, for a point each.
+    pub tail_bite: Option<u32>,
+    /// Add a reflection of the snake that has to be avoided just like the snake itself.
+    pub mirror: bool,
+    /// Also allow moving diagonally, steered with Q/E/Z/C.
+    pub diagonals: bool,
+    /// How many ticks pass between each step of the food. `0` keeps it stationary.
+    pub food_move_interval: u32,
+    /// Ticks a piece of food lasts before it turns into an obstacle. `0` never decays.
+    pub food_decay: u32,
+    /// Ticks food takes to shrink from several times its usual worth down to a plain pellet,
+    /// to reward getting to it quickly. `0` doesn't shrink it.
+    pub shrinking_food: u32,
+    /// Ticks without any food on the board after each piece is eaten.
+    pub food_respawn_delay: u32,
+    /// The chance, from `0` to `1`, that a piece of food is yellow bonus food, worth more
+    /// points but not making the snake grow.
+    pub bonus_food: f32,
+    /// The chance, from `0` to `1`, that a piece of food that isn't bonus food is a pink power
+    /// pellet, letting the snake eat through its own body for a few moves.
+    pub power_pellets: f32,
+    /// The chance, from `0` to `1`, that a piece of food that's none of the above is a cyan
+    /// rewind pellet, taking the snake back a few moves.
+    pub rewind_pellets: f32,
+    /// Whether food tends to show up near the head, far from it, or anywhere.
+    pub food_bias: FoodBias,
+    /// Put the food out in numbered chains of this many pellets, to be eaten in order.
+    pub food_chain: Option<u32>,
+    /// A file of obstacles to play around, as saved by `--edit`.
+    pub obstacles: Option<PathBuf>,
+    /// Lay out obstacles and save them to this file, instead of playing.
+    pub edit: Option<PathBuf>,
+    /// A file listing where the food appears, in order, before it goes back to random spots.
+    pub food_sequence: Option<PathBuf>,
+    /// A board to start from, and go back to on dying, instead of a fresh one, see `scenario`.
+    pub scenario: Option<PathBuf>,
+    /// How many points each piece of food is worth.
+    pub scoring: Scoring,
+    /// Flash the snake when its head passes right by its own body.
+    pub near_miss: bool,
+    /// Points awarded for each near miss.
+    pub near_miss_bonus: u32,
+    /// The snake stops growing once it's this long, though food still counts.
+    pub max_length: Option<usize>,
+    /// Start with the danger zone assist on, which flashes body segments just ahead of the head.
+    pub danger_zone: bool,
+    /// Start with a checkerboard background, which makes distances easier to read.
+    pub checkerboard: bool,
+    /// Send pulses of color down the body, moving this many segments a tick.
+    pub pulse: Option<f32>,
+    /// Sway the body from side to side as it goes, this many cells either way at most.
+    pub wobble: Option<f32>,
+    /// How many times a second the body sways with `wobble`.
+    pub wobble_hz: f32,
+    /// Magnify the cells around the head like a fisheye lens, from `0` to `1` for the most.
+    pub warp: Option<f32>,
+    /// Swell the head to this many times its size and back whenever it eats.
+    pub head_pulse: Option<f32>,
+    /// How long the swelling with `head_pulse` takes, in milliseconds.
+    pub head_pulse_ms: u32,
+    /// Lengths to celebrate the snake reaching, in increasing order, or none to not.
+    pub milestones: Vec<usize>,
+    /// Only let the cells within this many moves of the head be seen clearly.
+    pub fog: Option<u32>,
+    /// Hide the body, leaving only the head to see, except for a moment after eating.
+    pub invisible: bool,
+    /// Slide the snake smoothly from cell to cell instead of jumping a cell each tick.
+    pub interpolate: bool,
+    /// How the sliding with `interpolate` speeds up and slows down over a move.
+    pub easing: Easing,
+    /// Start with the heatmap of where the snake has been on.
+    pub heatmap: bool,
+    /// Start with every this many segments numbered with how far back from the head it is.
+    pub segment_labels: Option<u32>,
+    /// Start with the hint of the shortest way to the food on.
+    pub ghost: bool,
+    /// Have the snake flick its tongue now and then.
+    pub tongue: bool,
+    /// Give the snake eyes that follow the food around.
+    pub eyes: bool,
+    /// Put an arrow on the food that points the shortest way to the snake's head.
+    pub food_arrow: bool,
+    /// Draw streaks behind the head when the game runs faster than normal.
+    pub speed_lines: bool,
+    /// Cycle the background through the time of day, taking this many seconds per day.
+    pub day_cycle: Option<f32>,
+    /// Play a short tone, pitched per direction, whenever the snake turns.
+    pub turn_sounds: bool,
+    /// Loop this sound from the resources, e.g. `/music.ogg`, as background music.
+    pub music: Option<String>,
+    /// Raise the music's pitch as the snake closes in on something it would die on.
+    pub music_tension: bool,
+    /// How far the music dips while the eat sound plays, from `0` (not at all) to `1`.
+    pub duck: f32,
+    /// How long the music takes to come back up after dipping, in milliseconds.
+    pub duck_recovery_ms: u32,
+    /// Whether a second turn within one tick is queued or replaces the first.
+    pub input_mode: InputMode,
+    /// Whether each tick takes the next of the queued turns or skips to the latest.
+    pub turn_drain: TurnDrain,
+    /// How the snake's segments are drawn.
+    pub render_style: RenderStyle,
+    /// Space left around the snake's segments and the food, as a fraction of a cell.
+    pub cell_padding: f32,
+    /// Let the AI play.
+    pub autopilot: bool,
+    /// Have the autopilot fill the board without opening a window, then exit.
+    pub selftest: bool,
+    /// Play this replay back headlessly and check it ends the way it was recorded.
+    pub verify: Option<PathBuf>,
+    /// Fill the board with this many decorative snakes and show the frame time, to see how
+    /// drawing holds up under load. Only for debugging, it plays no part in the game.
+    pub stress: Option<usize>,
+    /// Play in the terminal instead of a window, see `tui`.
+    pub tui: bool,
+    /// Whether eating plays a sound, flashes the screen, or both.
+    pub eat_cue: EatCue,
+    /// What happens when the eat sound is triggered while it's still playing.
+    pub eat_sound_overlap: SoundOverlap,
+    /// Print statistics about past games and exit.
+    pub stats: bool,
+    /// Clear the saved scores (after asking) and exit.
+    pub reset_scores: bool,
+    /// Save the inputs of the game to this file so it can be replayed later.
+    pub record: Option<PathBuf>,
+    /// Have the autopilot play a demo after this many seconds idle on the game over screen.
+    pub attract: Option<f32>,
+    /// Write a hash of the game after every tick to this file, to find where two runs differ.
+    pub hashlog: Option<PathBuf>,
+    /// Capture the game as it's played and write it to this file as an animated GIF.
+    pub record_gif: Option<PathBuf>,
+    /// Watch a previously recorded game instead of playing.
+    pub replay: Option<PathBuf>,
+    /// Step through the replay by hand.
+    pub practice: bool,
+    /// How big the window is. The cells are made as big as fits, and the rest is letterboxed.
+    pub window_size: Option<WindowSize>,
+    /// Samples per pixel for anti-aliasing the edges of the rounded and circle styles.
+    /// Remembered for the next run.
+    pub msaa: Option<u8>,
+    /// How the game switches to and from the game over screen. Remembered for the next run.
+    pub transition: Option<TransitionStyle>,
+    /// How long that takes in milliseconds, `0` switching at once. Remembered for the next run.
+    pub transition_ms: Option<u32>,
+    /// Host a race against another player, who joins on this port.
+    pub host: Option<u16>,
+    /// Join the race hosted at this address.
+    pub join: Option<String>,
+    /// The seed to start the game from instead of a random one. Only ever set by `--host` and
+    /// `--join`, so both sides play the same board, and by `--daily`.
+    pub seed: Option<u64>,
+    /// Play the day's challenge, seeded from this date.
+    pub daily: Option<Date>,
+    /// Run a speedrun timer that takes a split every this many points.
+    pub splits: Option<u32>,
+    /// Carry on with the game that was saved when the last one was quit.
+    pub resume: bool,
+    /// The font for all the text, a file in the resources. Without it the built-in font is used.
+    pub font: String,
+    /// How much bigger (or smaller) to draw all the text.
+    pub text_scale: f32,
+    /// The food's sprite sheet, a file in the resources. Without it the food is a plain square.
+    pub food_sprites: String,
+    /// How many frames of the food's sprite sheet to show a second.
+    pub food_fps: f32,
+    /// Where to load the sounds from, if not one of the usual places.
+    pub resources: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            speed: DESIRED_FPS,
+            speed_curve: SpeedCurve::default(),
+            assist_slow: false,
+            max_updates_per_frame: MAX_UPDATES_PER_FRAME,
+            wrap_mode: WrapMode::default(),
+            wall_assist: false,
+            wrap_cooldown: 0,
+            start_direction: None,
+            grace_period: 0,
+            gravity: None,
+            combo: None,
+            rotate_view: false,
+            lives: None,
+            self_penalty: None,
+            self_penalty_segments: 3,
+            swarm: None,
+            reverse_kills: false,
+            event_priority: EventPriority::CollisionFirst,
+            reversible: false,
+            tail_bite: None,
+            mirror: false,
+            diagonals: false,
+            food_move_interval: 0,
+            food_decay: 0,
+            shrinking_food: 0,
+            food_respawn_delay: 0,
+            bonus_food: 0.0,
+            power_pellets: 0.0,
+            rewind_pellets: 0.0,
+            food_bias: FoodBias::default(),
+            food_chain: None,
+            obstacles: None,
+            edit: None,
+            food_sequence: None,
+            scenario: None,
+            scoring: Scoring::default(),
+            near_miss: false,
+            near_miss_bonus: 0,
+            max_length: None,
+            danger_zone: false,
+            checkerboard: false,
+            pulse: None,
+            wobble: None,
+            wobble_hz: 2.0,
+            warp: None,
+            head_pulse: None,
+            head_pulse_ms: 150,
+            milestones: Vec::new(),
+            fog: None,
+            invisible: false,
+            interpolate: false,
+            easing: Easing::default(),
+            heatmap: false,
+            segment_labels: None,
+            ghost: false,
+            tongue: false,
+            eyes: false,
+            food_arrow: false,
+            speed_lines: false,
+            day_cycle: None,
+            turn_sounds: false,
+            music: None,
+            music_tension: false,
+            duck: 0.5,
+            duck_recovery_ms: 400,
+            input_mode: InputMode::default(),
+            turn_drain: TurnDrain::default(),
+            render_style: RenderStyle::default(),
+            cell_padding: 0.0,
+            autopilot: false,
+            selftest: false,
+            verify: None,
+            stress: None,
+            tui: false,
+            eat_cue: EatCue::default(),
+            eat_sound_overlap: SoundOverlap::default(),
+            stats: false,
+            reset_scores: false,
+            record: None,
+            record_gif: None,
+            hashlog: None,
+            attract: None,
+            replay: None,
+            practice: false,
+            window_size: None,
+            msaa: None,
+            transition: None,
+            transition_ms: None,
+            splits: None,
+            host: None,
+            join: None,
+            seed: None,
+            daily: None,
+            resume: false,
+            font: "font.ttf".to_string(),
+            text_scale: 1.0,
+            food_sprites: "food.png".to_string(),
+            food_fps: 8.0,
+            resources: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> GameResult<Self> {
+        let mut config = Config::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--speed" => config.speed = parse_value(&arg, args.next())?,
+                "--food-bias" => config.food_bias = parse_value(&arg, args.next())?,
+                "--speed-curve" => config.speed_curve = parse_value(&arg, args.next())?,
+                "--assist-slow" => config.assist_slow = true,
+                "--max-updates-per-frame" => {
+                    config.max_updates_per_frame = parse_value(&arg, args.next())?;
+                    if config.max_updates_per_frame == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--max-updates-per-frame` must be at least 1".to_string(),
+                        ));
+                    }
+                }
+                "--wrap-mode" => config.wrap_mode = parse_value(&arg, args.next())?,
+                "--wall-assist" => config.wall_assist = true,
+                "--wrap-cooldown" => config.wrap_cooldown = parse_value(&arg, args.next())?,
+                "--diagonals" => config.diagonals = true,
+                "--mirror" => config.mirror = true,
+                "--reversible" => config.reversible = true,
+                "--tail-bite" => {
+                    let segments = parse_value(&arg, args.next())?;
+                    if segments == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--tail-bite` needs to bite off at least 1 segment".to_string(),
+                        ));
+                    }
+                    config.tail_bite = Some(segments);
+                }
+                "--reverse-kills" => config.reverse_kills = true,
+                "--event-priority" => config.event_priority = parse_value(&arg, args.next())?,
+                "--grace-period" => config.grace_period = parse_value(&arg, args.next())?,
+                "--gravity" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if !(seconds > 0.0 && seconds.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--gravity` must be a positive number of seconds".to_string(),
+                        ));
+                    }
+                    config.gravity = Some(seconds);
+                }
+                "--rotate-view" => config.rotate_view = true,
+                "--combo" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if !(seconds > 0.0 && seconds.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--combo` must be a positive number of seconds".to_string(),
+                        ));
+                    }
+                    config.combo = Some(seconds);
+                }
+                "--attract" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if !(seconds > 0.0 && seconds.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--attract` must be a positive number of seconds".to_string(),
+                        ));
+                    }
+                    config.attract = Some(seconds);
+                }
+                "--lives" => {
+                    let lives = parse_value(&arg, args.next())?;
+                    if lives == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--lives` needs at least 1 life".to_string(),
+                        ));
+                    }
+                    config.lives = Some(lives);
+                }
+                "--self-penalty" => config.self_penalty = Some(parse_value(&arg, args.next())?),
+                "--self-penalty-segments" => {
+                    config.self_penalty_segments = parse_value(&arg, args.next())?
+                }
+                "--swarm" => {
+                    let count = parse_value(&arg, args.next())?;
+                    if !(2..=swarm::MAX_SNAKES).contains(&count) {
+                        return Err(GameError::ConfigError(format!(
+                            "`--swarm` needs between 2 and {} snakes",
+                            swarm::MAX_SNAKES
+                        )));
+                    }
+                    config.swarm = Some(count);
+                }
+                "--start-direction" => {
+                    config.start_direction = Some(parse_value(&arg, args.next())?)
+                }
+                "--food-move-interval" => {
+                    config.food_move_interval = parse_value(&arg, args.next())?
+                }
+                "--food-decay" => config.food_decay = parse_value(&arg, args.next())?,
+                "--shrinking-food" => config.shrinking_food = parse_value(&arg, args.next())?,
+                "--food-respawn-delay" => {
+                    config.food_respawn_delay = parse_value(&arg, args.next())?
+                }
+                "--food-sequence" => config.food_sequence = Some(parse_value(&arg, args.next())?),
+                "--scoring" => config.scoring = parse_value(&arg, args.next())?,
+                "--near-miss" => config.near_miss = true,
+                "--near-miss-bonus" => {
+                    config.near_miss = true;
+                    config.near_miss_bonus = parse_value(&arg, args.next())?
+                }
+                "--max-length" => config.max_length = Some(parse_value(&arg, args.next())?),
+                "--danger-zone" => config.danger_zone = true,
+                "--checkerboard" => config.checkerboard = true,
+                "--heatmap" => config.heatmap = true,
+                "--segment-labels" => {
+                    let every = parse_value(&arg, args.next())?;
+                    if every == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--segment-labels` needs to number every 1 or more segments"
+                                .to_string(),
+                        ));
+                    }
+                    config.segment_labels = Some(every);
+                }
+                "--ghost" => config.ghost = true,
+                "--pulse" => config.pulse = Some(parse_value(&arg, args.next())?),
+                "--wobble" => {
+                    let amplitude: f32 = parse_value(&arg, args.next())?;
+                    if !(amplitude > 0.0 && amplitude <= 0.5) {
+                        return Err(GameError::ConfigError(
+                            "`--wobble` must be more than 0 and at most 0.5".to_string(),
+                        ));
+                    }
+                    config.wobble = Some(amplitude);
+                }
+                "--wobble-hz" => {
+                    config.wobble_hz = parse_value(&arg, args.next())?;
+                    if !(config.wobble_hz > 0.0 && config.wobble_hz.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--wobble-hz` must be more than 0".to_string(),
+                        ));
+                    }
+                }
+                "--warp" => {
+                    let strength = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&strength) {
+                        return Err(GameError::ConfigError(
+                            "`--warp` must be between 0 and 1".to_string(),
+                        ));
+                    }
+                    config.warp = Some(strength);
+                }
+                "--head-pulse" => {
+                    let scale: f32 = parse_value(&arg, args.next())?;
+                    if scale < 1.0 {
+                        return Err(GameError::ConfigError(
+                            "`--head-pulse` must be at least 1".to_string(),
+                        ));
+                    }
+                    config.head_pulse = Some(scale);
+                }
+                "--head-pulse-ms" => config.head_pulse_ms = parse_value(&arg, args.next())?,
+                "--milestones" => {
+                    let list: String = parse_value(&arg, args.next())?;
+                    let lengths: Option<Vec<usize>> = list
+                        .split(',')
+                        .map(|length| length.trim().parse().ok())
+                        .collect();
+                    match lengths {
+                        Some(lengths)
+                            if lengths.windows(2).all(|pair| pair[0] < pair[1])
+                                && lengths.first().is_some_and(|&first| first > 0) =>
+                        {
+                            config.milestones = lengths;
+                        }
+                        _ => {
+                            return Err(GameError::ConfigError(format!(
+                                "invalid value `{list}` for `--milestones`, expected increasing \
+                                 lengths like `10,25,50`"
+                            )))
+                        }
+                    }
+                }
+                "--fog" => config.fog = Some(parse_value(&arg, args.next())?),
+                "--invisible" => config.invisible = true,
+                "--interpolate" => config.interpolate = true,
+                "--easing" => config.easing = parse_value(&arg, args.next())?,
+                "--tongue" => config.tongue = true,
+                "--eyes" => config.eyes = true,
+                "--food-arrow" => config.food_arrow = true,
+                "--speed-lines" => config.speed_lines = true,
+                "--day-cycle" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if !(seconds > 0.0 && seconds.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--day-cycle` must be a positive number of seconds".to_string(),
+                        ));
+                    }
+                    config.day_cycle = Some(seconds);
+                }
+                "--turn-sounds" => config.turn_sounds = true,
+                "--music" => config.music = Some(parse_value(&arg, args.next())?),
+                "--music-tension" => config.music_tension = true,
+                "--duck" => {
+                    config.duck = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&config.duck) {
+                        return Err(GameError::ConfigError(
+                            "`--duck` must be between 0 and 1".to_string(),
+                        ));
+                    }
+                }
+                "--duck-recovery-ms" => config.duck_recovery_ms = parse_value(&arg, args.next())?,
+                "--input-mode" => config.input_mode = parse_value(&arg, args.next())?,
+                "--turn-drain" => config.turn_drain = parse_value(&arg, args.next())?,
+                "--render-style" => config.render_style = parse_value(&arg, args.next())?,
+                "--cell-padding" => {
+                    config.cell_padding = parse_value(&arg, args.next())?;
+                    if !(0.0..0.5).contains(&config.cell_padding) {
+                        return Err(GameError::ConfigError(
+                            "`--cell-padding` must be at least 0 and less than 0.5".to_string(),
+                        ));
+                    }
+                }
+                "--autopilot" => config.autopilot = true,
+                "--selftest" => config.selftest = true,
+                "--verify" => config.verify = Some(parse_value(&arg, args.next())?),
+                "--tui" => config.tui = true,
+                "--stress" => {
+                    let count: usize = parse_value(&arg, args.next())?;
+                    if count == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--stress` needs at least one snake".to_string(),
+                        ));
+                    }
+                    config.stress = Some(count);
+                }
+                "--eat-sound" => config.eat_sound_overlap = parse_value(&arg, args.next())?,
+                "--eat-cue" => config.eat_cue = parse_value(&arg, args.next())?,
+                "--stats" => config.stats = true,
+                "--reset-scores" => config.reset_scores = true,
+                "--record" => config.record = Some(parse_value(&arg, args.next())?),
+                "--daily" => config.daily = Some(Date::today()),
+                "--hashlog" => config.hashlog = Some(parse_value(&arg, args.next())?),
+                "--record-gif" => config.record_gif = Some(parse_value(&arg, args.next())?),
+                "--replay" => config.replay = Some(parse_value(&arg, args.next())?),
+                "--practice" => config.practice = true,
+                "--resume" => config.resume = true,
+                "--splits" => {
+                    let milestone = parse_value(&arg, args.next())?;
+                    if milestone == 0 {
+                        return Err(GameError::ConfigError(
+                            "`--splits` needs at least 1 point between splits".to_string(),
+                        ));
+                    }
+                    config.splits = Some(milestone);
+                }
+                "--host" => config.host = Some(parse_value(&arg, args.next())?),
+                "--join" => config.join = Some(parse_value(&arg, args.next())?),
+                "--window-size" => config.window_size = Some(parse_value(&arg, args.next())?),
+                "--msaa" => {
+                    let samples = parse_value(&arg, args.next())?;
+                    if !render::MSAA_COUNTS.contains(&samples) {
+                        return Err(GameError::ConfigError(format!(
+                            "`--msaa` must be one of {:?}",
+                            render::MSAA_COUNTS
+                        )));
+                    }
+                    config.msaa = Some(samples);
+                }
+                "--transition" => config.transition = Some(parse_value(&arg, args.next())?),
+                "--transition-ms" => config.transition_ms = Some(parse_value(&arg, args.next())?),
+                "--resources" => config.resources = Some(parse_value(&arg, args.next())?),
+                "--font" => config.font = parse_value(&arg, args.next())?,
+                "--obstacles" => config.obstacles = Some(parse_value(&arg, args.next())?),
+                "--edit" => config.edit = Some(parse_value(&arg, args.next())?),
+                "--scenario" => config.scenario = Some(parse_value(&arg, args.next())?),
+                "--bonus-food" => {
+                    config.bonus_food = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&config.bonus_food) {
+                        return Err(GameError::ConfigError(
+                            "`--bonus-food` must be between 0 and 1".to_string(),
+                        ));
+                    }
+                }
+                "--power-pellets" => {
+                    config.power_pellets = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&config.power_pellets) {
+                        return Err(GameError::ConfigError(
+                            "`--power-pellets` must be between 0 and 1".to_string(),
+                        ));
+                    }
+                }
+                "--rewind-pellets" => {
+                    config.rewind_pellets = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&config.rewind_pellets) {
+                        return Err(GameError::ConfigError(
+                            "`--rewind-pellets` must be between 0 and 1".to_string(),
+                        ));
+                    }
+                }
+                "--food-chain" => {
+                    let length = parse_value(&arg, args.next())?;
+                    if length < 2 {
+                        return Err(GameError::ConfigError(
+                            "`--food-chain` needs at least 2 pellets to a chain".to_string(),
+                        ));
+                    }
+                    config.food_chain = Some(length);
+                }
+                "--text-scale" => {
+                    config.text_scale = parse_value(&arg, args.next())?;
+                    if config.text_scale.is_nan() || config.text_scale <= 0.0 {
+                        return Err(GameError::ConfigError(
+                            "`--text-scale` must be more than 0".to_string(),
+                        ));
+                    }
+                }
+                "--food-sprites" => config.food_sprites = parse_value(&arg, args.next())?,
+                "--food-fps" => {
+                    config.food_fps = parse_value(&arg, args.next())?;
+                    if !(config.food_fps >= 0.0 && config.food_fps.is_finite()) {
+                        return Err(GameError::ConfigError(
+                            "`--food-fps` must be 0 or more".to_string(),
+                        ));
+                    }
+                }
+                _ => return Err(GameError::ConfigError(format!("unknown argument `{arg}`"))),
+            }
+        }
+        if config.practice && config.replay.is_none() {
+            return Err(GameError::ConfigError(
+                "`--practice` needs a game to step through, pass one with `--replay`".to_string(),
+            ));
+        }
+        if config.resume && (config.record.is_some() || config.replay.is_some()) {
+            return Err(GameError::ConfigError(
+                "a resumed game can't be recorded or replayed".to_string(),
+            ));
+        }
+        if config.host.is_some() || config.join.is_some() {
+            if config.host.is_some() && config.join.is_some() {
+                return Err(GameError::ConfigError(
+                    "`--host` and `--join` can't be used together".to_string(),
+                ));
+            }
+            if config.replay.is_some()
+                || config.resume
+                || config.autopilot
+                || config.reversible
+                || config.tail_bite.is_some()
+            {
+                return Err(GameError::ConfigError(
+                    "only plain games can be played over the network, so `--host` and `--join` \
+                     can't be combined with `--replay`, `--resume`, `--autopilot`, \
+                     `--reversible` or `--tail-bite`"
+                        .to_string(),
+                ));
+            }
+        }
+        if config.verify.is_some()
+            && (config.replay.is_some()
+                || config.record.is_some()
+                || config.resume
+                || config.host.is_some()
+                || config.join.is_some())
+        {
+            return Err(GameError::ConfigError(
+                "`--verify` plays the replay by itself, so it can't be combined with \
+                 `--replay`, `--record`, `--resume`, `--host` or `--join`"
+                    .to_string(),
+            ));
+        }
+        if let Some(date) = config.daily {
+            if config.replay.is_some()
+                || config.resume
+                || config.host.is_some()
+                || config.join.is_some()
+            {
+                return Err(GameError::ConfigError(
+                    "the daily challenge plays a board of its own, so `--daily` can't be \
+                     combined with `--replay`, `--resume`, `--host` or `--join`"
+                        .to_string(),
+                ));
+            }
+            config.seed = Some(date.seed());
+        }
+        if config.edit.is_some()
+            && (config.replay.is_some()
+                || config.resume
+                || config.autopilot
+                || config.host.is_some()
+                || config.join.is_some())
+        {
+            return Err(GameError::ConfigError(
+                "`--edit` only lays out obstacles, so it can't be combined with `--replay`, \
+                 `--resume`, `--autopilot`, `--host` or `--join`"
+                    .to_string(),
+            ));
+        }
+        if config.reversible && config.record.is_some() {
+            return Err(GameError::ConfigError(
+                "replays can't capture reversals, so `--reversible` can't be combined with \
+                 `--record`"
+                    .to_string(),
+            ));
+        }
+        if config.tail_bite.is_some() && config.record.is_some() {
+            return Err(GameError::ConfigError(
+                "replays can't capture tail bites, so `--tail-bite` can't be combined with \
+                 `--record`"
+                    .to_string(),
+            ));
+        }
+        let windowed_only = [
+            ("--host", config.host.is_some()),
+            ("--join", config.join.is_some()),
+            ("--edit", config.edit.is_some()),
+            ("--practice", config.practice),
+            ("--record-gif", config.record_gif.is_some()),
+            ("--attract", config.attract.is_some()),
+        ];
+        if let Some((flag, _)) = windowed_only.iter().find(|(_, set)| config.tui && *set) {
+            return Err(GameError::ConfigError(format!(
+                "`{flag}` needs the window, so it can't be combined with `--tui`"
+            )));
+        }
+        if config.self_penalty.is_some() && (config.lives.is_some() || config.swarm.is_some()) {
+            return Err(GameError::ConfigError(
+                "`--self-penalty` is a way of surviving on its own, so it can't be combined with \
+                 `--lives` or `--swarm`"
+                    .to_string(),
+            ));
+        }
+        if config.swarm.is_some() && config.lives.is_some() {
+            return Err(GameError::ConfigError(
+                "losing any snake of a swarm ends the game, so `--swarm` can't be combined with \
+                 `--lives`"
+                    .to_string(),
+            ));
+        }
+        if config.swarm.is_some() && (config.host.is_some() || config.join.is_some()) {
+            return Err(GameError::ConfigError(
+                "races are one snake against one, so `--swarm` can't be combined with `--host` \
+                 or `--join`"
+                    .to_string(),
+            ));
+        }
+        if config.scenario.is_some()
+            && (config.replay.is_some()
+                || config.record.is_some()
+                || config.resume
+                || config.daily.is_some()
+                || config.swarm.is_some()
+                || config.obstacles.is_some()
+                || config.edit.is_some()
+                || config.host.is_some()
+                || config.join.is_some())
+        {
+            return Err(GameError::ConfigError(
+                "a scenario sets up the board itself and starts over on dying, so `--scenario` \
+                 can't be combined with `--replay`, `--record`, `--resume`, `--daily`, \
+                 `--swarm`, `--obstacles`, `--edit`, `--host` or `--join`"
+                    .to_string(),
+            ));
+        }
+        if config.start_direction.is_some_and(Direction::is_diagonal) && !config.diagonals {
+            return Err(GameError::ConfigError(
+                "a diagonal `--start-direction` needs `--diagonals`".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// The options that change how a game plays out, with a flag and value each, for a replay
+    /// to keep and only be played back under, see `replay`.
+    pub fn replay_rules(&self) -> Vec<(String, String)> {
+        let file = |path: &Option<PathBuf>| {
+            path.as_deref()
+                .and_then(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+        };
+        [
+            ("--speed", format!("{}", self.speed)),
+            ("--wrap-mode", format!("{:?}", self.wrap_mode)),
+            ("--wall-assist", format!("{}", self.wall_assist)),
+            ("--wrap-cooldown", format!("{}", self.wrap_cooldown)),
+            ("--start-direction", format!("{:?}", self.start_direction)),
+            ("--grace-period", format!("{}", self.grace_period)),
+            ("--gravity", format!("{:?}", self.gravity)),
+            ("--combo", format!("{:?}", self.combo)),
+            ("--lives", format!("{:?}", self.lives)),
+            ("--self-penalty", format!("{:?}", self.self_penalty)),
+            (
+                "--self-penalty-segments",
+                format!("{}", self.self_penalty_segments),
+            ),
+            ("--swarm", format!("{:?}", self.swarm)),
+            ("--reverse-kills", format!("{}", self.reverse_kills)),
+            ("--event-priority", format!("{:?}", self.event_priority)),
+            ("--mirror", format!("{}", self.mirror)),
+            ("--diagonals", format!("{}", self.diagonals)),
+            (
+                "--food-move-interval",
+                format!("{}", self.food_move_interval),
+            ),
+            ("--food-decay", format!("{}", self.food_decay)),
+            ("--shrinking-food", format!("{}", self.shrinking_food)),
+            (
+                "--food-respawn-delay",
+                format!("{}", self.food_respawn_delay),
+            ),
+            ("--bonus-food", format!("{}", self.bonus_food)),
+            ("--power-pellets", format!("{}", self.power_pellets)),
+            ("--rewind-pellets", format!("{}", self.rewind_pellets)),
+            ("--food-bias", format!("{:?}", self.food_bias)),
+            ("--food-chain", format!("{:?}", self.food_chain)),
+            ("--obstacles", format!("{:?}", file(&self.obstacles))),
+            (
+                "--food-sequence",
+                format!("{:?}", file(&self.food_sequence)),
+            ),
+            ("--scoring", format!("{:?}", self.scoring)),
+            ("--near-miss", format!("{}", self.near_miss)),
+            ("--near-miss-bonus", format!("{}", self.near_miss_bonus)),
+            ("--max-length", format!("{:?}", self.max_length)),
+            ("--input-mode", format!("{:?}", self.input_mode)),
+            ("--turn-drain", format!("{:?}", self.turn_drain)),
+        ]
+        .into_iter()
+        .map(|(flag, value)| (flag.to_string(), value))
+        .collect()
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> GameResult<T> {
+    let value = value.ok_or_else(|| GameError::ConfigError(format!("`{flag}` expects a value")))?;
+    value
+        .parse()
+        .map_err(|_| GameError::ConfigError(format!("invalid value `{value}` for `{flag}`")))
+}
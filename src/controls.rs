@@ -0,0 +1,123 @@
+use crate::Direction;
+use ggez::input::gamepad::gilrs::Button;
+use ggez::input::keyboard::KeyCode;
+
+/// Everything the player can do with the keyboard, and some of it with a gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Steer(Direction),
+    Pause,
+    Help,
+    DangerZone,
+    Checkerboard,
+    Heatmap,
+    ResetHeatmap,
+    Coordinates,
+    SegmentLabels,
+    Ghost,
+    Reverse,
+    BiteTail,
+    CycleRenderStyle,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Action {
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Steer(Direction::Up) => "Move up",
+            Action::Steer(Direction::Down) => "Move down",
+            Action::Steer(Direction::Left) => "Move left",
+            Action::Steer(Direction::Right) => "Move right",
+            Action::Steer(Direction::UpLeft) => "Move up and left",
+            Action::Steer(Direction::UpRight) => "Move up and right",
+            Action::Steer(Direction::DownLeft) => "Move down and left",
+            Action::Steer(Direction::DownRight) => "Move down and right",
+            Action::Pause => "Pause",
+            Action::Help => "Show/hide this help",
+            Action::DangerZone => "Toggle danger zone assist",
+            Action::Checkerboard => "Toggle checkerboard background",
+            Action::Heatmap => "Toggle heatmap of where the snake has been",
+            Action::ResetHeatmap => "Clear the heatmap",
+            Action::Coordinates => "Toggle grid coordinates",
+            Action::SegmentLabels => "Toggle numbers along the snake",
+            Action::Ghost => "Toggle a hint of the way to the food",
+            Action::Reverse => "Turn the snake around",
+            Action::BiteTail => "Bite off the end of the tail",
+            Action::CycleRenderStyle => "Switch to the next render style",
+            Action::ZoomIn => "Zoom in on the snake",
+            Action::ZoomOut => "Zoom back out",
+        }
+    }
+}
+
+/// The key bound to each action. Lookups go through here rather than matching on
+/// `KeyCode`s directly, so the help overlay always shows what the keys actually do.
+#[derive(Clone, Debug)]
+pub struct Controls {
+    bindings: Vec<(Action, KeyCode)>,
+}
+
+impl Controls {
+    /// The default bindings, with keys for the diagonals, reversing and biting the tail only if
+    /// they're enabled.
+    pub fn new(diagonals: bool, reversible: bool, tail_bite: bool) -> Self {
+        let mut bindings = vec![
+            (Action::Steer(Direction::Up), KeyCode::Up),
+            (Action::Steer(Direction::Down), KeyCode::Down),
+            (Action::Steer(Direction::Left), KeyCode::Left),
+            (Action::Steer(Direction::Right), KeyCode::Right),
+            (Action::Pause, KeyCode::P),
+            (Action::Help, KeyCode::H),
+            (Action::DangerZone, KeyCode::D),
+            (Action::Checkerboard, KeyCode::B),
+            (Action::Heatmap, KeyCode::M),
+            (Action::ResetHeatmap, KeyCode::X),
+            (Action::Coordinates, KeyCode::G),
+            (Action::SegmentLabels, KeyCode::L),
+            (Action::Ghost, KeyCode::K),
+            (Action::CycleRenderStyle, KeyCode::S),
+            (Action::ZoomIn, KeyCode::Equals),
+            (Action::ZoomOut, KeyCode::Minus),
+        ];
+        if reversible {
+            bindings.push((Action::Reverse, KeyCode::R));
+        }
+        if tail_bite {
+            bindings.push((Action::BiteTail, KeyCode::T));
+        }
+        if diagonals {
+            bindings.extend([
+                (Action::Steer(Direction::UpLeft), KeyCode::Q),
+                (Action::Steer(Direction::UpRight), KeyCode::E),
+                (Action::Steer(Direction::DownLeft), KeyCode::Z),
+                (Action::Steer(Direction::DownRight), KeyCode::C),
+            ]);
+        }
+        Controls { bindings }
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == key)
+            .map(|(action, _)| *action)
+    }
+
+    /// The D-pad steers, Start pauses and Select shows the help. These can't be rebound.
+    pub fn button_action(button: Button) -> Option<Action> {
+        match button {
+            Button::DPadUp => Some(Action::Steer(Direction::Up)),
+            Button::DPadDown => Some(Action::Steer(Direction::Down)),
+            Button::DPadLeft => Some(Action::Steer(Direction::Left)),
+            Button::DPadRight => Some(Action::Steer(Direction::Right)),
+            Button::Start => Some(Action::Pause),
+            Button::Select => Some(Action::Help),
+            _ => None,
+        }
+    }
+
+    pub fn bindings(&self) -> &[(Action, KeyCode)] {
+        &self.bindings
+    }
+}
@@ -0,0 +1,113 @@
+//! `--daily`: a challenge that's the same for everyone on the same day. The seed comes from
+//! the date in UTC, so the food and obstacles turn up in the same places for anyone playing
+//! with the same rules, and scores can be compared.
+//!
+//! The best score for each day is kept apart from the scoreboard, one `YYYY-MM-DD score` line
+//! per day.
+
+use crate::scores::data_dir;
+use ggez::GameResult;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAILY_FILE: &str = "daily.txt";
+
+/// A day of the Gregorian calendar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        Date::from_days((secs / 86_400) as i64)
+    }
+
+    /// The date `days` after 1970-01-01, going by Howard Hinnant's `civil_from_days`.
+    fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        Date { year, month, day }
+    }
+
+    /// The day's seed: the date as `YYYYMMDD`, mixed up by SplitMix64's finalizer so days next
+    /// to each other get nothing alike.
+    pub fn seed(self) -> u64 {
+        let mut z = (self.year as u64 * 10_000 + self.month as u64 * 100 + self.day as u64)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+fn path() -> PathBuf {
+    data_dir().join(DAILY_FILE)
+}
+
+/// The best score of each day played. Lines that don't parse are skipped.
+fn load() -> GameResult<BTreeMap<String, u32>> {
+    let contents = match fs::read_to_string(path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let (date, score) = line.trim().split_once(' ')?;
+            Some((date.to_string(), score.parse().ok()?))
+        })
+        .collect())
+}
+
+pub fn best(date: Date) -> GameResult<Option<u32>> {
+    Ok(load()?.get(&date.to_string()).copied())
+}
+
+/// Keeps `score` if it's the best on `date` so far, returning whether it was.
+pub fn record(date: Date, score: u32) -> GameResult<bool> {
+    let mut bests = load()?;
+    let day = date.to_string();
+    if bests.get(&day).is_some_and(|&best| best >= score) {
+        return Ok(false);
+    }
+    bests.insert(day, score);
+    let path = path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents: String = bests
+        .iter()
+        .map(|(date, score)| format!("{date} {score}\n"))
+        .collect();
+    fs::write(path, contents)?;
+    Ok(true)
+}
@@ -0,0 +1,62 @@
+//! How `--interpolate` slides the snake from one cell to the next between ticks.
+
+/// Maps how far through a tick it is, from `0.0` to `1.0`, to how far along the move the
+/// snake is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// At a steady pace.
+    #[default]
+    Linear,
+    /// Slow off the mark and slow into the next cell, quickest halfway.
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl std::str::FromStr for Easing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Easing::Linear),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            _ => Err(format!("unknown easing `{s}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_from_start_through_halfway_to_end() {
+        for easing in [Easing::Linear, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(0.5), 0.5, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn ease_in_out_is_slow_at_either_end() {
+        assert!(Easing::EaseInOut.apply(0.25) < 0.25);
+        assert!(Easing::EaseInOut.apply(0.75) > 0.75);
+    }
+
+    #[test]
+    fn clamps_outside_the_tick() {
+        for easing in [Easing::Linear, Easing::EaseInOut] {
+            assert_eq!(easing.apply(-0.5), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.5), 1.0, "{easing:?}");
+        }
+    }
+}
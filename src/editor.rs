@@ -0,0 +1,89 @@
+//! `--edit`: lay out obstacles by clicking cells on and off, with undo and redo. Every change
+//! is kept as an `Edit` that knows how to reverse itself, so undoing is just applying the
+//! inverse of the last one.
+
+use crate::GridPosition;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// How many edits can be undone. Past that the oldest are forgotten.
+const MAX_HISTORY: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    Place(GridPosition),
+    Remove(GridPosition),
+}
+
+impl Edit {
+    fn apply(self, obstacles: &mut HashSet<GridPosition>) {
+        match self {
+            Edit::Place(pos) => obstacles.insert(pos),
+            Edit::Remove(pos) => obstacles.remove(&pos),
+        };
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            Edit::Place(pos) => Edit::Remove(pos),
+            Edit::Remove(pos) => Edit::Place(pos),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Editor {
+    /// Where the layout is saved when the editor is closed.
+    path: PathBuf,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl Editor {
+    pub fn new(path: PathBuf) -> Self {
+        Editor {
+            path,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Places an obstacle at `pos`, or takes away the one that's already there.
+    pub fn toggle(&mut self, obstacles: &mut HashSet<GridPosition>, pos: GridPosition) {
+        let edit = if obstacles.contains(&pos) {
+            Edit::Remove(pos)
+        } else {
+            Edit::Place(pos)
+        };
+        edit.apply(obstacles);
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edit);
+        self.redo.clear();
+    }
+
+    /// Takes back the last edit, returning `false` if there's nothing left to undo.
+    pub fn undo(&mut self, obstacles: &mut HashSet<GridPosition>) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        edit.inverse().apply(obstacles);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Makes the last undone edit again, returning `false` if there's nothing to redo.
+    pub fn redo(&mut self, obstacles: &mut HashSet<GridPosition>) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        edit.apply(obstacles);
+        self.undo.push_back(edit);
+        true
+    }
+}
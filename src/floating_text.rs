@@ -0,0 +1,58 @@
+//! Little bits of text that drift up from a spot on the board and fade away, for calling out
+//! points and the like.
+
+use crate::text::TextStyle;
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color};
+use std::time::Duration;
+
+const LIFETIME: Duration = Duration::from_millis(800);
+/// How far the text rises over its lifetime, in pixels.
+const RISE: f32 = 40.0;
+
+struct FloatingText {
+    text: String,
+    /// Where the text started out, in pixels.
+    pos: Vec2,
+    color: Color,
+    age: Duration,
+}
+
+#[derive(Default)]
+pub struct FloatingTexts {
+    texts: Vec<FloatingText>,
+}
+
+impl FloatingTexts {
+    pub fn spawn(&mut self, text: impl Into<String>, pos: Vec2, color: impl Into<Color>) {
+        self.texts.push(FloatingText {
+            text: text.into(),
+            pos,
+            color: color.into(),
+            age: Duration::ZERO,
+        });
+    }
+
+    pub fn update(&mut self, dt: Duration) {
+        for text in &mut self.texts {
+            text.age += dt;
+        }
+        self.texts.retain(|text| text.age < LIFETIME);
+    }
+
+    pub fn draw(&self, canvas: &mut graphics::Canvas, style: &TextStyle) {
+        for text in &self.texts {
+            let t = text.age.as_secs_f32() / LIFETIME.as_secs_f32();
+            let mut color = text.color;
+            color.a *= 1.0 - t;
+            let mut drawable = style.text(text.text.as_str(), 24.0);
+            drawable.set_layout(graphics::TextLayout::center());
+            canvas.draw(
+                &drawable,
+                graphics::DrawParam::new()
+                    .dest(text.pos - Vec2::new(0.0, RISE * t))
+                    .color(color),
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! `--gravity SECS`: a puzzle mode where "down" turns a quarter turn clockwise every so
+//! often, and a snake left alone for a few ticks gets pulled that way.
+//!
+//! The clock runs on ticks, `SECS` at the starting speed, so replays and saves see the turns
+//! at the same moments. With `--rotate-view` the board turns along with it, so down is always
+//! the bottom of the window.
+
+use crate::Direction;
+use std::f32::consts::FRAC_PI_2;
+use std::time::Duration;
+
+/// Ticks without a turn from the player before gravity turns the snake.
+pub const PULL_AFTER: u32 = 5;
+/// How long the view takes to swing round to the new down.
+pub const VIEW_TURN: Duration = Duration::from_millis(400);
+
+#[derive(Clone, Debug)]
+pub struct Gravity {
+    /// Ticks between quarter turns.
+    pub interval: u32,
+    pub ticks_until_turn: u32,
+    /// Quarter turns so far.
+    pub turns: u32,
+}
+
+impl Gravity {
+    pub fn new(interval: u32) -> Self {
+        Gravity {
+            interval,
+            ticks_until_turn: interval,
+            turns: 0,
+        }
+    }
+
+    pub fn down(&self) -> Direction {
+        (0..self.turns % 4).fold(Direction::Down, |dir, _| dir.clockwise())
+    }
+
+    /// Counts down a tick, returning whether down just turned.
+    pub fn tick(&mut self) -> bool {
+        self.ticks_until_turn = self.ticks_until_turn.saturating_sub(1);
+        if self.ticks_until_turn > 0 {
+            return false;
+        }
+        self.ticks_until_turn = self.interval;
+        self.turns += 1;
+        true
+    }
+
+    /// How far, in radians, the board is turned to put down at the bottom of the window,
+    /// `since_turn` into swinging round from the last down.
+    pub fn view_angle(&self, since_turn: Option<Duration>) -> f32 {
+        let swing = since_turn.map_or(1.0, |since| {
+            let t = (since.as_secs_f32() / VIEW_TURN.as_secs_f32()).min(1.0);
+            t * t * (3.0 - 2.0 * t)
+        });
+        let turns = (self.turns % 4) as f32 - 1.0 + swing;
+        -turns * FRAC_PI_2
+    }
+}
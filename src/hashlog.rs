@@ -0,0 +1,219 @@
+//! `--hashlog FILE`: writes a hash of the game after every tick, for tracking down where two
+//! runs that should have played out the same way, a replay and the game it was recorded
+//! from or the two sides of a network game, part ways. Diffing two logs gives the first tick
+//! they disagree on.
+//!
+//! Each line is `tick hash`, the hash in hex. It covers everything that decides how the game
+//! goes on from that tick, and nothing that's only for show.
+
+use crate::body::Body;
+use crate::{Direction, EventPriority, GameState, GridPosition, Scoring, Snake, WrapMode};
+use ggez::GameResult;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// 64-bit FNV-1a, byte by byte, so the same game hashes the same on any machine and with any
+/// build, unlike `std`'s hasher.
+struct StateHasher(u64);
+
+impl StateHasher {
+    fn new() -> Self {
+        StateHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn option_u32(&mut self, value: Option<u32>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.u32(value);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn pos(&mut self, pos: GridPosition) {
+        self.bytes(&pos.x.to_le_bytes());
+        self.bytes(&pos.y.to_le_bytes());
+    }
+
+    /// A count, then the positions, so lists that run into each other can't collide.
+    fn positions(&mut self, positions: impl ExactSizeIterator<Item = GridPosition>) {
+        self.u32(positions.len() as u32);
+        for pos in positions {
+            self.pos(pos);
+        }
+    }
+
+    fn dir(&mut self, dir: Direction) {
+        let (dx, dy) = dir.offset();
+        self.bytes(&[dx as i8 as u8, dy as i8 as u8]);
+    }
+
+    fn snake(&mut self, snake: &Snake) {
+        self.pos(snake.head.pos);
+        // Written out like `positions`, since the body's iterator doesn't know its length.
+        self.u32(snake.body.len() as u32);
+        for seg in snake.body.iter() {
+            self.pos(seg.pos);
+        }
+        self.dir(snake.dir);
+        self.dir(snake.last_update_dir);
+        match snake.next_dir {
+            Some(dir) => {
+                self.u8(1);
+                self.dir(dir);
+            }
+            None => self.u8(0),
+        }
+        self.option_u32(snake.max_length.map(|max| max as u32));
+        self.u32(snake.grace_ticks);
+        self.u32(snake.invulnerable_ticks);
+    }
+}
+
+impl GameState {
+    pub fn state_hash(&self) -> u64 {
+        let mut h = StateHasher::new();
+        h.u64(self.tick);
+        h.u64(self.seed);
+        let (state, increment) = self.rng.state();
+        h.u64(state);
+        h.u64(increment);
+
+        let rules = self.rules;
+        h.u8(match rules.wrap_mode {
+            WrapMode::Wrap => 0,
+            WrapMode::Walls => 1,
+            WrapMode::Bounce => 2,
+        });
+        h.bytes(&[
+            rules.wall_assist as u8,
+            rules.mirror as u8,
+            rules.reverse_kills as u8,
+        ]);
+        // Only when it's food first, so the hashes of other games stay as they were.
+        if rules.event_priority == EventPriority::FoodFirst {
+            h.u8(1);
+        }
+        h.u32(rules.wrap_cooldown);
+        h.u32(rules.grace_period);
+        h.u32(match self.scoring {
+            Scoring::Flat => 0,
+            Scoring::Distance { cells_per_point } => cells_per_point,
+        });
+
+        h.bytes(&[self.gameover as u8, self.won as u8, self.paused as u8]);
+        h.u32(self.score);
+        h.option_u32(self.lives);
+        h.u64(self.max_len as u64);
+        h.pos(self.last_food);
+        h.u32(self.reverse_cooldown);
+        h.u32(self.tail_bite_cooldown);
+        h.u32(self.wrap_cooldown);
+        h.u32(self.ticks_since_steer);
+
+        h.snake(&self.snake);
+
+        let food = &self.food;
+        h.pos(food.pos);
+        h.u8(food.kind as u8);
+        h.bytes(&food.velocity.to_le_bytes());
+        h.u32(food.ticks_until_move);
+        h.u32(food.ticks_until_decay);
+        // Left out unless the food shrinks, so the hashes of other games stay as they were.
+        if food.shrink_time > 0 {
+            h.u32(food.age);
+        }
+        h.positions(food.trail.into_iter());
+        h.u32(self.food_respawn_in);
+
+        // A set's order changes from run to run, so the obstacles go in sorted.
+        let mut obstacles: Vec<GridPosition> = self.obstacles.iter().copied().collect();
+        obstacles.sort_by_key(|pos| (pos.y, pos.x));
+        h.positions(obstacles.into_iter());
+        h.positions(self.food_sequence.iter().copied());
+
+        if let Some(chain) = &self.chain {
+            h.u32(chain.number);
+            h.positions(chain.upcoming.iter().copied());
+        }
+        if let Some(gravity) = &self.gravity {
+            h.u32(gravity.ticks_until_turn);
+            h.u32(gravity.turns);
+        }
+        if let Some(combo) = &self.combo {
+            h.u32(combo.ticks_left);
+            h.u32(combo.streak);
+        }
+        // Left out without power pellets, so the hashes of other games stay as they were.
+        if self.power_pellets > 0.0 {
+            for snake in self.snakes() {
+                h.u32(snake.powered_ticks);
+            }
+        }
+        if let Some(rewind) = &self.rewind {
+            h.u32(rewind.history.len() as u32);
+            for snakes in &rewind.history {
+                h.u32(snakes.len() as u32);
+                for snake in snakes {
+                    h.snake(snake);
+                }
+            }
+        }
+        // Left out without the penalty, so the hashes of other games stay as they were.
+        if let Some(penalty) = self.self_penalty {
+            h.u32(penalty.points);
+            h.u32(penalty.segments);
+            h.u32(self.snake.stunned_ticks);
+        }
+        // Left out without a swarm, so the hashes of other games stay as they were.
+        for snake in &self.swarm {
+            h.snake(snake);
+        }
+        h.0
+    }
+}
+
+pub struct HashLog {
+    out: Option<BufWriter<File>>,
+}
+
+impl HashLog {
+    pub fn create(path: &Path) -> GameResult<Self> {
+        Ok(HashLog {
+            out: Some(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Adds a line, giving up on the log for good if it can't be written. Every line is
+    /// flushed straight away, so the log is complete up to a crash.
+    pub fn record(&mut self, tick: u64, hash: u64) {
+        let Some(out) = &mut self.out else {
+            return;
+        };
+        if let Err(e) = writeln!(out, "{tick} {hash:016x}").and_then(|()| out.flush()) {
+            eprintln!("Could not write the hash log: {e}");
+            self.out = None;
+        }
+    }
+}
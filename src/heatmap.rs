@@ -0,0 +1,67 @@
+//! A spectator overlay showing where the snake has spent the game: every tick counts once for
+//! the cell the head is on, and the busiest cells are drawn the most strongly.
+
+use crate::{GridPosition, GRID_SIZE};
+use ggez::graphics;
+use ggez::Context;
+
+/// The color of the busiest cell; the others fade out towards transparent.
+const HOT: [f32; 4] = [1.0, 0.1, 0.0, 0.6];
+
+pub struct Heatmap {
+    /// One count per cell, row by row.
+    counts: Vec<u32>,
+    /// Built from `counts` the next time it's drawn after a change.
+    cells: Option<graphics::InstanceArray>,
+    dirty: bool,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap {
+            counts: vec![0; GRID_SIZE.0 as usize * GRID_SIZE.1 as usize],
+            cells: None,
+            dirty: false,
+        }
+    }
+}
+
+impl Heatmap {
+    fn index(pos: GridPosition) -> usize {
+        pos.y as usize * GRID_SIZE.0 as usize + pos.x as usize
+    }
+
+    pub fn record(&mut self, pos: GridPosition) {
+        self.counts[Self::index(pos)] += 1;
+        self.dirty = true;
+    }
+
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+        self.dirty = true;
+    }
+
+    pub fn draw(&mut self, ctx: &Context, canvas: &mut graphics::Canvas) {
+        let cells = self
+            .cells
+            .get_or_insert_with(|| graphics::InstanceArray::new(ctx, None));
+        if self.dirty {
+            let max = self.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+            let counts = &self.counts;
+            cells.set(
+                (0..GRID_SIZE.1)
+                    .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+                    .filter(|&pos| counts[Self::index(pos)] > 0)
+                    .map(|pos| {
+                        let [r, g, b, a] = HOT;
+                        let heat = counts[Self::index(pos)] as f32 / max;
+                        graphics::DrawParam::new()
+                            .dest_rect(pos.into())
+                            .color([r, g, b, a * heat])
+                    }),
+            );
+            self.dirty = false;
+        }
+        canvas.draw(cells, graphics::DrawParam::new());
+    }
+}
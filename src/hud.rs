@@ -0,0 +1,264 @@
+//! Where the HUD goes. Each element is pinned to a corner of the window, or hidden, and the
+//! elements sharing a corner stack up away from it in the order they're listed here. Pinning
+//! to the window rather than the board keeps them out in the letterboxing when the window is
+//! a different shape from the board.
+//!
+//! The layout is kept in `Settings`, as `hud.<element>=<corner>` lines, where the corner is
+//! `top-left`, `top-right`, `bottom-left`, `bottom-right` or `hidden`.
+
+use crate::text::{self, TextStyle};
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color, TextAlign};
+
+/// Space between an element and the edges of the window.
+const PADDING: f32 = 8.0;
+/// The size of the bar under an element that has one, like the combo's time left.
+const BAR_SIZE: (f32, f32) = (96.0, 4.0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        }
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+}
+
+/// A corner, or `None` for `hidden`.
+fn parse_corner(s: &str) -> Option<Option<Corner>> {
+    match s {
+        "hidden" => Some(None),
+        _ => Corner::ALL.into_iter().find(|c| c.name() == s).map(Some),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudElement {
+    Score,
+    Best,
+    Time,
+    /// Where the score would place on the scoreboard if the game ended now.
+    Rank,
+    Lives,
+    Combo,
+    /// The frame time and instance count with `--stress`.
+    Stress,
+}
+
+impl HudElement {
+    const ALL: [HudElement; 7] = [
+        HudElement::Score,
+        HudElement::Best,
+        HudElement::Time,
+        HudElement::Rank,
+        HudElement::Lives,
+        HudElement::Combo,
+        HudElement::Stress,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            HudElement::Score => "hud.score",
+            HudElement::Best => "hud.best",
+            HudElement::Time => "hud.time",
+            HudElement::Rank => "hud.rank",
+            HudElement::Lives => "hud.lives",
+            HudElement::Combo => "hud.combo",
+            HudElement::Stress => "hud.stress",
+        }
+    }
+}
+
+/// One element's line of text, with a bar under it filled to `bar` if it has one.
+pub struct HudLine {
+    pub element: HudElement,
+    pub text: String,
+    pub bar: Option<f32>,
+}
+
+impl HudLine {
+    pub fn new(element: HudElement, text: String) -> Self {
+        HudLine {
+            element,
+            text,
+            bar: None,
+        }
+    }
+
+    pub fn with_bar(self, filled: f32) -> Self {
+        HudLine {
+            bar: Some(filled.clamp(0.0, 1.0)),
+            ..self
+        }
+    }
+}
+
+/// Which corner each element goes in. Out of the box only the lives and the combo are shown,
+/// so the board stays uncluttered until asked otherwise, along with the line `--stress` is
+/// run for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HudLayout {
+    pub score: Option<Corner>,
+    pub best: Option<Corner>,
+    pub time: Option<Corner>,
+    pub rank: Option<Corner>,
+    pub lives: Option<Corner>,
+    pub combo: Option<Corner>,
+    pub stress: Option<Corner>,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        HudLayout {
+            score: None,
+            best: None,
+            time: None,
+            rank: None,
+            lives: Some(Corner::BottomLeft),
+            combo: Some(Corner::BottomRight),
+            stress: Some(Corner::TopLeft),
+        }
+    }
+}
+
+impl HudLayout {
+    pub fn corner(&self, element: HudElement) -> Option<Corner> {
+        match element {
+            HudElement::Score => self.score,
+            HudElement::Best => self.best,
+            HudElement::Time => self.time,
+            HudElement::Rank => self.rank,
+            HudElement::Lives => self.lives,
+            HudElement::Combo => self.combo,
+            HudElement::Stress => self.stress,
+        }
+    }
+
+    fn corner_mut(&mut self, element: HudElement) -> &mut Option<Corner> {
+        match element {
+            HudElement::Score => &mut self.score,
+            HudElement::Best => &mut self.best,
+            HudElement::Time => &mut self.time,
+            HudElement::Rank => &mut self.rank,
+            HudElement::Lives => &mut self.lives,
+            HudElement::Combo => &mut self.combo,
+            HudElement::Stress => &mut self.stress,
+        }
+    }
+
+    /// Takes a line of the settings file, returning whether it was one of the layout's.
+    pub fn set(&mut self, key: &str, value: &str) -> bool {
+        let Some(element) = HudElement::ALL.into_iter().find(|e| e.key() == key) else {
+            return false;
+        };
+        if let Some(corner) = parse_corner(value) {
+            *self.corner_mut(element) = corner;
+        }
+        true
+    }
+
+    /// The layout as lines for the settings file.
+    pub fn lines(&self) -> String {
+        HudElement::ALL
+            .into_iter()
+            .map(|element| {
+                let corner = self.corner(element).map_or("hidden", Corner::name);
+                format!("{}={corner}\n", element.key())
+            })
+            .collect()
+    }
+
+    /// Draws each of `lines` that isn't hidden in its corner of `screen`.
+    pub fn draw(
+        &self,
+        canvas: &mut graphics::Canvas,
+        style: &TextStyle,
+        screen: graphics::Rect,
+        lines: &[HudLine],
+    ) {
+        let line_height = text::DEFAULT_SIZE * 1.25;
+        for corner in Corner::ALL {
+            let mut offset = PADDING;
+            for line in lines
+                .iter()
+                .filter(|line| self.corner(line.element) == Some(corner))
+            {
+                let mut text = style.text(line.text.as_str(), text::DEFAULT_SIZE);
+                text.set_layout(graphics::TextLayout {
+                    h_align: if corner.is_left() {
+                        TextAlign::Begin
+                    } else {
+                        TextAlign::End
+                    },
+                    v_align: if corner.is_top() {
+                        TextAlign::Begin
+                    } else {
+                        TextAlign::End
+                    },
+                });
+                let x = if corner.is_left() {
+                    screen.left() + PADDING
+                } else {
+                    screen.right() - PADDING
+                };
+                let y = if corner.is_top() {
+                    screen.top() + offset
+                } else {
+                    screen.bottom() - offset
+                };
+                canvas.draw(
+                    &text,
+                    graphics::DrawParam::new()
+                        .dest(Vec2::new(x, y))
+                        .color(Color::BLACK),
+                );
+                offset += line_height;
+                let Some(filled) = line.bar else {
+                    continue;
+                };
+                // The bar goes on the side of the text away from the corner.
+                let (w, h) = BAR_SIZE;
+                let left = if corner.is_left() { x } else { x - w };
+                let top = if corner.is_top() {
+                    screen.top() + offset
+                } else {
+                    screen.bottom() - offset - h
+                };
+                let empty = graphics::Rect::new(left, top, w, h);
+                let full = graphics::Rect::new(left, top, w * filled, h);
+                for (rect, color) in [(empty, [0.0, 0.0, 0.0, 0.3]), (full, [0.0, 0.0, 0.0, 1.0])] {
+                    canvas.draw(
+                        &graphics::Quad,
+                        graphics::DrawParam::new().dest_rect(rect).color(color),
+                    );
+                }
+                offset += h * 2.0;
+            }
+        }
+    }
+}
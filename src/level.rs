@@ -0,0 +1,61 @@
+//! Hand-authored level data.
+
+use crate::{GridPosition, GRID_SIZE};
+use ggez::{GameError, GameResult};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// Reads the ordered list of spots the food should appear at, see `load_positions`.
+pub fn load_food_sequence(path: &Path) -> GameResult<VecDeque<GridPosition>> {
+    load_positions(path)
+}
+
+/// Reads a layout of obstacles in the same format as a food sequence.
+pub fn load_obstacles(path: &Path) -> GameResult<HashSet<GridPosition>> {
+    Ok(load_positions(path)?.into_iter().collect())
+}
+
+/// Writes out a layout of obstacles so `load_obstacles` reads it back, in reading order so
+/// the file is easy to follow and to compare.
+pub fn save_obstacles(path: &Path, obstacles: &HashSet<GridPosition>) -> GameResult {
+    let mut cells: Vec<GridPosition> = obstacles.iter().copied().collect();
+    cells.sort_by_key(|pos| (pos.y, pos.x));
+    let mut contents = String::from("# Obstacles, one x,y per line\n");
+    for pos in cells {
+        contents.push_str(&format!("{},{}\n", pos.x, pos.y));
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Reads a list of grid positions, one `x,y` per line.
+/// Blank lines and lines starting with `#` are skipped.
+fn load_positions(path: &Path) -> GameResult<VecDeque<GridPosition>> {
+    let contents = fs::read_to_string(path)?;
+    let invalid = |line_no: usize, msg: String| {
+        GameError::ResourceLoadError(format!("{}:{line_no}: {msg}", path.display()))
+    };
+
+    let mut sequence = VecDeque::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line
+            .split_once(',')
+            .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+        let Some((x, y)) = parsed else {
+            return Err(invalid(i + 1, format!("expected `x,y`, found `{line}`")));
+        };
+        if !(0..GRID_SIZE.0).contains(&x) || !(0..GRID_SIZE.1).contains(&y) {
+            return Err(invalid(
+                i + 1,
+                format!("{x},{y} is off the {}x{} grid", GRID_SIZE.0, GRID_SIZE.1),
+            ));
+        }
+        sequence.push_back(GridPosition::new(x, y));
+    }
+    Ok(sequence)
+}
@@ -0,0 +1,5353 @@
+//! Some of the details may need to be changed to scale the game.
+//! For example, if we needed to draw hundreds or thousands of shapes,
+//! a `SpriteBatch` is going to offer far better performance than the direct draw calls that this example uses.
+//!
+//! Author: @termhn
+//! Original repo: <https://github.com/termhn/ggez_snake>
+
+mod ai;
+mod attract;
+#[doc(hidden)]
+pub mod bench;
+mod body;
+mod capture;
+mod chain;
+mod combo;
+mod config;
+mod controls;
+mod daily;
+mod easing;
+mod editor;
+mod floating_text;
+mod gravity;
+mod hashlog;
+mod heatmap;
+mod hud;
+mod level;
+mod loading;
+mod net;
+mod particles;
+mod performance;
+mod practice;
+mod render;
+mod replay;
+mod resources;
+mod rewind;
+mod savegame;
+mod scenario;
+mod scores;
+mod selftest;
+mod settings;
+mod sound;
+mod splits;
+mod sprites;
+mod stats;
+mod stress;
+mod swarm;
+mod text;
+mod transition;
+mod tui;
+mod verify;
+
+use attract::Attract;
+use body::{Body, RingBuffer};
+use capture::GifRecorder;
+use chain::FoodChain;
+use combo::Combo;
+use ggez::audio;
+use ggez::audio::SoundSource;
+use ggez::glam::*;
+use std::env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use config::Config;
+use controls::{Action, Controls};
+use easing::Easing;
+use editor::Editor;
+use floating_text::FloatingTexts;
+use ggez::{
+    event::{self, MouseButton},
+    graphics,
+    input::gamepad::{gilrs::Button, GamepadId},
+    input::keyboard::{KeyCode, KeyInput, KeyMods},
+    Context, GameError, GameResult,
+};
+use gravity::Gravity;
+use hashlog::HashLog;
+use heatmap::Heatmap;
+use hud::{HudElement, HudLayout, HudLine};
+use loading::Loading;
+use net::Versus;
+use oorandom::Rand32;
+use particles::ParticleSystem;
+use performance::Performance;
+use practice::Practice;
+use render::{CanvasCells, CellPainter, RenderStyle, Surface};
+use replay::{Cursor, Outcome, Player, Recorder, ReplayFile, ReplayResult};
+use rewind::Rewind;
+use scenario::ScenarioStart;
+use settings::Settings;
+use sound::{Ducking, EatCue, Music, SoundPool};
+use splits::Splits;
+use sprites::SpriteSheet;
+use std::collections::{HashSet, VecDeque};
+use stress::Stress;
+use text::TextStyle;
+use transition::{Transition, TransitionStyle};
+
+const GRID_SIZE: (i16, i16) = (30, 20);
+const GRID_CELL_SIZE: (i16, i16) = (32, 32); // Pixels
+
+const SCREEN_SIZE: (f32, f32) = (
+    GRID_SIZE.0 as f32 * GRID_CELL_SIZE.0 as f32,
+    GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1 as f32,
+);
+
+const DESIRED_FPS: u32 = 8;
+/// The fastest the game is allowed to tick.
+const MAX_FPS: u32 = 30;
+/// The most `--assist-slow` takes off the speed, on a full board.
+const ASSIST_SLOWDOWN: f32 = 0.5;
+/// How many logical updates a single frame may catch up on by default. After a hitch any
+/// time beyond that is dropped, rather than the snake teleporting across the board.
+const MAX_UPDATES_PER_FRAME: u32 = 4;
+
+/// The largest whole number of physical pixels a square cell can take up so that the grid
+/// fits in a `drawable` of that many pixels, and the offset that centers the grid in it.
+fn fit_cells(drawable: (f32, f32)) -> (f32, Vec2) {
+    let cell = (drawable.0 / GRID_SIZE.0 as f32)
+        .min(drawable.1 / GRID_SIZE.1 as f32)
+        .floor()
+        .max(1.0);
+    let offset = Vec2::new(
+        ((drawable.0 - cell * GRID_SIZE.0 as f32) / 2.0).floor(),
+        ((drawable.1 - cell * GRID_SIZE.1 as f32) / 2.0).floor(),
+    );
+    (cell, offset)
+}
+
+/// The part of the game's logical coordinate space, where a cell is `GRID_CELL_SIZE`, that
+/// covers a framebuffer of `drawable` physical pixels. Everything is drawn in logical
+/// coordinates and this maps them onto the cells from `fit_cells`, so on a 2x display a cell
+/// simply takes up twice as many pixels each way, and the space left over around the grid
+/// is letterboxed.
+fn viewport(drawable: (f32, f32)) -> graphics::Rect {
+    let (cell, offset) = fit_cells(drawable);
+    let scale = Vec2::new(
+        cell / GRID_CELL_SIZE.0 as f32,
+        cell / GRID_CELL_SIZE.1 as f32,
+    );
+    graphics::Rect::new(
+        -offset.x / scale.x,
+        -offset.y / scale.y,
+        drawable.0 / scale.x,
+        drawable.1 / scale.y,
+    )
+}
+
+fn random_seed() -> u64 {
+    let mut seed: [u8; 8] = [0; 8];
+    getrandom::getrandom(&mut seed[..]).expect("Could not create RNG seed");
+    u64::from_ne_bytes(seed)
+}
+
+/// Turns `view` by `angle` about its middle, shrunk to still fit inside its old bounds.
+fn rotated_view(view: graphics::Rect, angle: f32) -> Mat4 {
+    let (sin, cos) = angle.sin_cos();
+    let (w, h) = (view.w, view.h);
+    let fit = (w / (w * cos.abs() + h * sin.abs())).min(h / (w * sin.abs() + h * cos.abs()));
+    let center = Vec2::from(view.center()).extend(0.0);
+    Mat4::from_translation(center)
+        * Mat4::from_rotation_z(angle)
+        * Mat4::from_scale(Vec3::splat(fit))
+        * Mat4::from_translation(-center)
+}
+
+/// How far the view can be zoomed in, and how much each step zooms.
+const MAX_ZOOM: f32 = 4.0;
+const ZOOM_STEP: f32 = 1.25;
+/// How big the arrows pointing to food out of view are, and how far in from the edge.
+const RADAR_ARROW_SIZE: f32 = 18.0;
+const RADAR_MARGIN: f32 = 12.0;
+
+/// `screen` zoomed in by `zoom` and centered on `focus` as far as possible, without looking
+/// past the edges of the playfield unless the whole playfield is already in view.
+fn zoomed_view(screen: graphics::Rect, zoom: f32, focus: Vec2) -> graphics::Rect {
+    let (w, h) = (screen.w / zoom, screen.h / zoom);
+    let axis = |focus: f32, view: f32, field: f32| {
+        if view >= field {
+            (field - view) / 2.0
+        } else {
+            (focus - view / 2.0).clamp(0.0, field - view)
+        }
+    };
+    graphics::Rect::new(
+        axis(focus.x, w, SCREEN_SIZE.0),
+        axis(focus.y, h, SCREEN_SIZE.1),
+        w,
+        h,
+    )
+}
+
+/// Turn sounds closer together than this are skipped, so mashing keys doesn't turn into noise.
+const TURN_SOUND_COOLDOWN: Duration = Duration::from_millis(100);
+const TURN_SOUND_VOLUME: f32 = 0.3;
+
+/// How long a freshly grown tail segment takes to scale up to full size.
+const GROWTH_ANIMATION: Duration = Duration::from_millis(120);
+
+/// The tongue flicks out for this long, more often the closer the food is.
+const TONGUE_FLICK: Duration = Duration::from_millis(150);
+const TONGUE_PERIOD_MIN: f32 = 0.4;
+const TONGUE_PERIOD_PER_CELL: f32 = 0.08;
+const TONGUE_PERIOD_MAX: f32 = 2.0;
+
+/// How many cells ahead of the head the danger zone assist looks for the body.
+const DANGER_LOOKAHEAD: usize = 3;
+const DANGER_FLASH_PERIOD: Duration = Duration::from_millis(300);
+
+/// How long the snake takes to fall apart when it dies, before the game over screen shows.
+const DEATH_ANIMATION: Duration = Duration::from_millis(900);
+/// The fastest a piece of a dead snake flies off, in pixels per second.
+const DEATH_PARTICLE_SPEED: f32 = 250.0;
+
+/// The alternate cells of the checkerboard are the background with this much black on top.
+const CHECKERBOARD_SHADE: f32 = 0.08;
+
+/// Lays out the dark cells of the checkerboard as a single batch, through `painter`'s warp.
+fn checkerboard(cells: &mut graphics::InstanceArray, painter: &CellPainter) {
+    cells.set(
+        (0..GRID_SIZE.1)
+            .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+            .filter(|pos| (pos.x + pos.y) % 2 == 1)
+            .map(|pos| {
+                graphics::DrawParam::new()
+                    .dest_rect(painter.warp(pos.into()))
+                    .color([0.0, 0.0, 0.0, CHECKERBOARD_SHADE])
+            }),
+    );
+}
+
+/// How dark the fog gets away from the head, and over how many cells past the lit radius it
+/// thickens to that.
+const FOG_DARKNESS: f32 = 0.92;
+const FOG_FALLOFF: f32 = 2.0;
+
+/// How long the snake lights up after a close call.
+const NEAR_MISS_FLASH: Duration = Duration::from_millis(250);
+/// How long the screen flashes for when eating, with `EatCue::Flash`, or reaching a milestone.
+const EAT_FLASH: Duration = Duration::from_millis(150);
+/// How much higher the eat sound plays when the snake reaches one of `--milestones`.
+const MILESTONE_PITCH: f32 = 1.5;
+/// How long where the snake was before a rewind takes to fade out.
+const REWIND_FLASH: Duration = Duration::from_millis(400);
+/// How long the hidden body shows for after eating, with `--invisible`.
+const BODY_REVEAL: Duration = Duration::from_millis(400);
+/// The pitch of the eating sound for a rewind pellet, played low like a tape going backwards.
+const REWIND_PITCH: f32 = 0.6;
+
+/// The sky colors the background cycles through in the time-of-day mode, evenly spaced.
+const SKY_PALETTE: [[f32; 3]; 4] = [
+    [0.95, 0.6, 0.45],  // dawn
+    [0.45, 0.75, 0.95], // day
+    [0.85, 0.4, 0.3],   // dusk
+    [0.08, 0.1, 0.25],  // night
+];
+
+/// The background color `elapsed` into a day that lasts `cycle`, blended smoothly between the
+/// neighboring colors of `SKY_PALETTE`.
+fn sky_color(elapsed: Duration, cycle: Duration) -> graphics::Color {
+    let phase = (elapsed.as_secs_f32() / cycle.as_secs_f32()).fract() * SKY_PALETTE.len() as f32;
+    let from = SKY_PALETTE[phase as usize % SKY_PALETTE.len()];
+    let to = SKY_PALETTE[(phase as usize + 1) % SKY_PALETTE.len()];
+    let t = phase.fract();
+    graphics::Color::new(
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+        1.0,
+    )
+}
+
+/// we need them to be signed so that they work properly with our modulus arithmetic later.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct GridPosition {
+    x: i16,
+    y: i16,
+}
+
+impl GridPosition {
+    pub fn new(x: i16, y: i16) -> Self {
+        GridPosition { x, y }
+    }
+
+    /// We'll make another helper function that takes one grid position and returns a new one after
+    /// making one move in the direction of `dir`.
+    /// We use the [`rem_euclid()`](https://doc.rust-lang.org/std/primitive.i16.html#method.rem_euclid)
+    /// API when crossing the top/left limits, as the standard remainder function (`%`) returns a
+    /// negative value when the left operand is negative.
+    /// Only moves up or left require rem_euclid(); for consistency, it's used for all of them.
+    pub fn new_from_move(pos: GridPosition, dir: Direction) -> Self {
+        let (dx, dy) = dir.offset();
+        GridPosition::new(
+            (pos.x + dx).rem_euclid(GRID_SIZE.0),
+            (pos.y + dy).rem_euclid(GRID_SIZE.1),
+        )
+    }
+
+    /// Like `new_from_move`, but `None` when the move would leave the grid instead of wrapping.
+    pub fn checked_move(pos: GridPosition, dir: Direction) -> Option<Self> {
+        let (dx, dy) = dir.offset();
+        let (x, y) = (pos.x + dx, pos.y + dy);
+        ((0..GRID_SIZE.0).contains(&x) && (0..GRID_SIZE.1).contains(&y))
+            .then(|| GridPosition::new(x, y))
+    }
+
+    /// One move in `dir`, either wrapping around the edges or stopping at them.
+    pub fn moved(pos: GridPosition, dir: Direction, wrap_mode: WrapMode) -> Option<Self> {
+        GridPosition::moved_within(pos, dir, wrap_mode, GRID_SIZE)
+    }
+
+    /// Like `moved`, on a grid of `grid` cells rather than the game's.
+    pub fn moved_within(
+        pos: GridPosition,
+        dir: Direction,
+        wrap_mode: WrapMode,
+        grid: (i16, i16),
+    ) -> Option<Self> {
+        let (dx, dy) = dir.offset();
+        let (x, y) = (pos.x + dx, pos.y + dy);
+        match wrap_mode {
+            WrapMode::Wrap => Some(GridPosition::new(
+                x.rem_euclid(grid.0),
+                y.rem_euclid(grid.1),
+            )),
+            WrapMode::Walls | WrapMode::Bounce => ((0..grid.0).contains(&x)
+                && (0..grid.1).contains(&y))
+            .then(|| GridPosition::new(x, y)),
+        }
+    }
+
+    /// The reflection across the vertical center line of the grid.
+    pub fn mirrored(self) -> Self {
+        GridPosition::new(GRID_SIZE.0 - 1 - self.x, self.y)
+    }
+}
+
+impl From<GridPosition> for graphics::Rect {
+    fn from(pos: GridPosition) -> Self {
+        graphics::Rect::new_i32(
+            pos.x as i32 * GRID_CELL_SIZE.0 as i32,
+            pos.y as i32 * GRID_CELL_SIZE.1 as i32,
+            GRID_CELL_SIZE.0 as i32,
+            GRID_CELL_SIZE.1 as i32,
+        )
+    }
+}
+
+impl GridPosition {
+    /// The cell's rect, shrunk by `scale` around its center.
+    pub fn scaled_rect(self, scale: f32) -> graphics::Rect {
+        let mut rect: graphics::Rect = self.into();
+        let (w, h) = (rect.w * scale, rect.h * scale);
+        rect.x += (rect.w - w) / 2.0;
+        rect.y += (rect.h - h) / 2.0;
+        rect.w = w;
+        rect.h = h;
+        rect
+    }
+
+    /// `scaled_rect` for something moving here from `from`, drawn `step` of the way, from
+    /// `0.0` still at `from` to `1.0` here. Moves off one edge and back in on the other
+    /// aren't slid, since that would be the long way across the board.
+    pub fn slid_rect(self, from: GridPosition, step: f32, scale: f32) -> graphics::Rect {
+        let mut rect = self.scaled_rect(scale);
+        let (dx, dy) = (from.x - self.x, from.y - self.y);
+        if dx.abs() <= 1 && dy.abs() <= 1 {
+            rect.x += dx as f32 * GRID_CELL_SIZE.0 as f32 * (1.0 - step);
+            rect.y += dy as f32 * GRID_CELL_SIZE.1 as f32 * (1.0 - step);
+        }
+        rect
+    }
+}
+
+impl From<(i16, i16)> for GridPosition {
+    fn from(pos: (i16, i16)) -> Self {
+        GridPosition { x: pos.0, y: pos.1 }
+    }
+}
+
+/// The diagonals are only reachable in the experimental 8-direction mode (`--diagonals`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::Right,
+        Direction::Left,
+        Direction::Down,
+        Direction::Up,
+    ];
+
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    /// Every direction, the straight ones first.
+    pub fn all() -> impl Iterator<Item = Direction> {
+        Direction::CARDINAL.into_iter().chain(Direction::DIAGONAL)
+    }
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        !Direction::CARDINAL.contains(&self)
+    }
+
+    /// How one step in this direction changes the x and y of a grid position.
+    pub fn offset(self) -> (i16, i16) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    pub fn from_offset(offset: (i16, i16)) -> Option<Self> {
+        Direction::all().find(|dir| dir.offset() == offset)
+    }
+
+    /// The directions at right angles to this one. A diagonal is split into the two
+    /// straight directions it's made of instead.
+    pub fn perpendicular(self) -> [Direction; 2] {
+        let (dx, dy) = self.offset();
+        let (a, b) = if self.is_diagonal() {
+            ((dx, 0), (0, dy))
+        } else {
+            ((dy, dx), (-dy, -dx))
+        };
+        [a, b].map(|offset| Direction::from_offset(offset).unwrap())
+    }
+
+    /// A quarter turn clockwise, as seen on the screen.
+    pub fn clockwise(self) -> Self {
+        let (dx, dy) = self.offset();
+        Direction::from_offset((-dy, dx)).unwrap()
+    }
+
+    /// A unit vector pointing in this direction, in screen coordinates.
+    pub fn vector(self) -> Vec2 {
+        let (dx, dy) = self.offset();
+        Vec2::new(dx as f32, dy as f32).normalize()
+    }
+
+    /// Each direction gets its own pitch so turns can be told apart by ear.
+    pub fn turn_pitch(self) -> f32 {
+        match self {
+            Direction::Up => 1.25,
+            Direction::Down => 0.8,
+            Direction::Left => 0.9,
+            Direction::Right => 1.1,
+            Direction::UpLeft => 1.15,
+            Direction::UpRight => 1.35,
+            Direction::DownLeft => 0.7,
+            Direction::DownRight => 0.85,
+        }
+    }
+}
+
+impl std::str::FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            "up-left" => Ok(Direction::UpLeft),
+            "up-right" => Ok(Direction::UpRight),
+            "down-left" => Ok(Direction::DownLeft),
+            "down-right" => Ok(Direction::DownRight),
+            _ => Err(format!("unknown direction `{s}`")),
+        }
+    }
+}
+
+/// What happens when the snake reaches the edge of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum WrapMode {
+    /// Come back in on the opposite side.
+    #[default]
+    Wrap,
+    /// The edges are walls, and running into one ends the game.
+    Walls,
+    /// The snake comes off the edges like a ball off a cushion.
+    Bounce,
+}
+
+impl WrapMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::Wrap => "wrap",
+            WrapMode::Walls => "walls",
+            WrapMode::Bounce => "bounce",
+        }
+    }
+}
+
+impl std::str::FromStr for WrapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(WrapMode::Wrap),
+            "walls" => Ok(WrapMode::Walls),
+            "bounce" => Ok(WrapMode::Bounce),
+            _ => Err(format!("unknown wrap mode `{s}`")),
+        }
+    }
+}
+
+/// Which way it goes when a single move takes the head onto the food and into the snake's own
+/// body, or its reflection with `--mirror`, at once.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum EventPriority {
+    /// The snake runs into itself and the food goes uneaten.
+    #[default]
+    CollisionFirst,
+    /// The snake eats the food and lives, lying across its own body for that move.
+    FoodFirst,
+}
+
+impl std::str::FromStr for EventPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "collision-first" => Ok(EventPriority::CollisionFirst),
+            "food-first" => Ok(EventPriority::FoodFirst),
+            _ => Err(format!("unknown event priority `{s}`")),
+        }
+    }
+}
+
+/// The parts of the configuration that change how the snake moves and dies.
+#[derive(Clone, Copy, Debug, Default)]
+struct Rules {
+    wrap_mode: WrapMode,
+    /// In walls mode, turn along a wall instead of running head first into it.
+    wall_assist: bool,
+    /// In wrap mode, ticks after a wrap during which the edges are walls.
+    wrap_cooldown: u32,
+    /// Moves after eating during which the snake passes through its own body unharmed.
+    grace_period: u32,
+    /// A reflection of the snake across the middle of the grid is just as deadly as the snake.
+    mirror: bool,
+    /// Turning straight back is let through, and the snake runs into its own neck, instead of
+    /// being ignored.
+    reverse_kills: bool,
+    event_priority: EventPriority,
+}
+
+impl Rules {
+    /// Whether turning to `dir` while heading `current` gets ignored as a reversal.
+    fn blocks_reversal(self, dir: Direction, current: Direction) -> bool {
+        !self.reverse_kills && dir.inverse() == current
+    }
+}
+
+/// How key presses turn into changes of the snake's direction.
+///
+/// `Queued` is the safe default: a second turn within the same tick is buffered for the
+/// following tick, so quick combos like left-then-up never get lost or turn the snake into
+/// itself. `Immediate` always applies the latest legal key straight away, which feels snappier
+/// but means a quick second press replaces the first instead of following it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum InputMode {
+    #[default]
+    Queued,
+    Immediate,
+}
+
+impl std::str::FromStr for InputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(InputMode::Queued),
+            "immediate" => Ok(InputMode::Immediate),
+            _ => Err(format!("unknown input mode `{s}`")),
+        }
+    }
+}
+
+/// How each tick takes the turns `InputMode::Queued` has buffered. At very high tick rates a
+/// frame runs several ticks, and every key pressed since the last frame is in before the first
+/// of them, so spending a tick on each turn can feel laggy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum TurnDrain {
+    /// One buffered turn a tick, in the order they were pressed.
+    #[default]
+    OneEachTick,
+    /// Skip to the most recent buffered turn that isn't straight back, dropping any before it.
+    Latest,
+}
+
+impl std::str::FromStr for TurnDrain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one-each-tick" => Ok(TurnDrain::OneEachTick),
+            "latest" => Ok(TurnDrain::Latest),
+            _ => Err(format!("unknown turn drain `{s}`")),
+        }
+    }
+}
+
+/// How many points a piece of food is worth.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Scoring {
+    /// One point each.
+    #[default]
+    Flat,
+    /// One point, plus one more for every `cells_per_point` cells between this food and the
+    /// previous one, to reward going after food that's far away.
+    Distance { cells_per_point: u32 },
+}
+
+impl Scoring {
+    fn points(self, distance: u32) -> u32 {
+        match self {
+            Scoring::Flat => 1,
+            Scoring::Distance { cells_per_point } => 1 + distance / cells_per_point,
+        }
+    }
+}
+
+impl std::str::FromStr for Scoring {
+    type Err = String;
+
+    /// `flat`, `distance`, or `distance:N` for a point every `N` cells (5 by default).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, cells) = match s.split_once(':') {
+            Some((name, cells)) => (name, Some(cells)),
+            None => (s, None),
+        };
+        match (name, cells) {
+            ("flat", None) => Ok(Scoring::Flat),
+            ("distance", cells) => {
+                let cells_per_point = match cells {
+                    Some(cells) => cells
+                        .parse()
+                        .ok()
+                        .filter(|&cells| cells > 0)
+                        .ok_or_else(|| format!("invalid cells per point `{cells}`"))?,
+                    None => 5,
+                };
+                Ok(Scoring::Distance { cells_per_point })
+            }
+            _ => Err(format!("unknown scoring `{s}`")),
+        }
+    }
+}
+
+/// How the game speeds up as the snake fills the board, from the starting speed up to
+/// `MAX_FPS` once there's no room left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SpeedCurve {
+    /// The starting speed the whole game.
+    #[default]
+    Constant,
+    Linear,
+    /// Slow for most of the game, then a rush at the end.
+    Exponential,
+    /// A jump in speed every quarter of the board.
+    Stepped,
+    /// Gently at first, then faster and faster.
+    EaseIn,
+}
+
+impl SpeedCurve {
+    /// The tick rate once `progress` of the way to a full board (`0.0` to `1.0`), starting
+    /// out at `start`.
+    fn fps_for(self, progress: f32, start: u32) -> u32 {
+        let p = progress.clamp(0.0, 1.0);
+        let shape = match self {
+            SpeedCurve::Constant => 0.0,
+            SpeedCurve::Linear => p,
+            SpeedCurve::Exponential => (4.0 * p).exp_m1() / 4.0_f32.exp_m1(),
+            SpeedCurve::Stepped => (p * 4.0).floor() / 4.0,
+            SpeedCurve::EaseIn => p * p,
+        };
+        let start = start.min(MAX_FPS);
+        start + ((MAX_FPS - start) as f32 * shape).round() as u32
+    }
+}
+
+impl std::str::FromStr for SpeedCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "constant" => Ok(SpeedCurve::Constant),
+            "linear" => Ok(SpeedCurve::Linear),
+            "exponential" => Ok(SpeedCurve::Exponential),
+            "stepped" => Ok(SpeedCurve::Stepped),
+            "ease-in" => Ok(SpeedCurve::EaseIn),
+            _ => Err(format!("unknown speed curve `{s}`")),
+        }
+    }
+}
+
+/// Where new food tends to show up, relative to the snake's head.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum FoodBias {
+    /// Any free cell is as likely as any other.
+    #[default]
+    Uniform,
+    /// Close to the head, for an easier game.
+    Near,
+    /// Far from the head, for a harder one.
+    Far,
+}
+
+impl FoodBias {
+    /// How likely food is to go `distance` moves from the head, relative to other cells.
+    /// The weights go with the square of the distance, or of how much closer than the far
+    /// side of the board it is, so the bias is easy to notice.
+    fn weight(self, distance: u32) -> u32 {
+        match self {
+            FoodBias::Uniform => 1,
+            FoodBias::Near => {
+                let span = (GRID_SIZE.0 + GRID_SIZE.1) as u32;
+                span.saturating_sub(distance).pow(2)
+            }
+            FoodBias::Far => distance.pow(2),
+        }
+    }
+}
+
+impl std::str::FromStr for FoodBias {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(FoodBias::Uniform),
+            "near" => Ok(FoodBias::Near),
+            "far" => Ok(FoodBias::Far),
+            _ => Err(format!("unknown food bias `{s}`")),
+        }
+    }
+}
+
+/// The fewest moves between `a` and `b` without diagonals, taking the short way around the
+/// edges if the snake wraps.
+fn grid_distance(a: GridPosition, b: GridPosition, wrap_mode: WrapMode) -> u32 {
+    let axis = |from: i16, to: i16, size: i16| {
+        let straight = (from - to).unsigned_abs() as u32;
+        match wrap_mode {
+            WrapMode::Wrap => straight.min(size as u32 - straight),
+            WrapMode::Walls | WrapMode::Bounce => straight,
+        }
+    };
+    axis(a.x, b.x, GRID_SIZE.0) + axis(a.y, b.y, GRID_SIZE.1)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    pos: GridPosition,
+}
+
+impl Segment {
+    pub fn new(pos: GridPosition) -> Self {
+        Segment { pos }
+    }
+}
+
+/// What a piece of food does when it's eaten.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum FoodKind {
+    /// Blue: the snake grows by one.
+    #[default]
+    Standard,
+    /// Yellow: worth `BONUS_FOOD_MULTIPLIER` times the points, but the snake doesn't grow.
+    Bonus,
+    /// Pink: the snake doesn't grow, but for `POWER_TICKS` it can cross its own body, eating
+    /// everything from there to the tail for `POWER_POINTS_PER_SEGMENT` each.
+    Power,
+    /// Cyan: the snake doesn't grow, but goes back to where it was a few moves before, see
+    /// `rewind`.
+    Rewind,
+}
+
+/// How many times the usual points a piece of bonus food is worth.
+const BONUS_FOOD_MULTIPLIER: u32 = 3;
+/// How many times the usual points shrinking food is worth when it's eaten the moment it
+/// appears, see `Food::worth`.
+const SHRINKING_FOOD_MULTIPLIER: u32 = 5;
+/// How big shrinking food ends up, as a share of a full cell.
+const SHRUNK_FOOD_SCALE: f32 = 0.4;
+
+impl FoodKind {
+    fn grows(self) -> bool {
+        self == FoodKind::Standard
+    }
+
+    fn points(self, points: u32) -> u32 {
+        match self {
+            FoodKind::Standard => points,
+            FoodKind::Bonus => points * BONUS_FOOD_MULTIPLIER,
+            FoodKind::Power | FoodKind::Rewind => points,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Food {
+    pos: GridPosition,
+    kind: FoodKind,
+    /// Horizontal step taken each time the food moves, flipped when it bounces.
+    velocity: i16,
+    /// Ticks between each step; `0` keeps the food stationary.
+    move_interval: u32,
+    ticks_until_move: u32,
+    /// Where the food was before its last step, drawn faintly to telegraph its motion.
+    trail: Option<GridPosition>,
+    /// Ticks an uneaten piece of food lasts before it turns into an obstacle; `0` never decays.
+    decay_time: u32,
+    ticks_until_decay: u32,
+    /// Ticks a piece of food takes to shrink from its full worth down to a plain pellet; `0`
+    /// doesn't shrink.
+    shrink_time: u32,
+    /// Ticks since this piece of food appeared.
+    age: u32,
+}
+
+impl Food {
+    pub fn new(pos: GridPosition, move_interval: u32, decay_time: u32) -> Self {
+        Food {
+            pos,
+            kind: FoodKind::Standard,
+            velocity: 1,
+            move_interval,
+            ticks_until_move: move_interval,
+            trail: None,
+            decay_time,
+            ticks_until_decay: decay_time,
+            shrink_time: 0,
+            age: 0,
+        }
+    }
+
+    /// A fresh piece of food at `pos`, with the decay timer started over.
+    fn respawn(&mut self, pos: GridPosition, kind: FoodKind) {
+        self.pos = pos;
+        self.kind = kind;
+        self.trail = None;
+        self.ticks_until_decay = self.decay_time;
+        self.age = 0;
+    }
+
+    /// How much shrinking food has left to shrink, from `1.0` when it appears down to `0.0`
+    /// once it's a plain pellet. Food that doesn't shrink stays at `0.0`.
+    fn fullness(&self) -> f32 {
+        if self.shrink_time == 0 {
+            return 0.0;
+        }
+        1.0 - self.age.min(self.shrink_time) as f32 / self.shrink_time as f32
+    }
+
+    /// What food worth `points` as a plain pellet is worth now, up to
+    /// `SHRINKING_FOOD_MULTIPLIER` times that if it's shrinking and only just appeared.
+    fn worth(&self, points: u32) -> u32 {
+        let extra = points * (SHRINKING_FOOD_MULTIPLIER - 1);
+        points + (extra as f32 * self.fullness()).round() as u32
+    }
+
+    /// Counts down the decay timer, returning `true` on the tick it runs out.
+    fn decay(&mut self) -> bool {
+        if self.decay_time == 0 {
+            return false;
+        }
+        self.ticks_until_decay -= 1;
+        self.ticks_until_decay == 0
+    }
+
+    /// Moving food goes back and forth horizontally. It wraps around the edges of the grid when
+    /// the snake does and turns around at them otherwise, and turns around whenever the next
+    /// cell is taken by the snake or an obstacle, so it never lands on one.
+    fn update(
+        &mut self,
+        snake: &Snake,
+        swarm: &[Snake],
+        obstacles: &HashSet<GridPosition>,
+        wrap_mode: WrapMode,
+    ) {
+        self.age = self.age.saturating_add(1);
+        if self.move_interval == 0 {
+            return;
+        }
+        self.ticks_until_move -= 1;
+        if self.ticks_until_move > 0 {
+            return;
+        }
+        self.ticks_until_move = self.move_interval;
+
+        for velocity in [self.velocity, -self.velocity] {
+            let x = match wrap_mode {
+                WrapMode::Wrap => (self.pos.x + velocity).rem_euclid(GRID_SIZE.0),
+                WrapMode::Walls | WrapMode::Bounce => self.pos.x + velocity,
+            };
+            let next = GridPosition::new(x, self.pos.y);
+            if (0..GRID_SIZE.0).contains(&next.x)
+                && !snake.occupies(next)
+                && !swarm.iter().any(|snake| snake.occupies(next))
+                && !obstacles.contains(&next)
+            {
+                self.trail = Some(self.pos);
+                self.pos = next;
+                self.velocity = velocity;
+                return;
+            }
+        }
+        // Boxed in on both sides, so stay put until there's room again.
+        self.trail = None;
+    }
+
+    /// Decaying food goes duller and duller until it turns to stone, from `1.0` fresh.
+    fn freshness(&self) -> f32 {
+        if self.decay_time == 0 {
+            1.0
+        } else {
+            0.4 + 0.6 * self.ticks_until_decay as f32 / self.decay_time as f32
+        }
+    }
+
+    /// The color of a plain square of this food.
+    fn color(&self) -> [f32; 4] {
+        let freshness = self.freshness();
+        match self.kind {
+            FoodKind::Standard => [0.0, 0.0, freshness, 1.0],
+            FoodKind::Bonus => [freshness, freshness * 0.85, 0.0, 1.0],
+            FoodKind::Power => [freshness, freshness * 0.4, freshness * 0.8, 1.0],
+            FoodKind::Rewind => [0.0, freshness * 0.9, freshness, 1.0],
+        }
+    }
+
+    /// Note: this method of drawing does not scale. If you need to render
+    /// a large number of shapes, use an `InstanceArray`.
+    ///
+    /// With `arrow_to`, the food also shows an arrow pointing the shortest way to that head
+    /// position, going around the edges if the snake wraps.
+    ///
+    /// With `sprites`, the food is the sheet's frame for `now`, tinted the way the quad would
+    /// be coloured.
+    fn draw(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        painter: &CellPainter,
+        sprites: Option<&SpriteSheet>,
+        now: Duration,
+        arrow_to: Option<(GridPosition, WrapMode)>,
+    ) -> GameResult {
+        if let Some(trail) = self.trail {
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(painter.warp(trail.into()))
+                    .color([0.0, 0.0, 1.0, 0.25]),
+            );
+        }
+
+        let rect = painter.warp(self.pos.into());
+        let dest = painter.inset(rect);
+        let scale = if self.shrink_time == 0 {
+            1.0
+        } else {
+            SHRUNK_FOOD_SCALE + (1.0 - SHRUNK_FOOD_SCALE) * self.fullness()
+        };
+        let dest = graphics::Rect::new(
+            dest.x + dest.w * (1.0 - scale) / 2.0,
+            dest.y + dest.h * (1.0 - scale) / 2.0,
+            dest.w * scale,
+            dest.h * scale,
+        );
+        if let Some(sprites) = sprites {
+            let tint = match self.kind {
+                FoodKind::Standard => {
+                    let freshness = self.freshness();
+                    [freshness, freshness, freshness, 1.0]
+                }
+                _ => self.color(),
+            };
+            sprites.draw(canvas, dest, tint, now);
+        } else {
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(dest)
+                    .color(self.color()),
+            );
+        }
+
+        let Some((head, wrap_mode)) = arrow_to else {
+            return Ok(());
+        };
+        let shortest = |delta: i16, size: i16| match wrap_mode {
+            WrapMode::Wrap if delta > size / 2 => delta - size,
+            WrapMode::Wrap if delta < -size / 2 => delta + size,
+            _ => delta,
+        };
+        let towards = Vec2::new(
+            shortest(head.x - self.pos.x, GRID_SIZE.0) as f32,
+            shortest(head.y - self.pos.y, GRID_SIZE.1) as f32,
+        )
+        .normalize_or_zero();
+        if towards == Vec2::ZERO {
+            return Ok(());
+        }
+        let cell = Vec2::new(rect.w, rect.h);
+        let center = Vec2::from(rect.center());
+        let side = Vec2::new(-towards.y, towards.x);
+        let tip = center + towards * cell * 0.35;
+        let back = center - towards * cell * 0.2;
+        let arrow = graphics::Mesh::new_polygon(
+            ctx,
+            graphics::DrawMode::fill(),
+            &[tip, back + side * cell * 0.25, back - side * cell * 0.25],
+            graphics::Color::WHITE,
+        )?;
+        canvas.draw(&arrow, graphics::DrawParam::new());
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Ate {
+    Itself,
+    Food,
+    Wall,
+}
+
+/// Ticks that have to pass after the snake reverses before it can reverse again.
+const REVERSE_COOLDOWN: u32 = 8;
+
+/// Ticks that have to pass after the snake bites its tail before it can bite it again.
+const TAIL_BITE_COOLDOWN: u32 = 20;
+/// How long the pieces of a bitten off tail take to scatter.
+const TAIL_BITE_ANIMATION: Duration = Duration::from_millis(500);
+
+/// Cells of room ahead past which the snake isn't in any danger, see `GameState::tension`.
+const TENSION_RANGE: u32 = 6;
+
+/// How often segments get numbered when `L` turns the numbers on without `--segment-labels`.
+const DEFAULT_LABEL_EVERY: u32 = 5;
+
+/// How many steps of the way to the food the ghost hint shows.
+const GHOST_STEPS: usize = 5;
+
+/// How many cells the snake starts out with, head included.
+const INITIAL_LENGTH: usize = 2;
+
+/// A fresh snake must be able to go at least this far straight ahead before it hits anything.
+const SAFE_START_MOVES: usize = 3;
+
+/// Moves after coming back from a lost life during which nothing can kill the snake.
+const RESPAWN_INVULNERABILITY: u32 = 15;
+
+/// `--self-penalty`: running into the body takes the move back and costs `points` and up to
+/// `segments` off the tail, then the snake sits still for `STUN_TICKS` moves to turn away.
+/// Once the score can't cover the points, running into itself is the end as usual.
+#[derive(Clone, Copy, Debug)]
+struct SelfPenalty {
+    points: u32,
+    segments: u32,
+}
+
+const STUN_TICKS: u32 = 1;
+
+/// Moves after eating a power pellet during which the snake can cross its own body.
+const POWER_TICKS: u32 = 25;
+/// Points for each segment eaten while powered up.
+const POWER_POINTS_PER_SEGMENT: u32 = 2;
+/// The powered-up snake starts blinking when it has this many moves left.
+const POWER_WARNING: u32 = 8;
+
+/// The cells a snake of `len` starting at `head` facing `dir` would take up, head first,
+/// or `None` if it doesn't fit on an otherwise empty grid or couldn't make `SAFE_START_MOVES`
+/// straight ahead without dying.
+fn start_cells(
+    head: GridPosition,
+    dir: Direction,
+    len: usize,
+    wrap_mode: WrapMode,
+) -> Option<Vec<GridPosition>> {
+    start_cells_within(head, dir, len, wrap_mode, GRID_SIZE)
+}
+
+/// Like `start_cells`, on a grid of `grid` cells rather than the game's.
+fn start_cells_within(
+    head: GridPosition,
+    dir: Direction,
+    len: usize,
+    wrap_mode: WrapMode,
+    grid: (i16, i16),
+) -> Option<Vec<GridPosition>> {
+    let on_grid = (0..grid.0).contains(&head.x) && (0..grid.1).contains(&head.y);
+    let mut cells = on_grid.then(|| vec![head])?;
+    for _ in 1..len {
+        let behind =
+            GridPosition::moved_within(*cells.last().unwrap(), dir.inverse(), wrap_mode, grid)?;
+        if cells.contains(&behind) {
+            return None;
+        }
+        cells.push(behind);
+    }
+
+    // A straight snake that wraps only ever chases its own tail, so only walls are a concern.
+    let mut ahead = head;
+    for _ in 0..SAFE_START_MOVES {
+        ahead = GridPosition::moved_within(ahead, dir, wrap_mode, grid)?;
+    }
+    Some(cells)
+}
+
+/// Where to start a snake of `len` so it doesn't die right away. That's a quarter of the way
+/// in from the edge behind it if it fits, otherwise wherever there's the most room straight
+/// ahead. The snake faces `dir` if given; otherwise it prefers facing right but may face any
+/// way that fits.
+fn safe_start(
+    len: usize,
+    dir: Option<Direction>,
+    wrap_mode: WrapMode,
+    grid: (i16, i16),
+) -> GameResult<(GridPosition, Direction)> {
+    let preferred_dir = dir.unwrap_or(Direction::Right);
+    let (dx, dy) = preferred_dir.offset();
+    let quarter_in = |step: i16, size: i16| match step {
+        1 => size / 4,
+        -1 => size - 1 - size / 4,
+        _ => size / 2,
+    };
+    let preferred = GridPosition::new(quarter_in(dx, grid.0), quarter_in(dy, grid.1));
+    if start_cells_within(preferred, preferred_dir, len, wrap_mode, grid).is_some() {
+        return Ok((preferred, preferred_dir));
+    }
+
+    let room_ahead = |mut pos: GridPosition, dir: Direction| {
+        let mut room = 0;
+        while let Some(next) = GridPosition::moved_within(pos, dir, WrapMode::Walls, grid) {
+            pos = next;
+            room += 1;
+        }
+        room
+    };
+    (0..grid.1)
+        .flat_map(|y| (0..grid.0).map(move |x| GridPosition::new(x, y)))
+        .flat_map(|pos| {
+            let dirs = match dir {
+                Some(dir) => vec![dir],
+                None => Direction::CARDINAL.to_vec(),
+            };
+            dirs.into_iter().map(move |dir| (pos, dir))
+        })
+        .filter(|&(pos, dir)| start_cells_within(pos, dir, len, wrap_mode, grid).is_some())
+        .max_by_key(|&(pos, dir)| room_ahead(pos, dir))
+        .ok_or_else(|| {
+            let facing = dir.map_or(String::new(), |dir| format!(" facing {dir:?}"));
+            ggez::GameError::ConfigError(format!(
+                "a {len}-cell snake can't start safely{facing} on a {}x{} grid in {wrap_mode:?} mode",
+                grid.0, grid.1
+            ))
+        })
+}
+
+const OBSTACLE_COLOR: [f32; 4] = [0.35, 0.35, 0.35, 1.0];
+/// The colors the snake is drawn in.
+const SNAKE_BODY_COLOR: [f32; 4] = [0.3, 0.3, 0.0, 1.0];
+const SNAKE_HEAD_COLOR: [f32; 4] = [1.0, 0.5, 0.0, 1.0];
+/// The brightest the body gets where a pulse passes, see `Snake::draw`.
+const PULSE_COLOR: [f32; 4] = [0.8, 0.75, 0.15, 1.0];
+/// Segments from one pulse to the next.
+const PULSE_LENGTH: f32 = 8.0;
+/// Segments from one sway of the wobble to the next, see `Wobble`.
+const WOBBLE_LENGTH: f32 = 6.0;
+/// Segments behind the head the wobble takes to build up to its full width, so the neck
+/// stays joined on.
+const WOBBLE_RAMP: f32 = 3.0;
+/// How opaque the snake is while it can't bite itself, see `Rules::grace_period`.
+const GRACE_ALPHA: f32 = 0.55;
+/// The colors the snake turns while it's powered up, see `FoodKind::Power`.
+const POWERED_BODY_COLOR: [f32; 4] = [0.45, 0.1, 0.5, 1.0];
+const POWERED_HEAD_COLOR: [f32; 4] = [1.0, 0.35, 0.85, 1.0];
+
+/// A slither drawn into the body, each segment swaying from side to side a little after the
+/// one in front of it. Only the drawing moves, never the cells the snake is in.
+#[derive(Clone, Copy, Debug)]
+struct Wobble {
+    /// How far the body sways to either side, in cells.
+    amplitude: f32,
+    /// Sways a second.
+    frequency: f32,
+}
+
+/// How `Snake::draw` draws the snake, past its own colors.
+#[derive(Clone, Copy)]
+struct SnakeLook<'a> {
+    /// A pair of eyes on the head, glancing at the nearest of these food positions, or looking
+    /// straight ahead if there's none.
+    eyes: Option<&'a [GridPosition]>,
+    /// Bands of brighter color down the body, this many segments further along it than at the
+    /// start of the game.
+    pulse: Option<f32>,
+    /// Otherwise only the head is drawn.
+    body_visible: bool,
+    /// How far along its last move to draw the snake, `1.0` being right in its cells, see
+    /// `GridPosition::slid_rect`.
+    step: f32,
+    /// How many times its usual size the head is drawn, around the middle of its cell.
+    head_scale: f32,
+    wobble: Option<Wobble>,
+}
+
+/// Generic over how the body is stored so the back ends can be compared, see `bench`.
+#[derive(Clone)]
+struct Snake<B: Body = RingBuffer> {
+    head: Segment,
+    dir: Direction,
+    body: B,
+    ate: Option<Ate>,
+    last_update_dir: Direction,
+    /// Store the direction that will be used in the `update` after the next `update`
+    /// This is needed so a user can press two directions (eg. left then up)
+    /// before one `update` has happened. It sort of queues up key press input
+    next_dir: Option<Direction>,
+    /// Length (head included) past which eating no longer makes the snake grow.
+    max_length: Option<usize>,
+    /// When the snake last grew, so the new tail segment can scale in rather than pop into view.
+    grown_at: Option<Duration>,
+    /// Whether the head is right next to the body, not counting the neck, after the last update.
+    close_to_body: bool,
+    /// Whether the last update took the head off one edge and back in on the other.
+    wrapped: bool,
+    /// Moves left during which running into the body is let off, see `Rules::grace_period`.
+    grace_ticks: u32,
+    /// Moves left during which nothing hurts the snake, after losing a life.
+    invulnerable_ticks: u32,
+    /// Moves left during which the snake eats its own body instead of dying on it, after
+    /// eating a power pellet.
+    powered_ticks: u32,
+    /// Segments eaten off the tail in the last update, while powered up.
+    bitten: usize,
+    /// Moves left that the snake sits out, after `SelfPenalty` let it off running into itself.
+    stunned_ticks: u32,
+    /// Whether the last update left the snake where it was, stunned or taking a move back.
+    held: bool,
+    /// Where the tail was before the last update, unless the snake grew, for sliding it along.
+    last_tail: Option<GridPosition>,
+}
+
+impl<B: Body> Snake<B> {
+    /// Lays out the snake in a straight line behind `pos`. The caller is expected to have
+    /// checked that it fits, see `safe_start`.
+    pub fn new(
+        pos: GridPosition,
+        dir: Direction,
+        wrap_mode: WrapMode,
+        max_length: Option<usize>,
+    ) -> Self {
+        let mut body = B::with_capacity(GRID_SIZE.0 as usize * GRID_SIZE.1 as usize);
+        let cells = start_cells(pos, dir, INITIAL_LENGTH, wrap_mode)
+            .expect("The snake doesn't fit on the grid");
+        for &cell in cells[1..].iter().rev() {
+            body.push_front(Segment::new(cell));
+        }
+        Snake {
+            head: Segment::new(pos),
+            dir,
+            last_update_dir: dir,
+            body,
+            ate: None,
+            next_dir: None,
+            max_length,
+            grown_at: None,
+            close_to_body: false,
+            wrapped: false,
+            grace_ticks: 0,
+            invulnerable_ticks: 0,
+            powered_ticks: 0,
+            bitten: 0,
+            stunned_ticks: 0,
+            held: false,
+            last_tail: None,
+        }
+    }
+
+    fn eats_food(&self, food: Option<&Food>) -> bool {
+        food.is_some_and(|food| self.head.pos == food.pos)
+    }
+
+    fn len(&self) -> usize {
+        self.body.len() + 1
+    }
+
+    fn occupies(&self, pos: GridPosition) -> bool {
+        self.head.pos == pos || self.body.iter().any(|seg| seg.pos == pos)
+    }
+
+    fn eats_self(&self) -> bool {
+        for seg in self.body.iter() {
+            if self.head.pos == seg.pos {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Turn towards `dir` if that's a legal move. If a turn has already been made since the last
+    /// update, this one is queued up for the update after that.
+    fn steer(&mut self, dir: Direction, rules: Rules) {
+        if self.dir != self.last_update_dir && !rules.blocks_reversal(dir, self.dir) {
+            self.next_dir = Some(dir);
+        } else if !rules.blocks_reversal(dir, self.last_update_dir) {
+            self.dir = dir;
+        }
+    }
+
+    /// Takes the buffered turn over the one it was waiting on, as long as it's legal from the
+    /// way the snake last went, see `TurnDrain::Latest`.
+    fn skip_to_latest_turn(&mut self, rules: Rules) {
+        if let Some(next) = self.next_dir.take() {
+            if !rules.blocks_reversal(next, self.last_update_dir) {
+                self.dir = next;
+            }
+        }
+    }
+
+    /// Turn towards `dir` right away, dropping anything queued up. Reversals are still
+    /// checked against the direction of the last update.
+    fn steer_immediately(&mut self, dir: Direction, rules: Rules) {
+        if !rules.blocks_reversal(dir, self.last_update_dir) {
+            self.dir = dir;
+            self.next_dir = None;
+        }
+    }
+
+    /// Swaps the head and the tail, so the snake carries on the way it came from what used to
+    /// be its tail. Reversing twice puts everything back as it was.
+    fn reverse(&mut self, wrap_mode: WrapMode) {
+        let mut cells: Vec<Segment> = self.body.iter().copied().collect();
+        let Some(tail) = cells.pop() else {
+            return;
+        };
+        let mut body = B::with_capacity(GRID_SIZE.0 as usize * GRID_SIZE.1 as usize);
+        body.push_front(self.head);
+        for seg in cells {
+            body.push_front(seg);
+        }
+        let behind = body.iter().next().unwrap().pos;
+        self.dir = Direction::all()
+            .find(|&dir| GridPosition::moved(behind, dir, wrap_mode) == Some(tail.pos))
+            .unwrap_or(self.dir.inverse());
+        self.last_update_dir = self.dir;
+        self.next_dir = None;
+        self.head = tail;
+        self.body = body;
+        self.grown_at = None;
+    }
+
+    /// Takes `count` segments off the end of the tail, returning where they were, unless that
+    /// would leave the snake shorter than it started out.
+    fn bite_tail(&mut self, count: usize) -> Option<Vec<GridPosition>> {
+        if self.len() < INITIAL_LENGTH + count {
+            return None;
+        }
+        let bitten = (0..count)
+            .filter_map(|_| self.body.pop_back())
+            .map(|seg| seg.pos)
+            .collect();
+        self.grown_at = None;
+        Some(bitten)
+    }
+
+    /// Takes back the move that ran the head into the body, then takes up to `segments` off
+    /// the end of the tail, never leaving the snake shorter than it started out, and stuns it
+    /// for `STUN_TICKS` moves to turn away. Returns how many segments came off.
+    fn recoil(&mut self, segments: u32) -> u32 {
+        // Running into itself kept the tail, so the body is the old one behind the old head.
+        if let Some(neck) = self.body.pop_front() {
+            self.head = neck;
+        }
+        let mut dropped = 0;
+        while dropped < segments && self.len() > INITIAL_LENGTH {
+            self.body.pop_back();
+            dropped += 1;
+        }
+        self.grown_at = None;
+        self.stunned_ticks = STUN_TICKS;
+        self.held = true;
+        self.ate = None;
+        dropped
+    }
+
+    /// If the snake is about to run into a wall, turn towards whichever side has the most open
+    /// space straight ahead. Nothing changes if both sides are blocked too.
+    fn turn_along_wall(&mut self) {
+        if GridPosition::checked_move(self.head.pos, self.dir).is_some() {
+            return;
+        }
+        let room = |dir: Direction| {
+            let (mut pos, mut room) = (self.head.pos, 0);
+            while let Some(next) = GridPosition::checked_move(pos, dir) {
+                if self.body.iter().any(|seg| seg.pos == next) {
+                    break;
+                }
+                pos = next;
+                room += 1;
+            }
+            room
+        };
+        let best = self
+            .dir
+            .perpendicular()
+            .into_iter()
+            .filter(|&dir| dir.inverse() != self.last_update_dir)
+            .map(|dir| (dir, room(dir)))
+            .filter(|&(_, room)| room > 0)
+            .max_by_key(|&(_, room)| room);
+        if let Some((dir, _)) = best {
+            self.dir = dir;
+        }
+    }
+
+    /// If the snake is about to go off the grid, flips whichever of its moves across and down
+    /// takes it off, both of them in a corner. A snake going straight at an edge would bounce
+    /// right back into its own neck, so when the bounce isn't clear it turns along the edge
+    /// instead, and only runs into it if that's blocked too.
+    fn bounce(&mut self, obstacles: &HashSet<GridPosition>) {
+        let (dx, dy) = self.dir.offset();
+        let (x, y) = (self.head.pos.x + dx, self.head.pos.y + dy);
+        let flip = |to: i16, d: i16, size: i16| if (0..size).contains(&to) { d } else { -d };
+        let offset = (flip(x, dx, GRID_SIZE.0), flip(y, dy, GRID_SIZE.1));
+        if offset == (dx, dy) {
+            return;
+        }
+        let bounced = Direction::from_offset(offset).unwrap();
+        let clear = GridPosition::checked_move(self.head.pos, bounced).is_some_and(|pos| {
+            !obstacles.contains(&pos) && !self.body.iter().any(|seg| seg.pos == pos)
+        });
+        if clear {
+            self.dir = bounced;
+        } else {
+            self.turn_along_wall();
+        }
+    }
+
+    /// Moves the snake one cell. With `wrap_blocked`, going off an edge is as deadly as it is
+    /// in walls mode.
+    fn update(
+        &mut self,
+        food: Option<&Food>,
+        obstacles: &HashSet<GridPosition>,
+        rules: Rules,
+        wrap_blocked: bool,
+    ) {
+        self.held = self.stunned_ticks > 0;
+        if self.held {
+            self.stunned_ticks -= 1;
+            self.ate = None;
+            self.bitten = 0;
+            self.wrapped = false;
+            self.last_tail = None;
+            return;
+        }
+        if self.last_update_dir == self.dir && self.next_dir.is_some() {
+            self.dir = self.next_dir.unwrap();
+            self.next_dir = None;
+        }
+        if rules.wall_assist && rules.wrap_mode == WrapMode::Walls {
+            self.turn_along_wall();
+        }
+        if rules.wrap_mode == WrapMode::Bounce {
+            self.bounce(obstacles);
+        }
+        let invulnerable = self.invulnerable_ticks > 0;
+        self.invulnerable_ticks = self.invulnerable_ticks.saturating_sub(1);
+        let powered = self.powered_ticks > 0;
+        self.powered_ticks = self.powered_ticks.saturating_sub(1);
+        self.bitten = 0;
+
+        let in_bounds = GridPosition::moved(self.head.pos, self.dir, WrapMode::Walls);
+        self.wrapped = rules.wrap_mode == WrapMode::Wrap && in_bounds.is_none() && !wrap_blocked;
+        let new_head_pos = if wrap_blocked {
+            in_bounds
+        } else {
+            GridPosition::moved(self.head.pos, self.dir, rules.wrap_mode)
+        };
+        let Some(new_head_pos) = new_head_pos else {
+            // Ran into a wall, so the snake stays where it is.
+            self.ate = (!invulnerable).then_some(Ate::Wall);
+            self.last_update_dir = self.dir;
+            return;
+        };
+        let prev_len = self.len();
+        let graced = self.grace_ticks > 0;
+        self.grace_ticks = self.grace_ticks.saturating_sub(1);
+        let new_head = Segment::new(new_head_pos);
+        self.body.push_front(self.head);
+        self.head = new_head;
+
+        let mut bit_itself = self.eats_self() && !graced;
+        // Powered up, crossing the body eats it from there back, which leaves the cell free
+        // for the head. Everything but the neck can be eaten, so there's always a body left.
+        let crossed = self
+            .body
+            .iter()
+            .position(|seg| seg.pos == new_head_pos)
+            .filter(|&i| bit_itself && powered && i > 0);
+        if let Some(keep) = crossed {
+            while self.body.len() > keep {
+                self.body.pop_back();
+                self.bitten += 1;
+            }
+            bit_itself = false;
+        }
+        let bit_mirror = rules.mirror && self.occupies(new_head_pos.mirrored());
+        let collided = (bit_itself || bit_mirror) && !invulnerable;
+        let fed_first =
+            collided && rules.event_priority == EventPriority::FoodFirst && self.eats_food(food);
+        if collided && !fed_first {
+            self.ate = Some(Ate::Itself);
+        } else if obstacles.contains(&new_head_pos) && !invulnerable {
+            // Obstacles are as solid as the walls.
+            self.ate = Some(Ate::Wall);
+        } else if self.eats_food(food) {
+            self.ate = Some(Ate::Food);
+            self.grace_ticks = rules.grace_period;
+            if food.is_some_and(|food| food.kind == FoodKind::Power) {
+                self.powered_ticks = POWER_TICKS;
+            }
+        } else {
+            self.ate = None;
+        }
+
+        // The length cap has the final say over whether the tail is kept.
+        let under_cap = self.max_length.is_none_or(|max| self.len() <= max);
+        let grows = match self.ate {
+            Some(Ate::Food) => food.is_some_and(|food| food.kind.grows()),
+            ate => ate.is_some(),
+        };
+        // Eating the body already took the tail with it.
+        self.last_tail = None;
+        if (!grows || !under_cap) && self.bitten == 0 {
+            self.last_tail = self.body.pop_back().map(|seg| seg.pos);
+        }
+
+        let neighbors: Vec<GridPosition> = Direction::CARDINAL
+            .into_iter()
+            .filter_map(|dir| GridPosition::moved(self.head.pos, dir, rules.wrap_mode))
+            .collect();
+        self.close_to_body = self
+            .body
+            .iter()
+            .skip(1)
+            .any(|seg| neighbors.contains(&seg.pos));
+
+        self.last_update_dir = self.dir;
+        self.debug_check(prev_len, graced || invulnerable || fed_first);
+    }
+
+    /// Invariants every move has to keep, checked in debug builds only.
+    fn debug_check(&self, prev_len: usize, graced: bool) {
+        debug_assert!(
+            self.bitten == 0 || self.len() + self.bitten == prev_len + 1,
+            "the snake ate {} segments but went from {prev_len} to {} long",
+            self.bitten,
+            self.len()
+        );
+        debug_assert!(
+            matches!(self.ate, Some(Ate::Itself)) || graced || !self.eats_self(),
+            "the head at {:?} is inside the body without the snake having eaten itself",
+            self.head.pos
+        );
+        debug_assert!(
+            self.bitten > 0 || self.len() == prev_len || self.len() == prev_len + 1,
+            "the snake went from {prev_len} to {} long in a single move",
+            self.len()
+        );
+        debug_assert!(
+            self.body
+                .iter()
+                .map(|seg| seg.pos)
+                .chain([self.head.pos])
+                .all(|pos| (0..GRID_SIZE.0).contains(&pos.x) && (0..GRID_SIZE.1).contains(&pos.y)),
+            "part of the snake is off the grid"
+        );
+    }
+
+    /// larger scale games will likely need a more optimized render path
+    /// using `InstanceArray` or something similar that batches draw calls.
+    fn draw(
+        &self,
+        canvas: &mut graphics::Canvas,
+        painter: &CellPainter,
+        now: Duration,
+        look: &SnakeLook,
+    ) {
+        let SnakeLook {
+            eyes,
+            pulse,
+            body_visible,
+            step,
+            head_scale: swell,
+            wobble,
+        } = *look;
+        let tail_scale = match self.grown_at {
+            Some(grown_at) => {
+                ((now - grown_at).as_secs_f32() / GROWTH_ANIMATION.as_secs_f32()).min(1.0)
+            }
+            None => 1.0,
+        };
+        // Blinking back to normal just before the power runs out.
+        let powered = self.powered_ticks > POWER_WARNING || self.powered_ticks % 2 == 1;
+        let (body_color, head_color) = if powered {
+            (POWERED_BODY_COLOR, POWERED_HEAD_COLOR)
+        } else {
+            (SNAKE_BODY_COLOR, SNAKE_HEAD_COLOR)
+        };
+        // Each cell was where the one behind it is before the last move, and the tail where it
+        // left, unless the snake grew.
+        let cells: Vec<GridPosition> = self.body.iter().map(|seg| seg.pos).collect();
+        let from = |i: usize| {
+            cells
+                .get(i + 1)
+                .copied()
+                .or(self.last_tail)
+                .unwrap_or(cells[i])
+        };
+        // Sideways to the body where each segment is, going by the cells either side of it.
+        let sway = |i: usize| {
+            let Some(Wobble {
+                amplitude,
+                frequency,
+            }) = wobble
+            else {
+                return Vec2::ZERO;
+            };
+            let ahead = if i == 0 { self.head.pos } else { cells[i - 1] };
+            let behind = cells.get(i + 1).copied().unwrap_or(cells[i]);
+            // The short way round, where the body wraps.
+            let shortest = |delta: i16, size: i16| match delta {
+                delta if delta > size / 2 => delta - size,
+                delta if delta < -size / 2 => delta + size,
+                delta => delta,
+            };
+            let along = Vec2::new(
+                shortest(ahead.x - behind.x, GRID_SIZE.0) as f32,
+                shortest(ahead.y - behind.y, GRID_SIZE.1) as f32,
+            )
+            .normalize_or_zero();
+            let phase =
+                std::f32::consts::TAU * (frequency * now.as_secs_f32() - i as f32 / WOBBLE_LENGTH);
+            let ramp = ((i + 1) as f32 / WOBBLE_RAMP).min(1.0);
+            let cell = Vec2::new(GRID_CELL_SIZE.0 as f32, GRID_CELL_SIZE.1 as f32);
+            Vec2::new(-along.y, along.x) * cell * amplitude * ramp * phase.sin()
+        };
+        let body = self.body.iter().enumerate().filter(|_| body_visible);
+        for (i, seg) in body {
+            let scale = if i == self.body.len() - 1 {
+                tail_scale
+            } else {
+                1.0
+            };
+            let color = match pulse {
+                Some(offset) => {
+                    let phase = (i as f32 - offset) / PULSE_LENGTH * std::f32::consts::TAU;
+                    let t = 0.5 + 0.5 * phase.cos();
+                    let mut color = body_color;
+                    for (channel, bright) in color.iter_mut().zip(PULSE_COLOR) {
+                        *channel += (bright - *channel) * t;
+                    }
+                    color
+                }
+                None => body_color,
+            };
+            let mut rect = seg.pos.slid_rect(from(i), step, scale);
+            let offset = sway(i);
+            rect.x += offset.x;
+            rect.y += offset.y;
+            painter.draw(canvas, rect, self.color(color));
+        }
+
+        let head_scale = if painter.style() == RenderStyle::Circle {
+            1.2 * swell
+        } else {
+            swell
+        };
+        let head_rect = match cells.first() {
+            Some(&neck) => self.head.pos.slid_rect(neck, step, head_scale),
+            None => self.head.pos.scaled_rect(head_scale),
+        };
+        painter.draw(canvas, head_rect, self.color(head_color));
+        if let Some(food) = eyes {
+            self.draw_eyes(canvas, painter.warp(head_rect), food);
+        }
+    }
+
+    /// `color`, faded out while the grace period or the invulnerability after a lost life
+    /// is on.
+    fn color(&self, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+        let alpha = if self.grace_ticks > 0 || self.invulnerable_ticks > 0 {
+            GRACE_ALPHA
+        } else {
+            1.0
+        };
+        [r, g, b, a * alpha]
+    }
+
+    fn draw_eyes(
+        &self,
+        canvas: &mut graphics::Canvas,
+        head: graphics::Rect,
+        food: &[GridPosition],
+    ) {
+        let center = Vec2::from(head.center());
+        let nearest = food
+            .iter()
+            .min_by_key(|pos| (pos.x - self.head.pos.x).abs() + (pos.y - self.head.pos.y).abs());
+        let gaze = nearest
+            .map(|&pos| {
+                let target: graphics::Rect = pos.into();
+                (Vec2::from(target.center()) - center).normalize_or_zero()
+            })
+            .unwrap_or(Vec2::ZERO);
+
+        let forward = self.dir.vector();
+        let side = Vec2::new(-forward.y, forward.x);
+        let cell = Vec2::new(head.w, head.h);
+        let (eye_size, pupil_size) = (cell * 0.28, cell * 0.14);
+        for offset in [-0.2, 0.2] {
+            let eye = center + forward * cell * 0.15 + side * cell * offset;
+            let pupil = eye + gaze * (eye_size - pupil_size) / 2.0;
+            for (pos, size, color) in [
+                (eye, eye_size, graphics::Color::WHITE),
+                (pupil, pupil_size, graphics::Color::BLACK),
+            ] {
+                canvas.draw(
+                    &graphics::Quad,
+                    graphics::DrawParam::new()
+                        .dest_rect(graphics::Rect::new(
+                            pos.x - size.x / 2.0,
+                            pos.y - size.y / 2.0,
+                            size.x,
+                            size.y,
+                        ))
+                        .color(color),
+                );
+            }
+        }
+    }
+
+    /// Every so often flick a little tongue out the front of the head. The closer the food,
+    /// the more excited the snake gets and the more often it flicks.
+    fn draw_tongue(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        food: GridPosition,
+        now: Duration,
+    ) -> GameResult {
+        let distance = (self.head.pos.x - food.x).abs() + (self.head.pos.y - food.y).abs();
+        let period =
+            (TONGUE_PERIOD_MIN + TONGUE_PERIOD_PER_CELL * distance as f32).min(TONGUE_PERIOD_MAX);
+        if now.as_secs_f32() % period > TONGUE_FLICK.as_secs_f32() {
+            return Ok(());
+        }
+
+        let rect: graphics::Rect = self.head.pos.into();
+        let forward = self.dir.vector();
+        let side = Vec2::new(-forward.y, forward.x);
+        let cell = Vec2::new(rect.w, rect.h);
+        let base = Vec2::from(rect.center()) + forward * cell / 2.0;
+        let tip = base + forward * cell * 0.4;
+        let half_width = side * cell * 0.08;
+        let tongue = graphics::Mesh::new_polygon(
+            ctx,
+            graphics::DrawMode::fill(),
+            &[base - half_width, tip, base + half_width],
+            graphics::Color::from([0.8, 0.0, 0.1, 1.0]),
+        )?;
+        canvas.draw(&tongue, graphics::DrawParam::new());
+        Ok(())
+    }
+
+    /// Faint streaks trailing behind the head, more visible the closer `speed` (from 0 at the
+    /// normal tick rate up to 1 at the cap) gets to the top.
+    fn draw_speed_lines(&self, canvas: &mut graphics::Canvas, speed: f32) {
+        if speed <= 0.0 {
+            return;
+        }
+        let rect: graphics::Rect = self.head.pos.into();
+        let back = self.dir.inverse().vector();
+        let side = Vec2::new(-back.y, back.x);
+        let cell = Vec2::new(rect.w, rect.h);
+        let center = Vec2::from(rect.center());
+        let length = cell * (1.0 + 2.0 * speed);
+
+        for offset in [-0.3, 0.0, 0.3] {
+            let start = center + back * cell / 2.0 + side * cell * offset;
+            let end = start + back * length;
+            let min = start.min(end) - side.abs();
+            let max = start.max(end) + side.abs();
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(graphics::Rect::new(
+                        min.x,
+                        min.y,
+                        max.x - min.x,
+                        max.y - min.y,
+                    ))
+                    .color([1.0, 1.0, 1.0, 0.5 * speed]),
+            );
+        }
+    }
+
+    /// Numbers every `every`th segment with how many cells back from the head it is.
+    fn draw_labels(&self, canvas: &mut graphics::Canvas, style: &TextStyle, every: u32) {
+        let every = every as usize;
+        for (i, seg) in self.body.iter().enumerate() {
+            let distance = i + 1;
+            if distance % every != 0 {
+                continue;
+            }
+            let mut label = style.text(distance.to_string(), text::DEFAULT_SIZE * 0.75);
+            label.set_layout(graphics::TextLayout::center());
+            let rect: graphics::Rect = seg.pos.into();
+            canvas.draw(
+                &label,
+                graphics::DrawParam::new()
+                    .dest(rect.center())
+                    .color(graphics::Color::WHITE),
+            );
+        }
+    }
+
+    /// Flash any body segment the head is about to run into if it keeps going straight.
+    fn draw_danger_zone(&self, canvas: &mut graphics::Canvas, now: Duration) {
+        let flash_on =
+            now.as_millis() % DANGER_FLASH_PERIOD.as_millis() < DANGER_FLASH_PERIOD.as_millis() / 2;
+        if !flash_on {
+            return;
+        }
+
+        let mut pos = self.head.pos;
+        for _ in 0..DANGER_LOOKAHEAD {
+            pos = GridPosition::new_from_move(pos, self.dir);
+            if self.body.iter().any(|seg| seg.pos == pos) {
+                canvas.draw(
+                    &graphics::Quad,
+                    graphics::DrawParam::new()
+                        .dest_rect(pos.into())
+                        .color([1.0, 0.0, 0.0, 0.8]),
+                );
+            }
+        }
+    }
+}
+
+/// Things that happened during a logical update, for the presentation side to react to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GameEvent {
+    Turned(Direction),
+    AteFood {
+        grew: bool,
+        points: u32,
+        at: GridPosition,
+    },
+    /// A powered-up snake crossed its body at `at`, eating `segments` of it, see
+    /// `FoodKind::Power`.
+    AteBody {
+        segments: u32,
+        points: u32,
+        at: GridPosition,
+    },
+    /// The head just came up alongside the body without hitting it.
+    NearMiss {
+        bonus: u32,
+    },
+    /// Down turned, see `gravity`.
+    GravityTurned {
+        down: Direction,
+    },
+    /// The last pellet of a food chain was eaten, see `chain`.
+    ChainCompleted {
+        bonus: u32,
+    },
+    /// A pellet of a food chain was eaten before its turn.
+    ChainBroken {
+        penalty: u32,
+    },
+    /// A rewind pellet took the snake back `ticks` moves, see `rewind`.
+    Rewound {
+        ticks: usize,
+    },
+    /// The snake ran into itself but paid for it with `points` and `segments` of its tail,
+    /// see `SelfPenalty`.
+    Penalized {
+        points: u32,
+        segments: u32,
+    },
+    /// The snake died but had a life to spare, and is back somewhere safe.
+    LostLife {
+        lives_left: u32,
+    },
+    /// The snake died and the scenario started over, see `scenario`.
+    ScenarioReset {
+        attempt: u32,
+    },
+    Died,
+    /// The snake filled the whole board.
+    Won,
+}
+
+/// Picks a random cell that isn't `taken`, each with a chance in proportion to its `weight`,
+/// or `None` if the board is full. With every weight the same this draws from the RNG just
+/// as picking uniformly always has, so unbiased games play out the same as before. If every
+/// free cell weighs nothing the pick is uniform instead.
+fn random_free_cell(
+    rng: &mut Rand32,
+    taken: impl Fn(GridPosition) -> bool,
+    weight: impl Fn(GridPosition) -> u32,
+) -> Option<GridPosition> {
+    let free: Vec<(GridPosition, u32)> = (0..GRID_SIZE.1)
+        .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+        .filter(|&pos| !taken(pos))
+        .map(|pos| (pos, weight(pos)))
+        .collect();
+    if free.is_empty() {
+        return None;
+    }
+    let total: u32 = free.iter().map(|&(_, weight)| weight).sum();
+    if total == 0 {
+        return Some(free[rng.rand_range(0..free.len() as u32) as usize].0);
+    }
+    let mut pick = rng.rand_range(0..total);
+    for &(pos, weight) in &free {
+        if pick < weight {
+            return Some(pos);
+        }
+        pick -= weight;
+    }
+    unreachable!("the pick is less than the total weight")
+}
+
+/// How `random_free_cell` weighs a cell for food, going by `bias` and how far it is from
+/// `head`.
+fn food_weight(
+    bias: FoodBias,
+    head: GridPosition,
+    wrap_mode: WrapMode,
+) -> impl Fn(GridPosition) -> u32 {
+    move |pos| bias.weight(grid_distance(head, pos, wrap_mode))
+}
+
+/// Everything the simulation needs to pick up again from a given tick.
+#[derive(Clone)]
+struct Snapshot {
+    snake: Snake,
+    swarm: Vec<Snake>,
+    food: Food,
+    food_respawn_in: u32,
+    obstacles: HashSet<GridPosition>,
+    rng: Rand32,
+    food_sequence: VecDeque<GridPosition>,
+    score: u32,
+    lives: Option<u32>,
+    chain: Option<FoodChain>,
+    last_food: GridPosition,
+    gameover: bool,
+    won: bool,
+    tick: u64,
+    wrap_cooldown: u32,
+    gravity: Option<Gravity>,
+    ticks_since_steer: u32,
+    combo: Option<Combo>,
+    rewind: Option<Rewind>,
+    replay_cursor: Option<Cursor>,
+}
+
+/// The next spot from `sequence` that isn't `taken`, or a random free cell weighed by `weight`
+/// once the sequence runs out. Spots that are taken when their turn comes are skipped.
+fn next_food_cell(
+    sequence: &mut VecDeque<GridPosition>,
+    rng: &mut Rand32,
+    taken: impl Fn(GridPosition) -> bool,
+    weight: impl Fn(GridPosition) -> u32,
+) -> Option<GridPosition> {
+    while let Some(pos) = sequence.pop_front() {
+        if !taken(pos) {
+            return Some(pos);
+        }
+    }
+    random_free_cell(rng, taken, weight)
+}
+
+/// Whether food can't go at `pos` because something's already there.
+fn cell_taken(
+    pos: GridPosition,
+    snake: &Snake,
+    swarm: &[Snake],
+    obstacles: &HashSet<GridPosition>,
+    rules: Rules,
+) -> bool {
+    snake.occupies(pos)
+        || swarm.iter().any(|snake| snake.occupies(pos))
+        || obstacles.contains(&pos)
+        || (rules.mirror && snake.occupies(pos.mirrored()))
+}
+
+struct GameState {
+    snake: Snake,
+    /// The rest of the snakes steered along with `snake`, with `--swarm`.
+    swarm: Vec<Snake>,
+    food: Food,
+    /// Ticks until the next piece of food shows up. While this is counting down there's no
+    /// food on the board.
+    food_respawn_in: u32,
+    food_respawn_delay: u32,
+    /// How often a new piece of food is bonus food, from `0.0` to `1.0`.
+    bonus_food: f32,
+    /// How often a new piece of food that isn't bonus food is a power pellet.
+    power_pellets: f32,
+    /// The rewind pellets and what they go back to, with `--rewind-pellets`.
+    rewind: Option<Rewind>,
+    /// When the snake was last rewound, for fading out where it was.
+    rewound_at: Option<Duration>,
+    food_bias: FoodBias,
+    /// The rest of the food chain, with `--food-chain`.
+    chain: Option<FoodChain>,
+    /// Cells the snake dies on, left behind by food that decayed before it was eaten.
+    obstacles: HashSet<GridPosition>,
+    score: u32,
+    /// Lives left with `--lives`, counting the one being played.
+    lives: Option<u32>,
+    self_penalty: Option<SelfPenalty>,
+    scoring: Scoring,
+    /// Where the previous piece of food was eaten, or where the snake started.
+    last_food: GridPosition,
+    /// The longest the snake has been this game.
+    max_len: usize,
+    seed: u64,
+    started: Instant,
+    /// How long the game lasted, once it's over.
+    finished_after: Option<Duration>,
+    /// The top of the scoreboard when the game started, or of the day for a daily challenge.
+    best_score: u32,
+    /// The scoreboard as it was when the game started, for where the score would place.
+    scoreboard: scores::Scoreboard,
+    /// The day whose challenge this is, if it's one.
+    daily: Option<daily::Date>,
+    /// Logical steps played so far: the game's own clock, for anything that should happen
+    /// after so many ticks. Paused ticks and rendered frames don't count.
+    tick: u64,
+    gameover: bool,
+    won: bool,
+    paused: bool,
+    /// The gamepad the player last pressed a button on.
+    gamepad: Option<GamepadId>,
+    /// Set while the game is paused because that gamepad was disconnected.
+    gamepad_lost: bool,
+    /// Toggled with `H`; the help is also shown whenever the game is paused.
+    show_help: bool,
+    controls: Controls,
+    /// Toggled with `D`.
+    danger_zone: bool,
+    /// Toggled with `B`.
+    checkerboard: bool,
+    /// Toggled with `G`: column and row numbers along the edges, for level design.
+    coordinates: bool,
+    /// Toggled with `L`: every `label_every`th segment numbered with how far back it is.
+    show_segment_labels: bool,
+    label_every: u32,
+    checkerboard_cells: Option<graphics::InstanceArray>,
+    /// Segments a tick the body's color pulses travel, if they're on.
+    pulse: Option<f32>,
+    wobble: Option<Wobble>,
+    /// How big the head swells on eating, and for how long, if it does.
+    head_pulse: Option<f32>,
+    head_pulse_time: Duration,
+    /// When the head last swelled.
+    head_pulsed_at: Option<Duration>,
+    /// Lengths to celebrate the snake reaching.
+    milestones: Vec<usize>,
+    /// Only cells within this many moves of the head can be seen clearly.
+    fog: Option<u32>,
+    fog_cells: Option<graphics::InstanceArray>,
+    /// Whether the body is hidden, see `body_visible`.
+    invisible: bool,
+    /// How the snake slides between cells, with `--interpolate`.
+    easing: Option<Easing>,
+    /// When the last tick was played, see `draw_step`.
+    stepped_at: Option<Duration>,
+    /// When the hidden body was last shown for eating.
+    revealed_at: Option<Duration>,
+    /// Whether the heatmap of where the head has been is drawn. It's kept up either way.
+    show_heatmap: bool,
+    heatmap: Heatmap,
+    /// The decorative snakes of `--stress`, which are only drawn.
+    stress: Option<Stress>,
+    /// Toggled with `K`: the next few steps of the shortest way to the food, as a hint.
+    show_ghost: bool,
+    ghost_path: Vec<GridPosition>,
+    tongue: bool,
+    eyes: bool,
+    /// Point an arrow from the food towards the head.
+    food_arrow: bool,
+    speed_lines: bool,
+    /// How long a full day takes when the background cycles through the time of day.
+    day_cycle: Option<Duration>,
+    /// Logical updates per second, following `speed_curve` from `start_tick_rate`.
+    tick_rate: u32,
+    start_tick_rate: u32,
+    /// Slow down as the board fills, see `assisted`.
+    assist_slow: bool,
+    speed_curve: SpeedCurve,
+    /// The fastest the machine has shown it can keep up with, see `Performance`.
+    speed_cap: u32,
+    performance: Performance,
+    max_updates_per_frame: u32,
+    /// Let the AI steer instead of the keyboard.
+    autopilot: bool,
+    /// With near misses on, the points each one is worth.
+    near_miss_bonus: Option<u32>,
+    near_miss_at: Option<Duration>,
+    eat_cue: EatCue,
+    /// When the screen last flashed for eating, see `EatCue`.
+    eat_flash_at: Option<Duration>,
+    input_mode: InputMode,
+    turn_drain: TurnDrain,
+    rules: Rules,
+    /// Whether the diagonal keys steer, see `Direction`.
+    diagonals: bool,
+    /// Ticks until the snake may reverse again.
+    reverse_cooldown: u32,
+    /// Segments each tail bite takes off, if the snake can bite its tail.
+    tail_bite: Option<u32>,
+    /// Ticks until the snake may bite its tail again.
+    tail_bite_cooldown: u32,
+    /// Ticks until the snake may wrap around an edge again, see `Rules::wrap_cooldown`.
+    wrap_cooldown: u32,
+    /// Which way is down, if it turns, see `gravity`.
+    gravity: Option<Gravity>,
+    /// Turn the board to keep down at the bottom of the window.
+    rotate_view: bool,
+    /// When down last turned, to swing the view round.
+    gravity_turned_at: Option<Duration>,
+    /// Ticks since anything last steered the snake, for gravity to take over.
+    ticks_since_steer: u32,
+    /// The streak of quickly eaten food, with `--combo`.
+    combo: Option<Combo>,
+    painter: CellPainter,
+    /// The logical coordinates the window shows, see `viewport`.
+    screen: graphics::Rect,
+    /// How far the playfield is zoomed in, see `zoomed_view`. Remembered in `Settings`.
+    zoom: f32,
+    /// Where each part of the HUD goes, from `Settings`.
+    hud: HudLayout,
+    /// Samples per pixel the window was made with, kept in `Settings` for the next run.
+    msaa: u8,
+    /// Between playing and the game over screen, from `Settings`.
+    transition: Transition,
+    rng: Rand32,
+    /// Hand-picked spots for the next pieces of food, used up before falling back to random ones.
+    food_sequence: VecDeque<GridPosition>,
+    /// Sounds are only loaded when there's a window; headless games stay silent.
+    sound: Option<SoundPool>,
+    turn_sound: Option<audio::Source>,
+    /// The food's sprite sheet, if there's one in the resources.
+    food_sprites: Option<SpriteSheet>,
+    last_turn_sound: Option<Duration>,
+    music: Option<Music>,
+    /// Follow how close the snake is to danger with the music's pitch, see `Music`.
+    music_tension: bool,
+    /// How the music makes room for the eat sound.
+    ducking: Ducking,
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+    /// `--hashlog`, see `hashlog`.
+    hashlog: Option<HashLog>,
+    /// Stepping through a replay by hand instead of watching it play.
+    practice: Option<Practice>,
+    /// Racing someone over the network, see `net`.
+    versus: Option<Versus>,
+    /// Laying out obstacles instead of playing, see `editor`.
+    editor: Option<Editor>,
+    /// The speedrun timer, see `splits`.
+    splits: Option<Splits>,
+    /// `--record-gif`, see `capture`.
+    gif: Option<GifRecorder>,
+    /// The assets still to load before the game starts, see `loading`.
+    loading: Option<Loading>,
+    /// The board to go back to each time the snake dies, see `scenario`.
+    scenario: Option<ScenarioStart>,
+    /// The demo that plays on the game over screen, see `attract`.
+    attract: Option<Attract>,
+    particles: ParticleSystem,
+    floating_texts: FloatingTexts,
+    text_style: TextStyle,
+    /// Set when the snake dies. Until then it's falling apart, after that the game over
+    /// screen shows.
+    dying_until: Option<Duration>,
+}
+
+impl GameState {
+    pub fn new(ctx: &mut Context, config: Config) -> GameResult<Self> {
+        let loading = Loading::new(&config);
+        let practice = config.practice;
+        let msaa = config.msaa;
+        let (transition, transition_ms) = (config.transition, config.transition_ms);
+        let splits = config.splits;
+        let attract = config
+            .attract
+            .map(|seconds| Attract::new(Duration::from_secs_f32(seconds), config.clone()));
+        let gif = config
+            .record_gif
+            .as_deref()
+            .map(|path| GifRecorder::new(ctx, path))
+            .transpose()?;
+        let mut state = if config.resume {
+            GameState::load_game(config)?
+        } else {
+            GameState::new_headless(config)?
+        };
+        state.screen = viewport(ctx.gfx.drawable_size());
+        let settings = Settings::load()?;
+        state.zoom = settings.zoom.clamp(1.0, MAX_ZOOM);
+        state.hud = settings.hud;
+        state.msaa = msaa.unwrap_or(settings.msaa);
+        state.transition = Transition::new(
+            transition.unwrap_or(settings.transition),
+            Duration::from_millis(transition_ms.unwrap_or(settings.transition_ms).into()),
+        );
+        state.scoreboard = scores::Scoreboard::load()?;
+        state.best_score = match state.daily {
+            Some(date) => daily::best(date)?,
+            None => state.scoreboard.best(),
+        }
+        .unwrap_or(0);
+        state.splits = splits.map(Splits::new).transpose()?;
+        state.gif = gif;
+        state.attract = attract;
+        if practice {
+            state.practice = Some(Practice::new(&mut state));
+        }
+
+        state.loading = Some(loading);
+        Ok(state)
+    }
+
+    /// A game without window, sound or any other resources, driven purely through `step`.
+    pub fn new_headless(config: Config) -> GameResult<Self> {
+        let (snake_pos, snake_dir) = safe_start(
+            INITIAL_LENGTH,
+            config.start_direction,
+            config.wrap_mode,
+            GRID_SIZE,
+        )?;
+
+        let rules = config.replay_rules();
+        let player = match &config.replay {
+            Some(path) => Some(Player::new(ReplayFile::load(path)?, &rules)?),
+            None => None,
+        };
+        let seed = match &player {
+            Some(player) => player.seed(),
+            None => config.seed.unwrap_or_else(random_seed),
+        };
+        log::info!("event=game_start seed={seed} config={config:?}");
+        let recorder = config.record.map(|path| Recorder::new(path, seed, rules));
+        let hashlog = config.hashlog.as_deref().map(HashLog::create).transpose()?;
+        let mut rng = Rand32::new(seed);
+        let mut food_sequence = match &config.food_sequence {
+            Some(path) => level::load_food_sequence(path)?,
+            None => VecDeque::new(),
+        };
+        let scenario = config
+            .scenario
+            .as_deref()
+            .map(|path| scenario::load(path, config.wrap_mode, config.diagonals))
+            .transpose()?;
+
+        let mut swarm: Vec<Snake> = swarm::spread(
+            snake_pos,
+            snake_dir,
+            config.swarm.unwrap_or(1),
+            config.wrap_mode,
+        )?
+        .into_iter()
+        .map(|pos| Snake::new(pos, snake_dir, config.wrap_mode, config.max_length))
+        .collect();
+        let snake = swarm.remove(0);
+        let layout = config.edit.as_ref().filter(|path| path.exists());
+        let obstacles = match layout.or(config.obstacles.as_ref()) {
+            Some(path) => level::load_obstacles(path)?,
+            None => HashSet::new(),
+        };
+        if let Some(pos) = obstacles
+            .iter()
+            .find(|&&pos| snake.occupies(pos) || swarm.iter().any(|snake| snake.occupies(pos)))
+        {
+            return Err(GameError::ConfigError(format!(
+                "there's an obstacle at {},{}, where the snake starts",
+                pos.x, pos.y
+            )));
+        }
+        let rules = Rules {
+            wrap_mode: config.wrap_mode,
+            wall_assist: config.wall_assist,
+            wrap_cooldown: config.wrap_cooldown,
+            grace_period: config.grace_period,
+            mirror: config.mirror,
+            reverse_kills: config.reverse_kills,
+            event_priority: config.event_priority,
+        };
+        let food_pos = next_food_cell(
+            &mut food_sequence,
+            &mut rng,
+            |pos| cell_taken(pos, &snake, &swarm, &obstacles, rules),
+            food_weight(config.food_bias, snake.head.pos, rules.wrap_mode),
+        )
+        .expect("The grid is too small");
+
+        let mut state = GameState {
+            snake,
+            swarm,
+            food: Food {
+                shrink_time: config.shrinking_food,
+                ..Food::new(food_pos, config.food_move_interval, config.food_decay)
+            },
+            food_respawn_in: 0,
+            food_respawn_delay: config.food_respawn_delay,
+            obstacles,
+            score: 0,
+            lives: config.lives,
+            self_penalty: config.self_penalty.map(|points| SelfPenalty {
+                points,
+                segments: config.self_penalty_segments,
+            }),
+            scoring: config.scoring,
+            last_food: snake_pos,
+            max_len: INITIAL_LENGTH,
+            seed,
+            started: Instant::now(),
+            finished_after: None,
+            best_score: 0,
+            scoreboard: scores::Scoreboard::default(),
+            daily: config.daily,
+            tick: 0,
+            gameover: false,
+            won: false,
+            paused: false,
+            gamepad: None,
+            gamepad_lost: false,
+            show_help: false,
+            controls: Controls::new(
+                config.diagonals,
+                config.reversible,
+                config.tail_bite.is_some(),
+            ),
+            danger_zone: config.danger_zone,
+            checkerboard: config.checkerboard,
+            checkerboard_cells: None,
+            pulse: config.pulse,
+            wobble: config.wobble.map(|amplitude| Wobble {
+                amplitude,
+                frequency: config.wobble_hz,
+            }),
+            head_pulse: config.head_pulse,
+            head_pulse_time: Duration::from_millis(config.head_pulse_ms.into()),
+            head_pulsed_at: None,
+            milestones: config.milestones,
+            fog: config.fog,
+            invisible: config.invisible,
+            easing: config.interpolate.then_some(config.easing),
+            stepped_at: None,
+            revealed_at: None,
+            fog_cells: None,
+            show_heatmap: config.heatmap,
+            heatmap: Heatmap::default(),
+            stress: config.stress.map(Stress::new),
+            show_ghost: config.ghost,
+            ghost_path: Vec::new(),
+            coordinates: false,
+            show_segment_labels: config.segment_labels.is_some(),
+            label_every: config.segment_labels.unwrap_or(DEFAULT_LABEL_EVERY),
+            tongue: config.tongue,
+            eyes: config.eyes,
+            food_arrow: config.food_arrow,
+            speed_lines: config.speed_lines,
+            day_cycle: config.day_cycle.map(Duration::from_secs_f32),
+            tick_rate: config.speed.clamp(1, MAX_FPS),
+            start_tick_rate: config.speed.clamp(1, MAX_FPS),
+            assist_slow: config.assist_slow,
+            speed_curve: config.speed_curve,
+            speed_cap: MAX_FPS,
+            performance: Performance::default(),
+            max_updates_per_frame: config.max_updates_per_frame,
+            autopilot: config.autopilot,
+            near_miss_bonus: config.near_miss.then_some(config.near_miss_bonus),
+            near_miss_at: None,
+            eat_cue: config.eat_cue,
+            eat_flash_at: None,
+            input_mode: config.input_mode,
+            turn_drain: config.turn_drain,
+            rules,
+            diagonals: config.diagonals,
+            reverse_cooldown: 0,
+            tail_bite: config.tail_bite,
+            tail_bite_cooldown: 0,
+            wrap_cooldown: 0,
+            gravity: config.gravity.map(|seconds| {
+                let rate = config.speed.clamp(1, MAX_FPS) as f32;
+                Gravity::new(((seconds * rate).round() as u32).max(1))
+            }),
+            rotate_view: config.rotate_view,
+            gravity_turned_at: None,
+            ticks_since_steer: 0,
+            combo: config.combo.map(|seconds| {
+                let rate = config.speed.clamp(1, MAX_FPS) as f32;
+                Combo::new(((seconds * rate).round() as u32).max(1))
+            }),
+            painter: {
+                let mut painter = CellPainter::new(config.render_style, config.cell_padding);
+                painter.set_warp(config.warp.unwrap_or(0.0));
+                painter
+            },
+            screen: graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1),
+            zoom: 1.0,
+            hud: HudLayout::default(),
+            msaa: 1,
+            transition: Transition::new(TransitionStyle::None, Duration::ZERO),
+            rng,
+            food_sequence,
+            sound: None,
+            music: None,
+            music_tension: config.music_tension,
+            ducking: Ducking {
+                depth: config.duck,
+                recovery: Duration::from_millis(config.duck_recovery_ms.into()),
+            },
+            turn_sound: None,
+            food_sprites: None,
+            last_turn_sound: None,
+            recorder,
+            player,
+            hashlog,
+            practice: None,
+            versus: None,
+            editor: config.edit.map(Editor::new),
+            splits: None,
+            gif: None,
+            loading: None,
+            scenario: None,
+            attract: None,
+            particles: ParticleSystem::new(),
+            floating_texts: FloatingTexts::default(),
+            text_style: TextStyle::default(),
+            dying_until: None,
+            bonus_food: config.bonus_food,
+            power_pellets: config.power_pellets,
+            rewind: (config.rewind_pellets > 0.0).then(|| Rewind::new(config.rewind_pellets)),
+            rewound_at: None,
+            food_bias: config.food_bias,
+            chain: config.food_chain.map(FoodChain::new),
+        };
+        state.food.kind = state.next_food_kind();
+        state.lay_out_chain();
+        if let Some(scenario) = scenario {
+            state.set_up_scenario(scenario);
+        }
+        Ok(state)
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            snake: self.snake.clone(),
+            swarm: self.swarm.clone(),
+            food: self.food.clone(),
+            food_respawn_in: self.food_respawn_in,
+            obstacles: self.obstacles.clone(),
+            rng: self.rng,
+            food_sequence: self.food_sequence.clone(),
+            score: self.score,
+            lives: self.lives,
+            chain: self.chain.clone(),
+            last_food: self.last_food,
+            gameover: self.gameover,
+            won: self.won,
+            tick: self.tick,
+            wrap_cooldown: self.wrap_cooldown,
+            gravity: self.gravity.clone(),
+            ticks_since_steer: self.ticks_since_steer,
+            combo: self.combo.clone(),
+            rewind: self.rewind.clone(),
+            replay_cursor: self.player.as_ref().map(Player::cursor),
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.snake = snapshot.snake.clone();
+        self.swarm = snapshot.swarm.clone();
+        self.food = snapshot.food.clone();
+        self.food_respawn_in = snapshot.food_respawn_in;
+        self.obstacles = snapshot.obstacles.clone();
+        self.rng = snapshot.rng;
+        self.food_sequence = snapshot.food_sequence.clone();
+        self.score = snapshot.score;
+        self.lives = snapshot.lives;
+        self.chain = snapshot.chain.clone();
+        self.last_food = snapshot.last_food;
+        self.gameover = snapshot.gameover;
+        self.won = snapshot.won;
+        self.tick = snapshot.tick;
+        self.wrap_cooldown = snapshot.wrap_cooldown;
+        self.gravity = snapshot.gravity.clone();
+        self.ticks_since_steer = snapshot.ticks_since_steer;
+        self.combo = snapshot.combo.clone();
+        self.rewind = snapshot.rewind.clone();
+        if let (Some(player), Some(cursor)) = (&mut self.player, snapshot.replay_cursor) {
+            player.seek(cursor);
+        }
+    }
+
+    fn seek_practice(&mut self, tick: u64) {
+        if let Some(mut practice) = self.practice.take() {
+            practice.seek(self, tick);
+            self.practice = Some(practice);
+        }
+    }
+
+    /// In practice mode space or right steps forward a tick and left steps back one.
+    /// Returns whether the key was used for that.
+    fn practice_key(&mut self, key: KeyCode) -> bool {
+        let Some(tick) = self.practice.as_ref().map(Practice::tick) else {
+            return false;
+        };
+        match key {
+            KeyCode::Space | KeyCode::Right => self.seek_practice(tick + 1),
+            KeyCode::Left => self.seek_practice(tick.saturating_sub(1)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Runs a single logical update of the game.
+    fn step(&mut self) -> Vec<GameEvent> {
+        let (tick, was_over) = (self.tick, self.gameover);
+        let events = self.run_tick();
+        for &event in &events {
+            self.log_event(event);
+        }
+        // Saved once the tick is done, so the result matches what `--verify` sees.
+        if self.gameover && !was_over {
+            self.save_recording();
+        }
+        if self.hashlog.is_some() && self.tick != tick {
+            let hash = self.state_hash();
+            if let Some(hashlog) = &mut self.hashlog {
+                hashlog.record(self.tick, hash);
+            }
+        }
+        events
+    }
+
+    fn run_tick(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.gameover || self.paused {
+            return events;
+        }
+        self.tick += 1;
+
+        if let Some(player) = &mut self.player {
+            let dirs: Vec<Direction> = player.advance().iter().map(|input| input.dir).collect();
+            for dir in dirs {
+                self.steer(dir);
+            }
+        } else if self.autopilot {
+            if let Some(dir) = ai::next_direction(self.snake.head.pos) {
+                self.steer(dir);
+            }
+        }
+        // Here rather than in `update`, so replays and `--tui` drain the same way.
+        if self.turn_drain == TurnDrain::Latest {
+            let rules = self.rules;
+            for snake in self.snakes_mut() {
+                snake.skip_to_latest_turn(rules);
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.advance();
+        }
+        self.reverse_cooldown = self.reverse_cooldown.saturating_sub(1);
+        self.tail_bite_cooldown = self.tail_bite_cooldown.saturating_sub(1);
+        if let Some(gravity) = &mut self.gravity {
+            if gravity.tick() {
+                events.push(GameEvent::GravityTurned {
+                    down: gravity.down(),
+                });
+            }
+            // Heading straight up can't be turned into down, so it gets pulled round the
+            // long way, a quarter turn at a time.
+            if self.ticks_since_steer >= gravity::PULL_AFTER {
+                let down = gravity.down();
+                let rules = self.rules;
+                for snake in std::iter::once(&mut self.snake).chain(self.swarm.iter_mut()) {
+                    let pull = if snake.dir == down.inverse() {
+                        down.clockwise()
+                    } else {
+                        down
+                    };
+                    snake.steer(pull, rules);
+                }
+            }
+        }
+        self.ticks_since_steer += 1;
+        if let Some(combo) = &mut self.combo {
+            combo.tick();
+        }
+
+        let prev_dir = self.snake.last_update_dir;
+        if let Some(rewind) = &mut self.rewind {
+            let snakes = std::iter::once(&self.snake).chain(&self.swarm);
+            rewind.record(snakes.cloned().collect());
+        }
+        let prev_len = self.swarm_len();
+        let was_close = self.snake.close_to_body;
+        let food = (self.food_respawn_in == 0).then_some(&self.food);
+        let wrap_blocked = self.wrap_cooldown > 0;
+        self.snake
+            .update(food, &self.obstacles, self.rules, wrap_blocked);
+        let swarm_ate = swarm::update(
+            &mut self.swarm,
+            &self.snake,
+            food,
+            &self.obstacles,
+            self.rules,
+            wrap_blocked,
+        );
+        self.wrap_cooldown = self.wrap_cooldown.saturating_sub(1);
+        if self.snake.wrapped {
+            self.wrap_cooldown = self.rules.wrap_cooldown;
+        }
+        self.heatmap.record(self.snake.head.pos);
+        let head = self.snake.head.pos;
+        if let Some(chain) = self
+            .chain
+            .as_mut()
+            .filter(|chain| chain.upcoming.contains(&head))
+        {
+            chain.upcoming.clear();
+            self.score = self.score.saturating_sub(chain::PENALTY);
+            events.push(GameEvent::ChainBroken {
+                penalty: chain::PENALTY,
+            });
+            // A new chain follows the next piece of food, whenever that turns up.
+            if self.food_respawn_in == 0 {
+                self.respawn_food();
+            }
+        }
+        if self.snake.dir != prev_dir {
+            events.push(GameEvent::Turned(self.snake.dir));
+        }
+        if let Some(bonus) = self.near_miss_bonus {
+            if self.snake.ate.is_none() && self.snake.close_to_body && !was_close {
+                self.score += bonus;
+                events.push(GameEvent::NearMiss { bonus });
+            }
+        }
+        let crossings: Vec<(usize, GridPosition)> = self
+            .snakes()
+            .filter(|snake| snake.bitten > 0)
+            .map(|snake| (snake.bitten, snake.head.pos))
+            .collect();
+        for (segments, at) in crossings {
+            let segments = segments as u32;
+            let points = segments * POWER_POINTS_PER_SEGMENT;
+            self.score += points;
+            events.push(GameEvent::AteBody {
+                segments,
+                points,
+                at,
+            });
+        }
+        // Any snake dying ends the game, whichever of them ate.
+        let ate = match swarm_ate {
+            Some(Ate::Food) | None => self.snake.ate.or(swarm_ate),
+            died => died,
+        };
+        if let Some(ate) = ate {
+            match ate {
+                Ate::Food => {
+                    let at = self.food.pos;
+                    let distance = grid_distance(self.last_food, at, self.rules.wrap_mode);
+                    let multiplier = self.combo.as_mut().map_or(1, Combo::eat);
+                    let points = self.food.kind.points(self.scoring.points(distance));
+                    let points = self.food.worth(points) * multiplier;
+                    self.score += points;
+                    self.last_food = at;
+                    self.max_len = self.max_len.max(self.swarm_len());
+                    events.push(GameEvent::AteFood {
+                        grew: self.swarm_len() > prev_len,
+                        points,
+                        at,
+                    });
+                    if let Some(chain) = self
+                        .chain
+                        .as_ref()
+                        .filter(|chain| chain.upcoming.is_empty())
+                    {
+                        let bonus = chain.bonus();
+                        self.score += bonus;
+                        events.push(GameEvent::ChainCompleted { bonus });
+                    }
+                    if self.food.kind == FoodKind::Rewind {
+                        if let Some(ticks) = self.rewind_snakes() {
+                            events.push(GameEvent::Rewound { ticks });
+                        }
+                    }
+
+                    if self.food_respawn_delay > 0 && self.board_has_room() {
+                        self.food_respawn_in = self.food_respawn_delay;
+                    } else if !self.respawn_food() {
+                        self.end_game(true);
+                        events.push(GameEvent::Won);
+                        return events;
+                    }
+                }
+                Ate::Itself | Ate::Wall => {
+                    let penalty = self.self_penalty.filter(|penalty| {
+                        matches!(ate, Ate::Itself) && self.score > penalty.points
+                    });
+                    if let Some(penalty) = penalty {
+                        self.score -= penalty.points;
+                        let segments = self.snake.recoil(penalty.segments);
+                        events.push(GameEvent::Penalized {
+                            points: penalty.points,
+                            segments,
+                        });
+                    } else {
+                        if let Some(lives) = self.lives.filter(|&lives| lives > 1) {
+                            if self.respawn() {
+                                self.lives = Some(lives - 1);
+                                events.push(GameEvent::LostLife {
+                                    lives_left: lives - 1,
+                                });
+                                return events;
+                            }
+                        }
+                        if let Some(attempt) = self.retry_scenario() {
+                            events.push(GameEvent::ScenarioReset { attempt });
+                            return events;
+                        }
+                        self.end_game(false);
+                        events.push(GameEvent::Died);
+                        return events;
+                    }
+                }
+            }
+        } else if self.food_respawn_in > 0 {
+            self.food_respawn_in -= 1;
+            if self.food_respawn_in == 0 && !self.respawn_food() {
+                self.end_game(true);
+                events.push(GameEvent::Won);
+                return events;
+            }
+        } else if self.food.decay() {
+            self.obstacles.insert(self.food.pos);
+            // Nowhere left for food to go, and the board will never fill up.
+            if !self.respawn_food() {
+                self.end_game(false);
+                events.push(GameEvent::Died);
+                return events;
+            }
+        }
+        if self.food_respawn_in == 0 {
+            let wrap_mode = self.rules.wrap_mode;
+            self.food
+                .update(&self.snake, &self.swarm, &self.obstacles, wrap_mode);
+        }
+        events
+    }
+
+    /// Starts the snake over at its first length somewhere safe, keeping the score, or returns
+    /// `false` if there's nowhere safe left.
+    fn respawn(&mut self) -> bool {
+        let Some((pos, dir)) = self.safe_respawn() else {
+            return false;
+        };
+        self.snake = Snake::new(pos, dir, self.rules.wrap_mode, self.snake.max_length);
+        self.snake.invulnerable_ticks = RESPAWN_INVULNERABILITY;
+        if let Some(rewind) = &mut self.rewind {
+            rewind.history.clear();
+        }
+        self.wrap_cooldown = 0;
+        true
+    }
+
+    /// Where a new snake is as far as it can be from obstacles, and from the edges unless they
+    /// wrap, with room to go straight ahead and nothing in the way. Ties go to the middle.
+    /// Bounce edges count too: a bounce that's blocked runs the snake into the edge.
+    fn safe_respawn(&self) -> Option<(GridPosition, Direction)> {
+        let wrap_mode = self.rules.wrap_mode;
+        let food = self.food_on_board().map(|food| food.pos);
+        let blocked = |pos: GridPosition| self.obstacles.contains(&pos) || Some(pos) == food;
+        let hazard_distance = |pos: GridPosition| {
+            let to_wall = match wrap_mode {
+                WrapMode::Wrap => u32::MAX,
+                WrapMode::Walls | WrapMode::Bounce => {
+                    let x = pos.x.min(GRID_SIZE.0 - 1 - pos.x);
+                    let y = pos.y.min(GRID_SIZE.1 - 1 - pos.y);
+                    x.min(y) as u32 + 1
+                }
+            };
+            self.obstacles
+                .iter()
+                .map(|&obstacle| grid_distance(pos, obstacle, wrap_mode))
+                .fold(to_wall, u32::min)
+        };
+        let middle = GridPosition::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2);
+        (0..GRID_SIZE.1)
+            .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+            .flat_map(|pos| Direction::CARDINAL.map(|dir| (pos, dir)))
+            .filter(|&(pos, dir)| {
+                let Some(cells) = start_cells(pos, dir, INITIAL_LENGTH, wrap_mode) else {
+                    return false;
+                };
+                let ahead = (0..SAFE_START_MOVES).scan(pos, |at, _| {
+                    *at = GridPosition::moved(*at, dir, wrap_mode)?;
+                    Some(*at)
+                });
+                let mirrored =
+                    self.rules.mirror && cells.iter().any(|cell| cells.contains(&cell.mirrored()));
+                !mirrored && !cells.iter().copied().chain(ahead).any(blocked)
+            })
+            .max_by_key(|&(pos, _)| {
+                (
+                    hazard_distance(pos),
+                    std::cmp::Reverse(grid_distance(pos, middle, wrap_mode)),
+                )
+            })
+    }
+
+    /// Runs the `due` ticks that piled up since the last frame, up to `max_updates_per_frame`
+    /// of them, and returns how many ran and how many were dropped. Each tick's events go to
+    /// `on_tick`, along with the tick and the longest the snake had been before it.
+    fn catch_up(
+        &mut self,
+        due: u32,
+        mut on_tick: impl FnMut(&mut Self, u64, usize, Vec<GameEvent>),
+    ) -> (u32, u32) {
+        // Past the cap the rest of the time that piled up is just dropped.
+        let updates = due.min(self.max_updates_per_frame);
+        for _ in 0..updates {
+            let (tick, longest) = (self.tick, self.max_len);
+            let events = if self.versus.is_some() {
+                match self.step_versus() {
+                    Some(events) => events,
+                    None => continue,
+                }
+            } else {
+                self.step()
+            };
+            on_tick(self, tick, longest, events);
+            self.follow_speed_curve();
+        }
+        (updates, due - updates)
+    }
+
+    /// Caps the speed at `rate` ticks a second for the rest of the game, because the machine
+    /// can't keep up with anything faster.
+    fn slow_down(&mut self, rate: u32) {
+        log::warn!(
+            "event=slow_down rate={rate} kept_up={:.2} achieved={:.1}",
+            self.performance.ratio(),
+            self.performance.achieved_rate()
+        );
+        self.speed_cap = rate;
+        self.tick_rate = self.tick_rate.min(rate);
+        self.floating_texts.spawn(
+            format!("Too slow to keep up, slowing down to {rate} ticks/s"),
+            Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0),
+            graphics::Color::WHITE,
+        );
+    }
+
+    /// Sets the tick rate to what the speed curve says for how far the game has got.
+    fn follow_speed_curve(&mut self) {
+        let rate = self
+            .speed_curve
+            .fps_for(self.progress(), self.start_tick_rate);
+        self.tick_rate = self.assisted(rate).min(self.speed_cap);
+    }
+
+    /// With `--assist-slow`, `rate` brought down the fuller the board gets, by up to
+    /// `ASSIST_SLOWDOWN` once the snake takes up every cell the obstacles leave. It's applied
+    /// on top of the speed curve, so a curve that speeds up still does, only less so, and it
+    /// goes by how full the board is rather than how well the game is going.
+    fn assisted(&self, rate: u32) -> u32 {
+        if !self.assist_slow {
+            return rate;
+        }
+        let cells = GRID_SIZE.0 as usize * GRID_SIZE.1 as usize;
+        let free = cells.saturating_sub(self.obstacles.len()).max(1);
+        let fullness = (self.swarm_len() as f32 / free as f32).min(1.0);
+        ((rate as f32 * (1.0 - ASSIST_SLOWDOWN * fullness)).round() as u32).max(1)
+    }
+
+    /// How much of the board the snake has filled since it started out, from `0.0` to `1.0`.
+    fn progress(&self) -> f32 {
+        let room = GRID_SIZE.0 as usize * GRID_SIZE.1 as usize - INITIAL_LENGTH;
+        self.swarm_len().saturating_sub(INITIAL_LENGTH) as f32 / room as f32
+    }
+
+    /// How close the snake is to running into something if it keeps going straight, from
+    /// `0.0` with `TENSION_RANGE` cells or more to spare to `1.0` when it's right in front.
+    /// Sliding along its own body counts for half.
+    fn tension(&self) -> f32 {
+        let wrap_mode = if self.wrap_cooldown > 0 {
+            WrapMode::Walls
+        } else {
+            self.rules.wrap_mode
+        };
+        let mut pos = self.snake.head.pos;
+        let mut room = 0;
+        while room < TENSION_RANGE {
+            match GridPosition::moved(pos, self.snake.dir, wrap_mode) {
+                Some(next) if !self.snake.occupies(next) && !self.obstacles.contains(&next) => {
+                    pos = next;
+                    room += 1;
+                }
+                _ => break,
+            }
+        }
+        let ahead = 1.0 - room as f32 / TENSION_RANGE as f32;
+        if self.snake.close_to_body {
+            ahead.max(0.5)
+        } else {
+            ahead
+        }
+    }
+
+    /// Works out the hint of the way to the food again, if it's on. The tail is left out of
+    /// the way since it will have moved on by the time the head gets there.
+    fn update_ghost(&mut self) {
+        self.ghost_path.clear();
+        let Some(food) = self
+            .food_on_board()
+            .filter(|_| self.show_ghost && !self.gameover)
+        else {
+            return;
+        };
+        let body_len = self.snake.body.len();
+        let body: HashSet<GridPosition> = self
+            .snake
+            .body
+            .iter()
+            .take(body_len.saturating_sub(1))
+            .map(|seg| seg.pos)
+            .collect();
+        let blocked = |pos: GridPosition| {
+            body.contains(&pos)
+                || self.obstacles.contains(&pos)
+                || (self.rules.mirror && self.snake.occupies(pos.mirrored()))
+        };
+        if let Some(mut path) =
+            ai::path_to(self.snake.head.pos, food.pos, self.rules.wrap_mode, blocked)
+        {
+            path.truncate(GHOST_STEPS);
+            self.ghost_path = path;
+        }
+    }
+
+    /// The first steps of the way to the food, fading out the further along they are.
+    fn draw_ghost(&self, canvas: &mut graphics::Canvas) {
+        for (i, &pos) in self.ghost_path.iter().enumerate() {
+            let rect: graphics::Rect = pos.into();
+            let size = rect.w.min(rect.h) * 0.35;
+            let alpha = 0.4 * (1.0 - i as f32 / GHOST_STEPS as f32);
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(graphics::Rect::new(
+                        rect.center().x - size / 2.0,
+                        rect.center().y - size / 2.0,
+                        size,
+                        size,
+                    ))
+                    .color([1.0, 1.0, 1.0, alpha]),
+            );
+        }
+    }
+
+    /// The food, unless it's been eaten and the next piece hasn't shown up yet.
+    fn food_on_board(&self) -> Option<&Food> {
+        (self.food_respawn_in == 0 && !self.won).then_some(&self.food)
+    }
+
+    /// Puts out a fresh piece of food, returning `false` if there's nowhere to put it.
+    fn respawn_food(&mut self) -> bool {
+        let in_chain = self.chain.as_mut().and_then(|chain| {
+            let pos = chain.upcoming.pop_front()?;
+            chain.number += 1;
+            Some(pos)
+        });
+        let new_chain = in_chain.is_none();
+        match in_chain.or_else(|| self.next_food_cell()) {
+            Some(pos) => {
+                let kind = self.next_food_kind();
+                self.food.respawn(pos, kind);
+                if new_chain {
+                    self.lay_out_chain();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts a food chain with the food as its first pellet, placing the rest wherever food
+    /// would go. A board too full for all of them gets a shorter chain.
+    fn lay_out_chain(&mut self) {
+        let Some(chain) = &mut self.chain else {
+            return;
+        };
+        chain.number = 1;
+        chain.upcoming.clear();
+        let (snake, swarm, obstacles, rules, food) = (
+            &self.snake,
+            &self.swarm,
+            &self.obstacles,
+            self.rules,
+            self.food.pos,
+        );
+        for _ in 1..chain.length {
+            let upcoming = &chain.upcoming;
+            let Some(pos) = next_food_cell(
+                &mut self.food_sequence,
+                &mut self.rng,
+                |pos| {
+                    cell_taken(pos, snake, swarm, obstacles, rules)
+                        || pos == food
+                        || upcoming.contains(&pos)
+                },
+                food_weight(self.food_bias, snake.head.pos, rules.wrap_mode),
+            ) else {
+                break;
+            };
+            chain.upcoming.push_back(pos);
+        }
+    }
+
+    /// Standard food, or bonus food `bonus_food` of the time, or otherwise a power pellet
+    /// `power_pellets` of the time, or a rewind pellet as often as `rewind` says. The RNG is left alone for whichever never turns up, so
+    /// games without them play out just as they always have.
+    fn next_food_kind(&mut self) -> FoodKind {
+        if self.bonus_food > 0.0 && self.rng.rand_float() < self.bonus_food {
+            FoodKind::Bonus
+        } else if self.power_pellets > 0.0 && self.rng.rand_float() < self.power_pellets {
+            FoodKind::Power
+        } else if self
+            .rewind
+            .as_ref()
+            .is_some_and(|rewind| self.rng.rand_float() < rewind.chance)
+        {
+            FoodKind::Rewind
+        } else {
+            FoodKind::Standard
+        }
+    }
+
+    /// Whether there's any free cell left, without using up the RNG or the food sequence.
+    fn board_has_room(&self) -> bool {
+        (0..GRID_SIZE.1)
+            .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+            .any(|pos| !cell_taken(pos, &self.snake, &self.swarm, &self.obstacles, self.rules))
+    }
+
+    fn next_food_cell(&mut self) -> Option<GridPosition> {
+        let (snake, swarm, obstacles, rules) =
+            (&self.snake, &self.swarm, &self.obstacles, self.rules);
+        let upcoming = self.chain.as_ref().map(|chain| &chain.upcoming);
+        next_food_cell(
+            &mut self.food_sequence,
+            &mut self.rng,
+            |pos| {
+                cell_taken(pos, snake, swarm, obstacles, rules)
+                    || upcoming.is_some_and(|upcoming| upcoming.contains(&pos))
+            },
+            food_weight(self.food_bias, snake.head.pos, rules.wrap_mode),
+        )
+    }
+
+    /// Logs are silent unless enabled with e.g. `RUST_LOG=graphics=debug`.
+    fn log_event(&self, event: GameEvent) {
+        let (score, length) = (self.score, self.swarm_len());
+        match event {
+            GameEvent::Turned(dir) => log::trace!("event=turn dir={dir:?}"),
+            GameEvent::AteFood { grew, points, at } => log::debug!(
+                "event=food score={score} length={length} grew={grew} points={points} x={} y={}",
+                at.x,
+                at.y
+            ),
+            GameEvent::AteBody {
+                segments, points, ..
+            } => log::debug!(
+                "event=ate_body score={score} length={length} segments={segments} points={points}"
+            ),
+            GameEvent::NearMiss { bonus } => {
+                log::debug!("event=near_miss score={score} length={length} bonus={bonus}")
+            }
+            GameEvent::GravityTurned { down } => log::debug!("event=gravity down={down:?}"),
+            GameEvent::ChainCompleted { bonus } => {
+                log::debug!("event=chain_completed score={score} bonus={bonus}")
+            }
+            GameEvent::ChainBroken { penalty } => {
+                log::debug!("event=chain_broken score={score} penalty={penalty}")
+            }
+            GameEvent::Rewound { ticks } => {
+                log::debug!("event=rewound score={score} length={length} ticks={ticks}")
+            }
+            GameEvent::Penalized { points, segments } => log::debug!(
+                "event=penalized score={score} length={length} points={points} segments={segments}"
+            ),
+            GameEvent::LostLife { lives_left } => {
+                log::info!("event=lost_life score={score} lives_left={lives_left}")
+            }
+            GameEvent::ScenarioReset { attempt } => {
+                log::info!("event=scenario_reset attempt={attempt}")
+            }
+            GameEvent::Died => {
+                log::info!("event=game_over outcome=died score={score} length={length}")
+            }
+            GameEvent::Won => {
+                log::info!("event=game_over outcome=won score={score} length={length}")
+            }
+        }
+    }
+
+    fn steer(&mut self, dir: Direction) {
+        self.ticks_since_steer = 0;
+        let (input_mode, rules) = (self.input_mode, self.rules);
+        for snake in self.snakes_mut() {
+            match input_mode {
+                InputMode::Queued => snake.steer(dir, rules),
+                InputMode::Immediate => snake.steer_immediately(dir, rules),
+            }
+        }
+    }
+
+    fn handle_event(&mut self, ctx: &mut Context, event: GameEvent) {
+        match event {
+            GameEvent::Turned(dir) => self.play_turn_sound(ctx, dir),
+            GameEvent::AteFood { grew, points, at } => {
+                if self.eat_cue.sound() {
+                    let pitch = self.combo.as_ref().map_or(1.0, Combo::pitch);
+                    self.play_sound(ctx, pitch);
+                }
+                if self.eat_cue.flash() {
+                    self.eat_flash_at = Some(ctx.time.time_since_start());
+                }
+                if self.invisible {
+                    self.revealed_at = Some(ctx.time.time_since_start());
+                }
+                if self.head_pulse.is_some() {
+                    self.head_pulsed_at = Some(ctx.time.time_since_start());
+                }
+                if let Some(splits) = &mut self.splits {
+                    splits.record(ctx.time.time_since_start(), self.score);
+                }
+                if grew {
+                    let now = ctx.time.time_since_start();
+                    for snake in self.snakes_mut() {
+                        if matches!(snake.ate, Some(Ate::Food)) {
+                            snake.grown_at = Some(now);
+                        }
+                    }
+                }
+                if self.scoring != Scoring::Flat || points > 1 {
+                    let rect: graphics::Rect = at.into();
+                    self.floating_texts.spawn(
+                        format!("+{points}"),
+                        rect.center().into(),
+                        graphics::Color::WHITE,
+                    );
+                }
+            }
+            GameEvent::AteBody { points, at, .. } => {
+                let rect: graphics::Rect = at.into();
+                self.floating_texts.spawn(
+                    format!("Crunch! +{points}"),
+                    rect.center().into(),
+                    graphics::Color::from_rgb(255, 90, 220),
+                );
+            }
+            GameEvent::Rewound { .. } => {
+                self.rewound_at = Some(ctx.time.time_since_start());
+                self.play_sound(ctx, REWIND_PITCH);
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts.spawn(
+                    "Rewind!".to_string(),
+                    rect.center().into(),
+                    graphics::Color::from_rgb(0, 230, 255),
+                );
+            }
+            GameEvent::NearMiss { bonus } => {
+                self.near_miss_at = Some(ctx.time.time_since_start());
+                let label = match bonus {
+                    0 => "Close call!".to_string(),
+                    bonus => format!("Close call! +{bonus}"),
+                };
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts
+                    .spawn(label, rect.center().into(), graphics::Color::WHITE);
+            }
+            GameEvent::GravityTurned { .. } => {
+                self.gravity_turned_at = Some(ctx.time.time_since_start());
+            }
+            GameEvent::ChainCompleted { bonus } => {
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts.spawn(
+                    format!("Chain! +{bonus}"),
+                    rect.center().into(),
+                    graphics::Color::WHITE,
+                );
+            }
+            GameEvent::ChainBroken { penalty } => {
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts.spawn(
+                    format!("Out of order! -{penalty}"),
+                    rect.center().into(),
+                    graphics::Color::from_rgb(200, 0, 0),
+                );
+            }
+            GameEvent::Penalized { points, .. } => {
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts.spawn(
+                    format!("Ouch! -{points}"),
+                    rect.center().into(),
+                    graphics::Color::from_rgb(200, 0, 0),
+                );
+            }
+            GameEvent::LostLife { lives_left } => {
+                let label = match lives_left {
+                    1 => "Last life!".to_string(),
+                    lives => format!("{lives} lives left"),
+                };
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts
+                    .spawn(label, rect.center().into(), graphics::Color::WHITE);
+            }
+            GameEvent::ScenarioReset { attempt } => {
+                let rect: graphics::Rect = self.snake.head.pos.into();
+                self.floating_texts.spawn(
+                    format!("Attempt {attempt}"),
+                    rect.center().into(),
+                    graphics::Color::WHITE,
+                );
+            }
+            GameEvent::Died => {
+                self.finish_splits(ctx.time.time_since_start());
+                self.start_dying(ctx.time.time_since_start());
+            }
+            GameEvent::Won => self.finish_splits(ctx.time.time_since_start()),
+        }
+    }
+
+    /// Makes a show of the snake growing past one of the milestones, for the first time since
+    /// it was at most `longest` long: a flash, the eat sound pitched up and the length
+    /// floating up off the head.
+    fn celebrate_milestone(&mut self, ctx: &mut Context, longest: usize) {
+        let Some(length) = self
+            .milestones
+            .iter()
+            .copied()
+            .filter(|&length| longest < length && length <= self.max_len)
+            .max()
+        else {
+            return;
+        };
+        self.eat_flash_at = Some(ctx.time.time_since_start());
+        self.play_sound(ctx, MILESTONE_PITCH);
+        let rect: graphics::Rect = self.snake.head.pos.into();
+        self.floating_texts.spawn(
+            format!("Length {length}!"),
+            rect.center().into(),
+            graphics::Color::from_rgb(255, 215, 0),
+        );
+    }
+
+    /// Bites the end off the tail for a point a segment, scattering the bitten off pieces, or
+    /// says why not if the snake's too short.
+    fn bite_tail(&mut self) {
+        let Some(count) = self.tail_bite else {
+            return;
+        };
+        let head: graphics::Rect = self.snake.head.pos.into();
+        let Some(bitten) = self.snake.bite_tail(count as usize) else {
+            self.floating_texts.spawn(
+                "Too short to bite".to_string(),
+                head.center().into(),
+                graphics::Color::WHITE,
+            );
+            return;
+        };
+        self.score = self.score.saturating_sub(count);
+        self.tail_bite_cooldown = TAIL_BITE_COOLDOWN;
+        log::debug!("event=tail_bite segments={count} score={}", self.score);
+        for &pos in &bitten {
+            self.particles.burst(
+                pos.into(),
+                SNAKE_BODY_COLOR,
+                DEATH_PARTICLE_SPEED / 2.0,
+                TAIL_BITE_ANIMATION,
+            );
+        }
+        if let Some(&last) = bitten.last() {
+            let rect: graphics::Rect = last.into();
+            self.floating_texts.spawn(
+                format!("-{count}"),
+                rect.center().into(),
+                graphics::Color::from_rgb(200, 0, 0),
+            );
+        }
+    }
+
+    /// Input is ignored while the assets are loading and during a transition, see
+    /// `transition`.
+    fn input_held(&self, ctx: &Context) -> bool {
+        self.loading.is_some() || self.transition.active(ctx.time.time_since_start())
+    }
+
+    /// Input on the game over screen puts off the attract mode demo, or ends the one
+    /// that's playing, in which case the input is used up.
+    fn interrupt_attract(&mut self, ctx: &Context) -> bool {
+        let now = ctx.time.time_since_start();
+        match self.attract.as_mut().filter(|_| self.gameover) {
+            Some(attract) => attract.interrupt(now),
+            None => false,
+        }
+    }
+
+    fn finish_splits(&mut self, now: Duration) {
+        if let Some(splits) = &mut self.splits {
+            if let Err(e) = splits.finish(now) {
+                eprintln!("Could not save the splits: {e}");
+            }
+        }
+    }
+
+    /// Breaks the snake up into pieces that fall off the screen. The score was already
+    /// settled in `end_game`, so this is purely for show.
+    fn start_dying(&mut self, now: Duration) {
+        let body = self
+            .snake
+            .body
+            .iter()
+            .map(|seg| (seg.pos, SNAKE_BODY_COLOR));
+        for (pos, color) in body.chain([(self.snake.head.pos, SNAKE_HEAD_COLOR)]) {
+            self.particles
+                .burst(pos.into(), color, DEATH_PARTICLE_SPEED, DEATH_ANIMATION);
+        }
+        self.dying_until = Some(now + DEATH_ANIMATION);
+    }
+
+    fn draw_game_over(&self, canvas: &mut graphics::Canvas) {
+        let mut text = self
+            .text_style
+            .text(format!("Game over\n\nScore: {}", self.score), 32.0);
+        text.set_layout(graphics::TextLayout::center());
+        canvas.draw(
+            &text,
+            graphics::DrawParam::new()
+                .dest(Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0))
+                .color(graphics::Color::BLACK),
+        );
+    }
+
+    /// Lights the whole snake up white for a moment after a close call, fading out.
+    fn draw_near_miss_flash(&self, canvas: &mut graphics::Canvas, now: Duration) {
+        let Some(since) = self.near_miss_at.map(|at| now - at) else {
+            return;
+        };
+        if since >= NEAR_MISS_FLASH {
+            return;
+        }
+        let alpha = 0.8 * (1.0 - since.as_secs_f32() / NEAR_MISS_FLASH.as_secs_f32());
+        let cells = self.snake.body.iter().map(|seg| seg.pos);
+        for pos in cells.chain([self.snake.head.pos]) {
+            self.painter
+                .draw(canvas, pos.into(), [1.0, 1.0, 1.0, alpha]);
+        }
+    }
+
+    /// How far through to the next tick it is, eased, for drawing the snake part of the way
+    /// along its last move with `--interpolate`. It's counted from the last tick actually
+    /// played, so a game that isn't moving, paused or waiting on the other player, settles
+    /// right in its cells.
+    fn draw_step(&self, now: Duration) -> f32 {
+        match (self.easing, self.stepped_at) {
+            (Some(easing), Some(at)) if !self.gameover => {
+                easing.apply(now.saturating_sub(at).as_secs_f32() * self.tick_rate as f32)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// How much bigger than usual to draw the head `now`, swelling up and back down over
+    /// `head_pulse_time` after eating with `--head-pulse`.
+    fn head_scale(&self, now: Duration) -> f32 {
+        let (Some(scale), Some(at)) = (self.head_pulse, self.head_pulsed_at) else {
+            return 1.0;
+        };
+        let t = now.saturating_sub(at).as_secs_f32() / self.head_pulse_time.as_secs_f32();
+        // Which also covers a pulse that takes no time at all.
+        if !(0.0..1.0).contains(&t) {
+            return 1.0;
+        }
+        1.0 + (scale - 1.0) * (t * std::f32::consts::PI).sin()
+    }
+
+    /// Whether to draw the body. With `--invisible` it's only shown for a moment after eating,
+    /// as a reward, and once the game is over, to see what went wrong.
+    fn body_visible(&self, now: Duration) -> bool {
+        !self.invisible
+            || self.gameover
+            || self
+                .revealed_at
+                .is_some_and(|at| now.saturating_sub(at) < BODY_REVEAL)
+    }
+
+    /// Where the snake was before it got rewound, fading out.
+    fn draw_rewind_flash(&self, canvas: &mut graphics::Canvas, now: Duration) {
+        let (Some(since), Some(rewind)) = (self.rewound_at.map(|at| now - at), &self.rewind) else {
+            return;
+        };
+        if since >= REWIND_FLASH {
+            return;
+        }
+        let alpha = 0.6 * (1.0 - since.as_secs_f32() / REWIND_FLASH.as_secs_f32());
+        for &pos in &rewind.abandoned {
+            self.painter
+                .draw(canvas, pos.into(), [0.0, 0.9, 1.0, alpha]);
+        }
+    }
+
+    /// Darkens every cell further than `radius` moves from the head, thickening over the first
+    /// few cells past it.
+    fn draw_fog(&mut self, ctx: &Context, canvas: &mut graphics::Canvas, radius: u32) {
+        let head = self.snake.head.pos;
+        let wrap_mode = self.rules.wrap_mode;
+        let cells = self
+            .fog_cells
+            .get_or_insert_with(|| graphics::InstanceArray::new(ctx, None));
+        cells.set(
+            (0..GRID_SIZE.1)
+                .flat_map(|y| (0..GRID_SIZE.0).map(move |x| GridPosition::new(x, y)))
+                .filter_map(|pos| {
+                    let beyond = grid_distance(head, pos, wrap_mode).checked_sub(radius)?;
+                    let alpha = FOG_DARKNESS * (beyond as f32 / FOG_FALLOFF).min(1.0);
+                    (alpha > 0.0).then(|| {
+                        graphics::DrawParam::new()
+                            .dest_rect(pos.into())
+                            .color([0.0, 0.0, 0.0, alpha])
+                    })
+                }),
+        );
+        canvas.draw(cells, graphics::DrawParam::new());
+    }
+
+    /// Everything on the board and the HUD over it, with `screen` as the part of the world
+    /// the canvas covers when zoomed all the way out.
+    fn draw_scene(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &mut graphics::Canvas,
+        screen: graphics::Rect,
+        now: Duration,
+    ) -> GameResult {
+        let background = match self.day_cycle {
+            Some(cycle) => sky_color(now, cycle),
+            None => graphics::Color::from([0.0, 1.0, 0.0, 1.0]),
+        };
+        let view = self.world_view(screen);
+        canvas.set_screen_coordinates(view);
+        if let Some(gravity) = self.gravity.as_ref().filter(|_| self.rotate_view) {
+            let since_turn = self.gravity_turned_at.map(|at| now - at);
+            canvas.set_projection(
+                Mat4::from(canvas.projection())
+                    * rotated_view(view, gravity.view_angle(since_turn)),
+            );
+        }
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1))
+                .color(background),
+        );
+
+        if self.painter.warping() {
+            let head = &self.snake.head;
+            let neck = self
+                .snake
+                .body
+                .iter()
+                .next()
+                .map_or(head.pos, |seg| seg.pos);
+            let rect = head.pos.slid_rect(neck, self.draw_step(now), 1.0);
+            self.painter.warp_around(rect.center().into());
+        }
+        if self.checkerboard {
+            let cells = self
+                .checkerboard_cells
+                .get_or_insert_with(|| graphics::InstanceArray::new(ctx, None));
+            // Laid out once, unless the warp moves the squares about with the head.
+            if cells.instances().is_empty() || self.painter.warping() {
+                checkerboard(cells, &self.painter);
+            }
+            canvas.draw(cells, graphics::DrawParam::new());
+        }
+        if self.show_heatmap {
+            self.heatmap.draw(ctx, canvas);
+        }
+        if let Some(stress) = &mut self.stress {
+            stress.draw(ctx, canvas);
+        }
+        self.painter.prepare(ctx)?;
+        if self.speed_lines && !self.gameover {
+            let speed =
+                self.tick_rate.saturating_sub(DESIRED_FPS) as f32 / (MAX_FPS - DESIRED_FPS) as f32;
+            self.snake.draw_speed_lines(canvas, speed);
+        }
+        self.paint_obstacles(&mut CanvasCells {
+            canvas,
+            painter: &self.painter,
+        });
+        let food = self.food_on_board();
+        let visible_food: &[GridPosition] = match food {
+            Some(food) => std::slice::from_ref(&food.pos),
+            None => &[],
+        };
+        if self.dying_until.is_none() {
+            if self.rules.mirror {
+                self.draw_mirror(canvas);
+            }
+            if let Some(versus) = &self.versus {
+                self.draw_rival(canvas, versus.rival());
+            }
+            let body_visible = self.body_visible(now);
+            let look = SnakeLook {
+                eyes: self.eyes.then_some(visible_food),
+                pulse: self.pulse.map(|speed| self.tick as f32 * speed),
+                body_visible,
+                step: self.draw_step(now),
+                head_scale: self.head_scale(now),
+                wobble: self.wobble,
+            };
+            for snake in self.snakes() {
+                snake.draw(canvas, &self.painter, now, &look);
+            }
+            // Both of these would give away where the body is.
+            if body_visible {
+                self.draw_near_miss_flash(canvas, now);
+            }
+            self.draw_rewind_flash(canvas, now);
+            if self.show_segment_labels && body_visible {
+                self.snake
+                    .draw_labels(canvas, &self.text_style, self.label_every);
+            }
+        }
+        if self.show_ghost {
+            self.draw_ghost(canvas);
+        }
+        if let Some(food) = food.filter(|_| self.tongue && !self.gameover) {
+            self.snake.draw_tongue(ctx, canvas, food.pos, now)?;
+        }
+        if self.danger_zone {
+            self.snake.draw_danger_zone(canvas, now);
+        }
+        if let Some(food) = food {
+            let arrow_to = self
+                .food_arrow
+                .then_some((self.snake.head.pos, self.rules.wrap_mode));
+            food.draw(
+                ctx,
+                canvas,
+                &self.painter,
+                self.food_sprites.as_ref(),
+                now,
+                arrow_to,
+            )?;
+        }
+        if let Some(chain) = &self.chain {
+            chain.draw(
+                canvas,
+                &self.painter,
+                &self.text_style,
+                food.map(|food| food.pos),
+            );
+        }
+        self.particles.draw(canvas);
+        if let Some(radius) = self.fog {
+            self.draw_fog(ctx, canvas, radius);
+        }
+        self.floating_texts.draw(canvas, &self.text_style);
+        if self.coordinates {
+            self.draw_coordinates(canvas);
+        }
+        canvas.set_screen_coordinates(screen);
+        let food = self.food_on_board().map(|food| food.pos);
+        self.draw_food_radar(ctx, canvas, screen, view, food.as_slice())?;
+        self.draw_eat_flash(canvas, screen, now);
+        self.draw_hud(canvas, screen);
+        if self.dying_until.is_some_and(|until| now >= until) {
+            self.draw_game_over(canvas);
+        }
+        if let Some(practice) = &self.practice {
+            canvas.draw(
+                &self.text_style.text(
+                    format!("tick {} / {}", practice.tick(), practice.last_tick()),
+                    text::DEFAULT_SIZE,
+                ),
+                graphics::DrawParam::new()
+                    .dest(Vec2::new(8.0, 8.0))
+                    .color(graphics::Color::BLACK),
+            );
+        }
+        if self.gamepad_lost {
+            self.draw_gamepad_lost(canvas);
+        } else if self.show_help || self.paused {
+            self.draw_help(canvas);
+        }
+        if let Some(versus) = &self.versus {
+            self.draw_versus_status(canvas, versus);
+        }
+        if let Some(splits) = &self.splits {
+            splits.draw(
+                canvas,
+                &self.text_style,
+                now,
+                Vec2::new(SCREEN_SIZE.0 - 8.0, 8.0),
+            );
+        }
+        if let Some(editor) = &self.editor {
+            canvas.draw(
+                &self.text_style.text(
+                    format!(
+                        "Editing {}: click to place or remove obstacles, Ctrl+Z to undo, \
+                         Ctrl+Y to redo",
+                        editor.path().display()
+                    ),
+                    text::DEFAULT_SIZE,
+                ),
+                graphics::DrawParam::new()
+                    .dest(Vec2::new(8.0, 8.0))
+                    .color(graphics::Color::BLACK),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn draw_hud(&self, canvas: &mut graphics::Canvas, screen: graphics::Rect) {
+        let time = self
+            .finished_after
+            .unwrap_or_else(|| self.started.elapsed());
+        let mut lines = vec![
+            HudLine::new(HudElement::Score, format!("Score: {}", self.score)),
+            HudLine::new(
+                HudElement::Best,
+                format!("Best: {}", self.best_score.max(self.score)),
+            ),
+            HudLine::new(
+                HudElement::Time,
+                format!("Time: {}", splits::format_time(time)),
+            ),
+        ];
+        // Only games that go on the scoreboard have a place on it.
+        if self.player.is_none() && !self.autopilot {
+            let rank = match self.scoreboard.rank(self.score) {
+                Some(rank) => format!("Would be #{rank}"),
+                None => format!("Not in the top {}", scores::SCOREBOARD_SIZE),
+            };
+            lines.push(HudLine::new(HudElement::Rank, rank));
+        }
+        if let Some(lives) = self.lives {
+            lines.push(HudLine::new(HudElement::Lives, format!("Lives: {lives}")));
+        }
+        if let Some(combo) = self.combo.as_ref().filter(|combo| combo.active()) {
+            lines.push(
+                HudLine::new(HudElement::Combo, format!("Combo x{}", combo.multiplier()))
+                    .with_bar(combo.fraction_left()),
+            );
+        }
+        if let Some(stress) = &self.stress {
+            lines.push(HudLine::new(HudElement::Stress, stress.status()));
+        }
+        self.hud.draw(canvas, &self.text_style, screen, &lines);
+    }
+
+    /// Tints the whole window white for a moment after eating, fading out.
+    fn draw_eat_flash(&self, canvas: &mut graphics::Canvas, screen: graphics::Rect, now: Duration) {
+        let Some(since) = self.eat_flash_at.map(|at| now - at) else {
+            return;
+        };
+        if since >= EAT_FLASH {
+            return;
+        }
+        let alpha = 0.35 * (1.0 - since.as_secs_f32() / EAT_FLASH.as_secs_f32());
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(screen)
+                .color([1.0, 1.0, 1.0, alpha]),
+        );
+    }
+
+    fn paint_obstacles(&self, surface: &mut impl Surface) {
+        for &obstacle in &self.obstacles {
+            surface.fill(obstacle, OBSTACLE_COLOR);
+        }
+    }
+
+    /// The snake's reflection, in a ghostly color so it's clearly not the real thing.
+    fn draw_mirror(&self, canvas: &mut graphics::Canvas) {
+        let cells = self.snake.body.iter().map(|seg| seg.pos);
+        for pos in cells.chain([self.snake.head.pos]) {
+            self.painter
+                .draw(canvas, pos.mirrored().into(), [0.6, 0.6, 1.0, 0.7]);
+        }
+    }
+
+    /// The other player's snake, faintly, so it's clear which one is yours.
+    fn draw_rival(&self, canvas: &mut graphics::Canvas, rival: &GameState) {
+        if rival.gameover {
+            return;
+        }
+        let cells = rival.snake.body.iter().map(|seg| seg.pos);
+        for pos in cells.chain([rival.snake.head.pos]) {
+            self.painter.draw(canvas, pos.into(), [0.2, 0.3, 0.9, 0.45]);
+        }
+    }
+
+    /// Both scores along the top, or why the game stopped if the other player is gone.
+    fn draw_versus_status(&self, canvas: &mut graphics::Canvas, versus: &Versus) {
+        let rival = versus.rival();
+        let status = if !versus.connected() {
+            "The other player has left".to_string()
+        } else {
+            let outcome = |game: &GameState| match (game.gameover, game.won) {
+                (true, true) => " (won)",
+                (true, false) => " (out)",
+                _ => "",
+            };
+            format!(
+                "You: {}{}    Rival: {}{}",
+                self.score,
+                outcome(self),
+                rival.score,
+                outcome(rival)
+            )
+        };
+        let mut text = self.text_style.text(status, text::DEFAULT_SIZE);
+        text.set_layout(graphics::TextLayout::center());
+        canvas.draw(
+            &text,
+            graphics::DrawParam::new()
+                .dest(Vec2::new(SCREEN_SIZE.0 / 2.0, 16.0))
+                .color(graphics::Color::BLACK),
+        );
+    }
+
+    /// An arrow on the edge of `screen` for each of `foods` that's out of `view`, the part of
+    /// the playfield zoomed in on, pointing from the middle of the screen towards it.
+    fn draw_food_radar(
+        &self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        screen: graphics::Rect,
+        view: graphics::Rect,
+        foods: &[GridPosition],
+    ) -> GameResult {
+        let center = Vec2::from(screen.center());
+        let half = Vec2::new(screen.w, screen.h) / 2.0 - RADAR_MARGIN;
+        for &pos in foods {
+            let rect: graphics::Rect = pos.into();
+            let food = Vec2::from(rect.center());
+            if view.contains(food) {
+                continue;
+            }
+            let on_screen = Vec2::from(screen.point())
+                + (food - Vec2::from(view.point()))
+                    * Vec2::new(screen.w / view.w, screen.h / view.h);
+            let towards = on_screen - center;
+            let Some(dir) = towards.try_normalize() else {
+                continue;
+            };
+            // As far along that way as stays inside the margin on both axes.
+            let reach = (half / towards.abs()).min_element();
+            let tip = center + towards * reach;
+            let side = Vec2::new(-dir.y, dir.x);
+            let back = tip - dir * RADAR_ARROW_SIZE;
+            let arrow = graphics::Mesh::new_polygon(
+                ctx,
+                graphics::DrawMode::fill(),
+                &[
+                    tip,
+                    back + side * RADAR_ARROW_SIZE * 0.6,
+                    back - side * RADAR_ARROW_SIZE * 0.6,
+                ],
+                graphics::Color::WHITE,
+            )?;
+            canvas.draw(&arrow, graphics::DrawParam::new());
+        }
+        Ok(())
+    }
+
+    /// Numbers the columns along the top edge and the rows along the left one.
+    fn draw_coordinates(&self, canvas: &mut graphics::Canvas) {
+        let scale = GRID_CELL_SIZE.0.min(GRID_CELL_SIZE.1) as f32 * 0.45;
+        let columns = (0..GRID_SIZE.0).map(|x| GridPosition::new(x, 0));
+        let rows = (1..GRID_SIZE.1).map(|y| GridPosition::new(0, y));
+        for pos in columns.chain(rows) {
+            let label = if pos.y == 0 { pos.x } else { pos.y };
+            let mut text = self.text_style.text(label.to_string(), scale);
+            text.set_layout(graphics::TextLayout::center());
+            let rect: graphics::Rect = pos.into();
+            canvas.draw(
+                &text,
+                graphics::DrawParam::new()
+                    .dest(rect.center())
+                    .color([0.0, 0.0, 0.0, 0.7]),
+            );
+        }
+    }
+
+    /// The part of the playfield the window shows, zoomed in on the head.
+    fn world_view(&self, screen: graphics::Rect) -> graphics::Rect {
+        let head: graphics::Rect = self.snake.head.pos.into();
+        zoomed_view(screen, self.zoom, head.center().into())
+    }
+
+    /// The cell under the window position `x`, `y`, in physical pixels as mouse events give
+    /// them.
+    fn cell_at(&self, drawable: (f32, f32), x: f32, y: f32) -> Option<GridPosition> {
+        let view = self.world_view(self.screen);
+        let x = view.x + x / drawable.0 * view.w;
+        let y = view.y + y / drawable.1 * view.h;
+        let pos = GridPosition::new(
+            (x / GRID_CELL_SIZE.0 as f32).floor() as i16,
+            (y / GRID_CELL_SIZE.1 as f32).floor() as i16,
+        );
+        let on_grid = x >= 0.0 && y >= 0.0 && pos.x < GRID_SIZE.0 && pos.y < GRID_SIZE.1;
+        on_grid.then_some(pos)
+    }
+
+    /// Zooms in (or out, for a factor below one) as far as the bounds allow, and remembers
+    /// the new zoom for the next run.
+    fn zoom_by(&mut self, factor: f32) {
+        let zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        if zoom == self.zoom {
+            return;
+        }
+        self.zoom = zoom;
+        let settings = Settings {
+            zoom,
+            hud: self.hud,
+            msaa: self.msaa,
+            transition: self.transition.style,
+            transition_ms: self.transition.duration.as_millis() as u32,
+        };
+        if let Err(e) = settings.save() {
+            eprintln!("Could not save the zoom: {e}");
+        }
+    }
+
+    /// Pauses the game when the gamepad being played on goes away, and carries on once it's
+    /// back. A game that was paused already stays paused, and a network game can't pause.
+    fn check_gamepad(&mut self, ctx: &Context) {
+        let Some(id) = self.gamepad else {
+            return;
+        };
+        let connected = ctx.gamepad.gamepad(id).is_connected();
+        if !connected && !self.paused && !self.gameover && self.versus.is_none() {
+            log::info!("event=gamepad_lost");
+            self.paused = true;
+            self.gamepad_lost = true;
+        } else if connected && self.gamepad_lost {
+            log::info!("event=gamepad_back");
+            self.paused = false;
+            self.gamepad_lost = false;
+        }
+    }
+
+    /// Gives up on the lost gamepad and carries on with the keyboard.
+    fn play_on_keyboard(&mut self) {
+        self.gamepad = None;
+        self.gamepad_lost = false;
+        self.paused = false;
+    }
+
+    fn draw_gamepad_lost(&self, canvas: &mut graphics::Canvas) {
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1))
+                .color([0.0, 0.0, 0.0, 0.6]),
+        );
+        let mut text = self.text_style.text(
+            "Reconnect controller\n\nor press any key to carry on with the keyboard",
+            text::DEFAULT_SIZE * 1.5,
+        );
+        text.set_layout(graphics::TextLayout::center());
+        canvas.draw(
+            &text,
+            graphics::DrawParam::new()
+                .dest(Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0))
+                .color(graphics::Color::WHITE),
+        );
+    }
+
+    /// Does what a key or button bound to `action` is for.
+    fn perform(&mut self, ctx: &Context, action: Action) {
+        match action {
+            // The other side would carry on without us.
+            Action::Pause if self.versus.is_some() => {}
+            Action::Pause => self.paused = !self.paused,
+            Action::Help => self.show_help = !self.show_help,
+            Action::DangerZone => self.danger_zone = !self.danger_zone,
+            Action::Checkerboard => self.checkerboard = !self.checkerboard,
+            Action::Heatmap => self.show_heatmap = !self.show_heatmap,
+            Action::ResetHeatmap => self.heatmap.reset(),
+            Action::Coordinates => self.coordinates = !self.coordinates,
+            Action::SegmentLabels => self.show_segment_labels = !self.show_segment_labels,
+            Action::Ghost => {
+                self.show_ghost = !self.show_ghost;
+                self.update_ghost();
+            }
+            Action::CycleRenderStyle => {
+                let style = self.painter.style().next();
+                self.painter.set_style(style);
+                self.floating_texts.spawn(
+                    style.name(),
+                    Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0),
+                    graphics::Color::WHITE,
+                );
+            }
+            Action::ZoomIn => self.zoom_by(ZOOM_STEP),
+            Action::ZoomOut => self.zoom_by(1.0 / ZOOM_STEP),
+            Action::Reverse => {
+                let playing = self.player.is_none() && !self.autopilot;
+                if playing && !self.gameover && !self.paused && self.reverse_cooldown == 0 {
+                    let wrap_mode = self.rules.wrap_mode;
+                    for snake in self.snakes_mut() {
+                        snake.reverse(wrap_mode);
+                    }
+                    self.reverse_cooldown = REVERSE_COOLDOWN;
+                }
+            }
+            Action::BiteTail => {
+                let playing = self.player.is_none() && !self.autopilot;
+                if playing && !self.gameover && !self.paused && self.tail_bite_cooldown == 0 {
+                    self.bite_tail();
+                }
+            }
+            Action::Steer(dir) => {
+                if self.player.is_some() || self.autopilot || self.gameover || self.paused {
+                    return;
+                }
+                if dir.is_diagonal() && !self.diagonals {
+                    return;
+                }
+                if let Some(splits) = &mut self.splits {
+                    splits.start(ctx.time.time_since_start());
+                }
+                if let Some(versus) = &mut self.versus {
+                    versus.turn(dir);
+                    return;
+                }
+                self.steer(dir);
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(dir);
+                }
+            }
+        }
+    }
+
+    /// Lists the current key bindings over a dimmed playfield.
+    fn draw_help(&self, canvas: &mut graphics::Canvas) {
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1))
+                .color([0.0, 0.0, 0.0, 0.6]),
+        );
+
+        let mut lines = String::from("Controls\n\n");
+        for (action, key) in self.controls.bindings() {
+            lines.push_str(&format!("{}: {key:?}\n", action.description()));
+        }
+        let mut text = self.text_style.text(lines, text::DEFAULT_SIZE);
+        text.set_layout(graphics::TextLayout::center());
+        canvas.draw(
+            &text,
+            graphics::DrawParam::new()
+                .dest(Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0))
+                .color(graphics::Color::WHITE),
+        );
+    }
+
+    fn play_sound(&mut self, ctx: &mut Context, pitch: f32) {
+        if let Some(sound) = &mut self.sound {
+            sound.play(ctx, pitch);
+            if let Some(music) = &mut self.music {
+                music.duck(self.ducking);
+            }
+        }
+    }
+
+    fn play_turn_sound(&mut self, ctx: &mut Context, dir: Direction) {
+        let Some(turn_sound) = &mut self.turn_sound else {
+            return;
+        };
+        let now = ctx.time.time_since_start();
+        if let Some(last) = self.last_turn_sound {
+            if now - last < TURN_SOUND_COOLDOWN {
+                return;
+            }
+        }
+        self.last_turn_sound = Some(now);
+        turn_sound.set_pitch(dir.turn_pitch());
+        let _ = turn_sound.play(ctx);
+    }
+
+    fn end_game(&mut self, won: bool) {
+        self.gameover = true;
+        self.won = won;
+        self.finished_after = Some(self.started.elapsed());
+        self.save_score();
+        self.save_record();
+    }
+
+    /// Adds this game to the history behind `--stats`. Like the scoreboard, only games the
+    /// player actually played, from a fresh board, count.
+    fn save_record(&self) {
+        if self.player.is_some() || self.autopilot || self.scenario.is_some() {
+            return;
+        }
+        let record = stats::GameRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            seed: self.seed,
+            score: self.score,
+            max_length: self.max_len,
+            duration: self.started.elapsed(),
+            mode: self.rules.wrap_mode,
+        };
+        if let Err(e) = stats::append(&record) {
+            eprintln!("Could not save game history: {e}");
+        }
+    }
+
+    /// Puts the final score on the persistent scoreboard. Replays and the autopilot don't count.
+    fn save_score(&self) {
+        if self.player.is_some() || self.autopilot || self.scenario.is_some() {
+            return;
+        }
+        let result = scores::Scoreboard::load().and_then(|mut scoreboard| {
+            scoreboard.insert(self.score);
+            scoreboard.save()
+        });
+        if let Err(e) = result {
+            eprintln!("Could not save score: {e}");
+        }
+        let Some(date) = self.daily else {
+            return;
+        };
+        match daily::record(date, self.score) {
+            Ok(true) => println!("New best for the {date} daily challenge: {}", self.score),
+            Ok(false) => {}
+            Err(e) => eprintln!("Could not save the daily score: {e}"),
+        }
+    }
+
+    fn save_recording(&mut self) {
+        if self.recorder.is_none() {
+            return;
+        }
+        let result = self.replay_result();
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.save(result) {
+                eprintln!("Could not save replay: {e}");
+            }
+        }
+    }
+
+    /// Where the game has got to, as a replay records it.
+    fn replay_result(&self) -> ReplayResult {
+        let outcome = match (self.gameover, self.won) {
+            (false, _) => Outcome::Quit,
+            (true, false) => Outcome::Died,
+            (true, true) => Outcome::Won,
+        };
+        ReplayResult {
+            outcome,
+            ticks: self.tick,
+            score: self.score,
+            length: self.swarm_len() as u32,
+            hash: self.state_hash(),
+        }
+    }
+}
+
+impl event::EventHandler<ggez::GameError> for GameState {
+    /// Update will happen on every frame before it is drawn.
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if self.loading.is_some() {
+            // The time spent loading isn't owed to the game as ticks.
+            while ctx.time.check_update_time(self.tick_rate) {}
+            if self.load_next_asset(ctx) {
+                self.loading = None;
+                self.started = Instant::now();
+                self.transition.start(ctx.time.time_since_start());
+            }
+            return Ok(());
+        }
+        self.check_gamepad(ctx);
+        if let Some(attract) = self.attract.as_mut().filter(|_| self.gameover) {
+            if attract.update(ctx, &self.text_style)? {
+                return Ok(());
+            }
+        }
+        let now = ctx.time.time_since_start();
+        self.transition.follow(self.gameover, now);
+        let transitioning = self.transition.active(now);
+        let mut due = 0;
+        while ctx.time.check_update_time(self.tick_rate) {
+            due += 1;
+        }
+        if self.practice.is_some() || self.editor.is_some() || transitioning {
+            due = 0;
+        }
+        let (updates, dropped) = self.catch_up(due, |state, tick, longest, events| {
+            if state.tick != tick {
+                // A snake that sat the move out has nowhere to slide from.
+                state.stepped_at = (!state.snake.held).then(|| ctx.time.time_since_start());
+            }
+            for event in events {
+                state.handle_event(ctx, event);
+            }
+            state.celebrate_milestone(ctx, longest);
+        });
+        if updates > 0 {
+            self.update_ghost();
+        }
+        if let Some(stress) = &mut self.stress {
+            for _ in 0..updates {
+                stress.step();
+            }
+            stress.record_frame(ctx.time.delta());
+        }
+        if let Some(rate) = self.performance.record(now, updates, dropped) {
+            if rate < self.tick_rate {
+                self.slow_down(rate);
+            }
+        }
+        self.particles.update(ctx.time.delta());
+        self.floating_texts.update(ctx.time.delta());
+        let tension = if self.music_tension && !self.gameover {
+            self.tension()
+        } else {
+            0.0
+        };
+        if let Some(music) = &mut self.music {
+            music.update(ctx, tension, ctx.time.delta());
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let now = ctx.time.time_since_start();
+        if let Some(gif) = &mut self.gif {
+            gif.collect(ctx)?;
+        }
+        let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::BLACK);
+        let screen = self.screen;
+        match self.attract.as_mut().and_then(Attract::demo_mut) {
+            _ if self.loading.is_some() => self.draw_loading(&mut canvas, screen),
+            Some(demo) => {
+                demo.draw_scene(ctx, &mut canvas, screen, now)?;
+                Attract::draw_banner(&mut canvas, &demo.text_style, screen);
+            }
+            None => self.draw_scene(ctx, &mut canvas, screen, now)?,
+        }
+        self.transition.draw(&mut canvas, screen, now);
+        canvas.finish(ctx)?;
+        // The GIF gets the whole board without the letterboxing, whatever shape the window is.
+        if let Some(mut canvas) = self.gif.as_mut().and_then(|gif| gif.capture(ctx, now)) {
+            let board = graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1);
+            self.draw_scene(ctx, &mut canvas, board, now)?;
+            canvas.finish(ctx)?;
+        }
+
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if self.input_held(ctx) {
+            return Ok(());
+        }
+        if self.interrupt_attract(ctx) {
+            return Ok(());
+        }
+        if button != MouseButton::Left {
+            return Ok(());
+        }
+        let Some(pos) = self.cell_at(ctx.gfx.drawable_size(), x, y) else {
+            return Ok(());
+        };
+        if self.snake.occupies(pos) || pos == self.food.pos {
+            return Ok(());
+        }
+        if let Some(editor) = &mut self.editor {
+            editor.toggle(&mut self.obstacles, pos);
+        }
+        Ok(())
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        if y > 0.0 {
+            self.zoom_by(ZOOM_STEP);
+        } else if y < 0.0 {
+            self.zoom_by(1.0 / ZOOM_STEP);
+        }
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.screen = viewport((width, height));
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        if self.input_held(ctx) {
+            return Ok(());
+        }
+        if self.interrupt_attract(ctx) {
+            return Ok(());
+        }
+        let Some(key) = input.keycode else {
+            return Ok(());
+        };
+        if self.practice_key(key) {
+            return Ok(());
+        }
+        if let Some(editor) = &mut self.editor {
+            if input.mods.contains(KeyMods::CTRL) {
+                match key {
+                    KeyCode::Z => editor.undo(&mut self.obstacles),
+                    KeyCode::Y => editor.redo(&mut self.obstacles),
+                    _ => false,
+                };
+                return Ok(());
+            }
+        }
+        if self.gamepad_lost {
+            self.play_on_keyboard();
+            return Ok(());
+        }
+        if let Some(action) = self.controls.action(key) {
+            self.perform(ctx, action);
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        if self.input_held(ctx) {
+            return Ok(());
+        }
+        if self.interrupt_attract(ctx) {
+            return Ok(());
+        }
+        self.gamepad = Some(id);
+        if let Some(action) = Controls::button_action(button) {
+            self.perform(ctx, action);
+        }
+        Ok(())
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        if let Some(gif) = &mut self.gif {
+            gif.finish(ctx);
+        }
+        if let Some(editor) = &self.editor {
+            match level::save_obstacles(editor.path(), &self.obstacles) {
+                Ok(()) => println!(
+                    "Obstacles saved, play them with `--obstacles {}`.",
+                    editor.path().display()
+                ),
+                Err(e) => eprintln!("Could not save the obstacles: {e}"),
+            }
+            return Ok(false);
+        }
+        if !self.gameover {
+            self.save_recording();
+            if self.player.is_none()
+                && !self.autopilot
+                && self.versus.is_none()
+                && self.scenario.is_none()
+            {
+                match self.save_game() {
+                    Ok(()) => println!("Game saved, carry on with `--resume`."),
+                    Err(e) => eprintln!("Could not save the game: {e}"),
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Everything the `graphics` binary does, going by its command line.
+pub fn run() -> GameResult {
+    env_logger::init();
+    let mut config = Config::from_args(env::args().skip(1))?;
+    if config.reset_scores {
+        if !scores::reset_interactively() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if config.stats {
+        return stats::print();
+    }
+    if config.selftest {
+        if !selftest::run(config)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(path) = config.verify.clone() {
+        if !verify::run(config, &path)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if config.tui {
+        return tui::run(config);
+    }
+
+    if let (Some(date), Some(seed)) = (config.daily, config.seed) {
+        println!("Daily challenge for {date}, seed {seed}");
+    }
+    let resource_dir = resources::find(config.resources.as_deref())?;
+    let peer = net::connect(&mut config)?;
+
+    let (width, height) = config
+        .window_size
+        .map_or(SCREEN_SIZE, |size| (size.width, size.height));
+    let mut settings = Settings::load()?;
+    let remembered = settings;
+    settings.msaa = config.msaa.unwrap_or(settings.msaa);
+    settings.transition = config.transition.unwrap_or(settings.transition);
+    settings.transition_ms = config.transition_ms.unwrap_or(settings.transition_ms);
+    if settings != remembered {
+        if let Err(e) = settings.save() {
+            eprintln!("Could not save the settings: {e}");
+        }
+    }
+    let build = |samples| {
+        ggez::ContextBuilder::new("snake", "Gray Olson")
+            .add_resource_path(&resource_dir)
+            .window_setup(
+                ggez::conf::WindowSetup::default()
+                    .title("Snake!")
+                    .samples(samples),
+            )
+            // A logical size, so the window comes out the same size on HiDPI screens.
+            .window_mode(ggez::conf::WindowMode {
+                logical_size: Some(ggez::winit::dpi::LogicalSize::new(width, height)),
+                ..Default::default()
+            })
+            .build()
+    };
+    let samples = render::samples(settings.msaa);
+    // Some adapters turn down multisampling even where the backend has it, so try again
+    // without rather than not starting at all.
+    let (mut ctx, events_loop) = match build(samples) {
+        Err(e) if samples != ggez::conf::NumSamples::One => {
+            log::warn!(
+                "Could not make a window with {}x MSAA, going without: {e}",
+                samples as u8
+            );
+            build(ggez::conf::NumSamples::One)?
+        }
+        built => built?,
+    };
+
+    let mut state = GameState::new(&mut ctx, config.clone())?;
+    if let Some(peer) = peer {
+        state.versus = Some(Versus::new(peer, config)?);
+    }
+    event::run(ctx, events_loop, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grids too small for the game's own, where a fixed `GRID_SIZE` used to hide mistakes.
+    const TINY_GRIDS: [(i16, i16); 10] = [
+        (1, 1),
+        (2, 1),
+        (3, 1),
+        (4, 1),
+        (5, 1),
+        (1, 4),
+        (1, 5),
+        (4, 4),
+        (5, 2),
+        (2, 5),
+    ];
+
+    #[test]
+    fn safe_start_on_tiny_walls_grids() {
+        for grid in TINY_GRIDS {
+            for len in 1..=4 {
+                let Ok((head, dir)) = safe_start(len, None, WrapMode::Walls, grid) else {
+                    continue;
+                };
+                let cells = start_cells_within(head, dir, len, WrapMode::Walls, grid)
+                    .unwrap_or_else(|| panic!("{grid:?}: start at {head:?} doesn't fit"));
+                assert_eq!(cells.len(), len);
+                for (i, cell) in cells.iter().enumerate() {
+                    assert!(
+                        (0..grid.0).contains(&cell.x) && (0..grid.1).contains(&cell.y),
+                        "{grid:?}: {cell:?} is off the grid"
+                    );
+                    assert!(!cells[..i].contains(cell), "{grid:?}: {cell:?} twice");
+                }
+
+                // Going straight ahead, the snake lives for at least `SAFE_START_MOVES`.
+                let mut body: VecDeque<_> = cells.into_iter().collect();
+                for step in 0..SAFE_START_MOVES {
+                    let next = GridPosition::moved_within(body[0], dir, WrapMode::Walls, grid)
+                        .unwrap_or_else(|| panic!("{grid:?}: into the wall on move {step}"));
+                    body.pop_back();
+                    assert!(!body.contains(&next), "{grid:?}: bit itself on move {step}");
+                    body.push_front(next);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn safe_start_needs_room_for_the_snake_and_its_first_moves() {
+        let len = INITIAL_LENGTH;
+        let room = len as i16 + SAFE_START_MOVES as i16;
+        assert_eq!(
+            safe_start(len, None, WrapMode::Walls, (room, 1)).unwrap(),
+            (GridPosition::new(len as i16 - 1, 0), Direction::Right)
+        );
+        let (_, dir) = safe_start(len, None, WrapMode::Walls, (1, room)).unwrap();
+        assert!(matches!(dir, Direction::Up | Direction::Down));
+        assert!(safe_start(len, None, WrapMode::Walls, (room - 1, 1)).is_err());
+        assert!(safe_start(len, Some(Direction::Up), WrapMode::Walls, (room, 1)).is_err());
+        assert!(safe_start(len, None, WrapMode::Walls, (1, 1)).is_err());
+    }
+
+    /// A headless game in `wrap_mode`, with nothing but the snake and its food.
+    fn headless(wrap_mode: WrapMode, seed: u64) -> GameState {
+        GameState::new_headless(Config {
+            wrap_mode,
+            seed: Some(seed),
+            ..Config::default()
+        })
+        .unwrap()
+    }
+
+    /// Steers at random for thousands of ticks in every wrap mode, starting over whenever the
+    /// snake dies, so `Snake::debug_check` gets to look at every kind of move.
+    #[test]
+    fn random_steering_keeps_the_snake_whole() {
+        for wrap_mode in [WrapMode::Wrap, WrapMode::Walls, WrapMode::Bounce] {
+            let mut rng = Rand32::new(148);
+            let mut games = 1;
+            let mut state = headless(wrap_mode, rng.rand_u32().into());
+            for _ in 0..5_000 {
+                if state.gameover {
+                    games += 1;
+                    state = headless(wrap_mode, rng.rand_u32().into());
+                }
+                // Turning every tick would mostly just bump into the body, so only sometimes.
+                if rng.rand_range(0..3) == 0 {
+                    let dirs = Direction::CARDINAL;
+                    state.steer(dirs[rng.rand_range(0..dirs.len() as u32) as usize]);
+                }
+                state.run_tick();
+
+                let snake = &state.snake;
+                let cells: HashSet<GridPosition> = snake.body.iter().map(|seg| seg.pos).collect();
+                assert_eq!(
+                    cells.len(),
+                    snake.body.len(),
+                    "{wrap_mode:?}: body overlaps"
+                );
+                if !state.gameover {
+                    assert!(
+                        !cells.contains(&snake.head.pos),
+                        "{wrap_mode:?}: alive in itself"
+                    );
+                }
+            }
+            assert!(games > 1, "{wrap_mode:?}: the snake never died");
+        }
+    }
+
+    /// A snake with its head at `head` and the rest laid out as `body`, neck first, having
+    /// come from `came_from` and now heading `dir`.
+    fn snake_at(
+        head: (i16, i16),
+        body: &[(i16, i16)],
+        came_from: Direction,
+        dir: Direction,
+    ) -> Snake {
+        let mut snake: Snake = Snake::new(GridPosition::new(5, 5), dir, WrapMode::Wrap, None);
+        snake.head = Segment::new(GridPosition::new(head.0, head.1));
+        snake.body = RingBuffer::with_capacity(GRID_SIZE.0 as usize * GRID_SIZE.1 as usize);
+        for &(x, y) in body.iter().rev() {
+            snake.body.push_front(Segment::new(GridPosition::new(x, y)));
+        }
+        snake.dir = dir;
+        snake.last_update_dir = came_from;
+        snake
+    }
+
+    fn food_at(x: i16, y: i16) -> Food {
+        Food::new(GridPosition::new(x, y), 0, 0)
+    }
+
+    /// Coiled round so that going up runs into the body, onto the food the body is lying on.
+    fn food_on_the_body() -> (Snake, Food) {
+        let snake = snake_at(
+            (5, 5),
+            &[(6, 5), (6, 4), (5, 4), (4, 4)],
+            Direction::Left,
+            Direction::Up,
+        );
+        (snake, food_at(5, 4))
+    }
+
+    /// Heading right into the middle of the grid, where the head's reflection lands on the
+    /// neck, with the food right there too.
+    fn food_on_the_mirror() -> (Snake, Food) {
+        let x = GRID_SIZE.0 / 2 - 1;
+        let snake = snake_at((x, 5), &[(x - 1, 5)], Direction::Right, Direction::Right);
+        (snake, food_at(x + 1, 5))
+    }
+
+    fn priority_rules(event_priority: EventPriority) -> Rules {
+        Rules {
+            mirror: true,
+            event_priority,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn collision_first_runs_into_the_body_or_mirror_under_the_food() {
+        for (mut snake, food) in [food_on_the_body(), food_on_the_mirror()] {
+            let rules = priority_rules(EventPriority::CollisionFirst);
+            snake.update(Some(&food), &HashSet::new(), rules, false);
+            assert!(matches!(snake.ate, Some(Ate::Itself)), "{:?}", snake.ate);
+            assert_eq!(snake.head.pos, food.pos);
+        }
+    }
+
+    #[test]
+    fn food_first_eats_the_food_on_the_body_or_mirror_and_lives() {
+        for (mut snake, food) in [food_on_the_body(), food_on_the_mirror()] {
+            let len = snake.len();
+            let rules = priority_rules(EventPriority::FoodFirst);
+            snake.update(Some(&food), &HashSet::new(), rules, false);
+            assert!(matches!(snake.ate, Some(Ate::Food)), "{:?}", snake.ate);
+            assert_eq!(snake.head.pos, food.pos);
+            assert_eq!(snake.len(), len + 1);
+
+            // Off the body again, it carries on as usual.
+            snake.steer(Direction::Up, rules);
+            snake.update(None, &HashSet::new(), rules, false);
+            assert!(snake.ate.is_none(), "{:?}", snake.ate);
+        }
+    }
+
+    fn bounce_rules() -> Rules {
+        Rules {
+            wrap_mode: WrapMode::Bounce,
+            ..Rules::default()
+        }
+    }
+
+    /// Moves `snake` once in bounce mode around `obstacles`, checking it lives through it.
+    fn bounce_once(snake: &mut Snake, obstacles: &[(i16, i16)]) {
+        let obstacles = obstacles
+            .iter()
+            .map(|&(x, y)| GridPosition::new(x, y))
+            .collect();
+        snake.update(None, &obstacles, bounce_rules(), false);
+        assert!(
+            snake.ate.is_none(),
+            "{:?} at {:?}",
+            snake.ate,
+            snake.head.pos
+        );
+    }
+
+    const MAX_X: i16 = GRID_SIZE.0 - 1;
+    const MAX_Y: i16 = GRID_SIZE.1 - 1;
+
+    #[test]
+    fn bounce_flips_the_move_off_each_edge() {
+        use Direction::*;
+        for (head, dir, bounced) in [
+            ((10, 0), UpRight, DownRight),
+            ((10, MAX_Y), DownLeft, UpLeft),
+            ((0, 10), UpLeft, UpRight),
+            ((MAX_X, 10), DownRight, DownLeft),
+        ] {
+            let (dx, dy) = dir.inverse().offset();
+            let mut snake = snake_at(head, &[(head.0 + dx, head.1 + dy)], dir, dir);
+            bounce_once(&mut snake, &[]);
+            assert_eq!(snake.dir, bounced, "{dir:?} off {head:?}");
+            let (dx, dy) = bounced.offset();
+            assert_eq!(snake.head.pos, GridPosition::new(head.0 + dx, head.1 + dy));
+        }
+    }
+
+    #[test]
+    fn bounce_flips_both_ways_in_each_corner() {
+        use Direction::*;
+        // The neck is off to the side, since right behind would be where it bounces to.
+        for (head, neck, dir, bounced) in [
+            ((0, 0), (1, 0), UpLeft, DownRight),
+            ((MAX_X, 0), (MAX_X - 1, 0), UpRight, DownLeft),
+            ((0, MAX_Y), (1, MAX_Y), DownLeft, UpRight),
+            ((MAX_X, MAX_Y), (MAX_X - 1, MAX_Y), DownRight, UpLeft),
+        ] {
+            let came_from = Direction::from_offset((head.0 - neck.0, head.1 - neck.1)).unwrap();
+            let mut snake = snake_at(head, &[neck], came_from, dir);
+            bounce_once(&mut snake, &[]);
+            assert_eq!(snake.dir, bounced, "{dir:?} into {head:?}");
+        }
+    }
+
+    #[test]
+    fn blocked_bounce_turns_along_the_edge() {
+        use Direction::*;
+        // Straight at an edge the bounce is straight back into the neck, so the snake turns
+        // towards whichever side has more room.
+        for (head, dir, along) in [
+            ((10, 0), Up, Right),
+            ((20, MAX_Y), Down, Left),
+            ((0, 5), Left, Down),
+            ((MAX_X, 15), Right, Up),
+            ((0, 0), Up, Right),
+            ((MAX_X, MAX_Y), Right, Up),
+        ] {
+            let (dx, dy) = dir.inverse().offset();
+            let mut snake = snake_at(head, &[(head.0 + dx, head.1 + dy)], dir, dir);
+            bounce_once(&mut snake, &[]);
+            assert_eq!(snake.dir, along, "{dir:?} into {head:?}");
+        }
+
+        // A diagonal bounce blocked by an obstacle goes along the edge the same way.
+        let mut snake = snake_at((10, 0), &[(9, 1)], UpRight, UpRight);
+        bounce_once(&mut snake, &[(11, 1)]);
+        assert_eq!(snake.dir, Right);
+    }
+
+    #[test]
+    fn bounce_runs_into_the_edge_with_nowhere_to_turn() {
+        let mut snake = snake_at(
+            (0, 0),
+            &[(0, 1), (1, 1), (1, 0)],
+            Direction::Up,
+            Direction::Up,
+        );
+        snake.update(None, &HashSet::new(), bounce_rules(), false);
+        assert!(matches!(snake.ate, Some(Ate::Wall)), "{:?}", snake.ate);
+        assert_eq!(snake.head.pos, GridPosition::new(0, 0));
+    }
+
+    #[test]
+    fn respawning_keeps_away_from_bounce_edges() {
+        let mut state = headless(WrapMode::Bounce, 170);
+        // Without the edges counting, the corners are as far as it gets from this.
+        state.obstacles = HashSet::from([GridPosition::new(GRID_SIZE.0 / 2, GRID_SIZE.1 / 2)]);
+        let (pos, _) = state.safe_respawn().unwrap();
+        let to_edge = [pos.x, MAX_X - pos.x, pos.y, MAX_Y - pos.y];
+        assert!(to_edge.iter().all(|&d| d >= 3), "respawned at {pos:?}");
+    }
+
+    /// The average distance from `head` of a thousand cells picked with `bias`.
+    fn mean_food_distance(bias: FoodBias, head: GridPosition) -> f64 {
+        let mut rng = Rand32::new(176);
+        let weight = food_weight(bias, head, WrapMode::Walls);
+        let picks = 1_000;
+        let total: u32 = (0..picks)
+            .map(|_| {
+                let pos = random_free_cell(&mut rng, |pos| pos == head, &weight).unwrap();
+                grid_distance(head, pos, WrapMode::Walls)
+            })
+            .sum();
+        total as f64 / picks as f64
+    }
+
+    #[test]
+    fn food_bias_moves_the_food_closer_or_further() {
+        for head in [GridPosition::new(3, 3), GridPosition::new(15, 10)] {
+            let uniform = mean_food_distance(FoodBias::Uniform, head);
+            let near = mean_food_distance(FoodBias::Near, head);
+            let far = mean_food_distance(FoodBias::Far, head);
+            assert!(
+                near < uniform,
+                "near {near} vs uniform {uniform} from {head:?}"
+            );
+            assert!(
+                far > uniform,
+                "far {far} vs uniform {uniform} from {head:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_free_cell_on_a_full_board() {
+        let mut rng = Rand32::new(176);
+        assert_eq!(random_free_cell(&mut rng, |_| true, |_| 1), None);
+        assert_eq!(random_free_cell(&mut rng, |_| true, |_| 0), None);
+    }
+
+    #[test]
+    fn weighing_nothing_picks_uniformly() {
+        let free = [(0, 0), (7, 3), (29, 19)].map(|(x, y)| GridPosition::new(x, y));
+        let taken = |pos| !free.contains(&pos);
+        let picks = |weight: u32| {
+            let mut rng = Rand32::new(176);
+            (0..300)
+                .map(|_| random_free_cell(&mut rng, taken, |_| weight).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let zero = picks(0);
+        // The very same picks as with every cell weighing the same.
+        assert_eq!(zero, picks(1));
+        for cell in free {
+            let count = zero.iter().filter(|&&pos| pos == cell).count();
+            assert!(count > 60, "{cell:?} picked {count} times out of 300");
+        }
+    }
+
+    const SPEED_CURVES: [SpeedCurve; 5] = [
+        SpeedCurve::Constant,
+        SpeedCurve::Linear,
+        SpeedCurve::Exponential,
+        SpeedCurve::Stepped,
+        SpeedCurve::EaseIn,
+    ];
+
+    #[test]
+    fn speed_curves_only_speed_up_to_the_cap() {
+        for curve in SPEED_CURVES {
+            for start in [1, 8, MAX_FPS - 1, MAX_FPS, MAX_FPS + 1, 100] {
+                assert_eq!(curve.fps_for(0.0, start), start.min(MAX_FPS), "{curve:?}");
+                let mut last = 0;
+                for i in 0..=1_000 {
+                    let fps = curve.fps_for(i as f32 / 1_000.0, start);
+                    assert!(fps >= last, "{curve:?} from {start} slowed down at {i}");
+                    assert!(fps <= MAX_FPS, "{curve:?} from {start} reached {fps}");
+                    last = fps;
+                }
+                let end = if curve == SpeedCurve::Constant {
+                    start.min(MAX_FPS)
+                } else {
+                    MAX_FPS
+                };
+                assert_eq!(curve.fps_for(1.0, start), end, "{curve:?}");
+                // Out of range progress is clamped rather than going past either end.
+                assert_eq!(curve.fps_for(-1.0, start), start.min(MAX_FPS), "{curve:?}");
+                assert_eq!(curve.fps_for(2.0, start), end, "{curve:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn grace_period_lets_the_snake_through_itself_for_that_many_moves() {
+        use Direction::*;
+        for grace_period in [0, 1, 3, 6] {
+            let rules = Rules {
+                grace_period,
+                ..Rules::default()
+            };
+            // Eating fills out a 2x2 square, which going round it never gets out of: every
+            // move after that is into the tail.
+            let mut snake = snake_at((6, 5), &[(6, 6), (5, 6)], Up, Left);
+            snake.update(Some(&food_at(5, 5)), &HashSet::new(), rules, false);
+            assert!(matches!(snake.ate, Some(Ate::Food)));
+            assert_eq!(snake.len(), 4);
+
+            for (i, dir) in [Down, Right, Up, Left].into_iter().cycle().enumerate() {
+                let moves = i as u32 + 1;
+                snake.steer(dir, rules);
+                snake.update(None, &HashSet::new(), rules, false);
+                if moves <= grace_period {
+                    assert!(snake.ate.is_none(), "grace {grace_period}, move {moves}");
+                } else {
+                    assert!(
+                        matches!(snake.ate, Some(Ate::Itself)),
+                        "grace {grace_period}, move {moves}: {:?}",
+                        snake.ate
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Whether the snake survives going off the top `gap` ticks after wrapping off the left,
+    /// with `wrap_cooldown`.
+    fn survives_second_wrap(wrap_cooldown: u32, gap: u32) -> bool {
+        let mut state = GameState::new_headless(Config {
+            wrap_cooldown,
+            seed: Some(145),
+            ..Config::default()
+        })
+        .unwrap();
+        state.snake = snake_at((0, 0), &[(1, 0)], Direction::Left, Direction::Left);
+        state.food.pos = GridPosition::new(15, 10);
+
+        state.run_tick();
+        assert!(state.snake.wrapped && !state.gameover);
+        for _ in 1..gap {
+            state.run_tick();
+        }
+        state.steer(Direction::Up);
+        let events = state.run_tick();
+        assert_eq!(state.gameover, events.contains(&GameEvent::Died));
+        !state.gameover
+    }
+
+    #[test]
+    fn wrapping_again_within_the_cooldown_kills() {
+        for wrap_cooldown in [1, 2, 5] {
+            for gap in 1..=wrap_cooldown {
+                assert!(
+                    !survives_second_wrap(wrap_cooldown, gap),
+                    "cooldown {wrap_cooldown}, wrapped again after {gap}"
+                );
+            }
+            assert!(survives_second_wrap(wrap_cooldown, wrap_cooldown + 1));
+        }
+    }
+
+    #[test]
+    fn no_wrap_cooldown_wraps_whenever() {
+        for gap in 1..=3 {
+            assert!(survives_second_wrap(0, gap), "wrapped again after {gap}");
+        }
+    }
+
+    #[test]
+    fn reversing_is_ignored_unless_it_kills() {
+        for immediate in [false, true] {
+            for reverse_kills in [false, true] {
+                let rules = Rules {
+                    reverse_kills,
+                    ..Rules::default()
+                };
+                let mut snake = snake_at(
+                    (5, 5),
+                    &[(4, 5), (3, 5)],
+                    Direction::Right,
+                    Direction::Right,
+                );
+                if immediate {
+                    snake.steer_immediately(Direction::Left, rules);
+                } else {
+                    snake.steer(Direction::Left, rules);
+                }
+                snake.update(None, &HashSet::new(), rules, false);
+                if reverse_kills {
+                    assert!(matches!(snake.ate, Some(Ate::Itself)), "{:?}", snake.ate);
+                } else {
+                    assert!(snake.ate.is_none(), "{:?}", snake.ate);
+                    assert_eq!(snake.dir, Direction::Right);
+                    assert_eq!(snake.head.pos, GridPosition::new(6, 5));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reversing_ends_the_game_with_reverse_kills() {
+        for reverse_kills in [false, true] {
+            let mut state = GameState::new_headless(Config {
+                reverse_kills,
+                seed: Some(143),
+                ..Config::default()
+            })
+            .unwrap();
+            let back = state.snake.dir.inverse();
+            state.steer(back);
+            let events = state.run_tick();
+            assert_eq!(events.contains(&GameEvent::Died), reverse_kills);
+            assert_eq!(state.gameover, reverse_kills);
+        }
+    }
+
+    fn cells(snake: &Snake) -> Vec<GridPosition> {
+        std::iter::once(snake.head.pos)
+            .chain(snake.body.iter().map(|seg| seg.pos))
+            .collect()
+    }
+
+    #[test]
+    fn reversing_twice_puts_the_snake_back() {
+        use Direction::*;
+        for (wrap_mode, head, body, came_from) in [
+            (WrapMode::Wrap, (5, 5), vec![(5, 6), (6, 6), (7, 6)], Up),
+            // Lying across the edge, so the ends only line up by wrapping.
+            (
+                WrapMode::Wrap,
+                (0, 3),
+                vec![(MAX_X, 3), (MAX_X - 1, 3)],
+                Right,
+            ),
+            (WrapMode::Walls, (5, 5), vec![(5, 6), (6, 6), (7, 6)], Up),
+            (WrapMode::Walls, (0, 3), vec![(1, 3), (2, 3), (2, 4)], Left),
+        ] {
+            let original = snake_at(head, &body, came_from, came_from);
+            let mut snake = original.clone();
+            snake.reverse(wrap_mode);
+            let mut reversed = cells(&original);
+            reversed.reverse();
+            assert_eq!(cells(&snake), reversed, "{wrap_mode:?}");
+            snake.reverse(wrap_mode);
+            assert_eq!(cells(&snake), cells(&original), "{wrap_mode:?}");
+            assert_eq!(snake.dir, original.dir, "{wrap_mode:?}");
+            assert_eq!(
+                snake.last_update_dir, original.last_update_dir,
+                "{wrap_mode:?}"
+            );
+
+            // And it carries on just as it would have.
+            let rules = Rules {
+                wrap_mode,
+                ..Rules::default()
+            };
+            let mut original = original;
+            for snake in [&mut snake, &mut original] {
+                snake.update(None, &HashSet::new(), rules, false);
+            }
+            assert_eq!(cells(&snake), cells(&original), "{wrap_mode:?}");
+        }
+    }
+
+    /// Framebuffer sizes at 1x: the game's window, wider, taller, one that fits neither a whole
+    /// number of cells nor the grid's shape, and one too small for even a pixel a cell.
+    const DRAWABLES: [(f32, f32); 5] = [
+        SCREEN_SIZE,
+        (1920.0, 1080.0),
+        (960.0, 1280.0),
+        (1001.0, 333.0),
+        (31.0, 19.0),
+    ];
+
+    #[test]
+    fn cells_fit_the_game_window_exactly() {
+        let cell = GRID_CELL_SIZE.0 as f32;
+        for scale in [1.0, 2.0] {
+            let drawable = (SCREEN_SIZE.0 * scale, SCREEN_SIZE.1 * scale);
+            assert_eq!(fit_cells(drawable), (cell * scale, Vec2::ZERO));
+        }
+    }
+
+    #[test]
+    fn cells_are_as_big_as_fit_and_the_grid_is_centered() {
+        let grid = Vec2::new(GRID_SIZE.0 as f32, GRID_SIZE.1 as f32);
+        for scale in [1.0, 2.0] {
+            for (w, h) in DRAWABLES {
+                let drawable = Vec2::new(w, h) * scale;
+                let (cell, offset) = fit_cells(drawable.into());
+                assert_eq!(cell, cell.floor(), "{drawable}");
+                let used = grid * cell;
+                if cell > 1.0 {
+                    assert!(used.x <= drawable.x && used.y <= drawable.y, "{drawable}");
+                }
+                let bigger = grid * (cell + 1.0);
+                assert!(bigger.x > drawable.x || bigger.y > drawable.y, "{drawable}");
+                let spare = drawable - used;
+                for (before, after) in [
+                    (offset.x, spare.x - offset.x),
+                    (offset.y, spare.y - offset.y),
+                ] {
+                    assert_eq!(before, before.floor(), "{drawable}");
+                    assert!(
+                        (after - before).abs() <= 1.0,
+                        "{drawable}: {before} vs {after}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn viewport_puts_the_grid_on_the_fitted_cells() {
+        for scale in [1.0, 2.0] {
+            for (w, h) in DRAWABLES {
+                let drawable = (w * scale, h * scale);
+                let (cell, offset) = fit_cells(drawable);
+                let view = viewport(drawable);
+                // Where a logical point ends up on the framebuffer.
+                let physical = |x: f32, y: f32| {
+                    Vec2::new(
+                        (x - view.x) * drawable.0 / view.w,
+                        (y - view.y) * drawable.1 / view.h,
+                    )
+                };
+                let close = |a: Vec2, b: Vec2| (a - b).abs().max_element() < 1e-3;
+                assert!(close(physical(0.0, 0.0), offset), "{drawable:?}");
+                let corner = offset + Vec2::new(GRID_SIZE.0 as f32, GRID_SIZE.1 as f32) * cell;
+                assert!(
+                    close(physical(SCREEN_SIZE.0, SCREEN_SIZE.1), corner),
+                    "{drawable:?}"
+                );
+                // Square: one logical cell is `cell` pixels both ways.
+                let one =
+                    physical(GRID_CELL_SIZE.0 as f32, GRID_CELL_SIZE.1 as f32) - physical(0.0, 0.0);
+                assert!(close(one, Vec2::splat(cell)), "{drawable:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn catching_up_runs_at_most_max_updates_per_frame() {
+        for max_updates_per_frame in [1, MAX_UPDATES_PER_FRAME, 10] {
+            let mut state = GameState::new_headless(Config {
+                speed: 10,
+                max_updates_per_frame,
+                seed: Some(133),
+                ..Config::default()
+            })
+            .unwrap();
+            // A few seconds' hitch, all owed at once.
+            let due = 5 * state.tick_rate;
+            let mut handled = Vec::new();
+            let (updates, dropped) =
+                state.catch_up(due, |state, tick, _, _| handled.push((tick, state.tick)));
+            assert_eq!(updates, max_updates_per_frame);
+            assert_eq!(dropped, due - max_updates_per_frame);
+            assert_eq!(state.tick, max_updates_per_frame as u64);
+            let expected: Vec<_> = (0..max_updates_per_frame as u64)
+                .map(|tick| (tick, tick + 1))
+                .collect();
+            assert_eq!(handled, expected);
+
+            // Under the cap everything owed runs.
+            let (updates, dropped) = state.catch_up(1, |_, _, _, _| {});
+            assert_eq!((updates, dropped), (1, 0));
+            assert_eq!(state.catch_up(0, |_, _, _, _| {}), (0, 0));
+        }
+    }
+
+    #[test]
+    fn food_still_scores_once_the_snake_stops_growing() {
+        let max_length = 5;
+        let mut state = GameState::new_headless(Config {
+            max_length: Some(max_length),
+            seed: Some(106),
+            ..Config::default()
+        })
+        .unwrap();
+        for meal in 1..=12 {
+            let (head, dir) = (state.snake.head.pos, state.snake.dir);
+            state.food.pos = GridPosition::moved(head, dir, WrapMode::Wrap).unwrap();
+            let score = state.score;
+            let events = state.run_tick();
+            let grew = events.iter().find_map(|event| match *event {
+                GameEvent::AteFood { grew, .. } => Some(grew),
+                _ => None,
+            });
+            let len = (INITIAL_LENGTH + meal).min(max_length);
+            assert_eq!(
+                grew,
+                Some(INITIAL_LENGTH + meal <= max_length),
+                "meal {meal}"
+            );
+            assert_eq!(state.snake.len(), len, "meal {meal}");
+            assert!(state.score > score, "meal {meal} scored nothing");
+        }
+    }
+
+    /// Where food moving right from the right edge goes next, with `obstacles` in the way.
+    fn food_off_the_right_edge(wrap_mode: WrapMode, obstacles: &[(i16, i16)]) -> (i16, i16) {
+        let mut food = Food::new(GridPosition::new(MAX_X, 5), 1, 0);
+        let snake = snake_at((5, 15), &[(4, 15)], Direction::Right, Direction::Right);
+        let obstacles = obstacles
+            .iter()
+            .map(|&(x, y)| GridPosition::new(x, y))
+            .collect();
+        food.update(&snake, &[], &obstacles, wrap_mode);
+        (food.pos.x, food.pos.y)
+    }
+
+    #[test]
+    fn moving_food_wraps_or_bounces_with_the_mode() {
+        assert_eq!(food_off_the_right_edge(WrapMode::Wrap, &[]), (0, 5));
+        assert_eq!(
+            food_off_the_right_edge(WrapMode::Walls, &[]),
+            (MAX_X - 1, 5)
+        );
+        assert_eq!(
+            food_off_the_right_edge(WrapMode::Bounce, &[]),
+            (MAX_X - 1, 5)
+        );
+    }
+
+    #[test]
+    fn wrapping_food_still_turns_around_at_what_is_in_the_way() {
+        assert_eq!(
+            food_off_the_right_edge(WrapMode::Wrap, &[(0, 5)]),
+            (MAX_X - 1, 5)
+        );
+        // Boxed in on both sides, it stays put.
+        assert_eq!(
+            food_off_the_right_edge(WrapMode::Wrap, &[(0, 5), (MAX_X - 1, 5)]),
+            (MAX_X, 5)
+        );
+
+        let mut food = Food::new(GridPosition::new(MAX_X, 5), 1, 0);
+        let snake = snake_at((0, 5), &[(1, 5)], Direction::Left, Direction::Left);
+        food.update(&snake, &[], &HashSet::new(), WrapMode::Wrap);
+        assert_eq!(
+            food.pos,
+            GridPosition::new(MAX_X - 1, 5),
+            "wrapped onto the snake"
+        );
+    }
+
+    #[test]
+    fn reversing_heads_off_the_way_the_tail_lies() {
+        use Direction::*;
+        for (body, dir) in [
+            (vec![(4, 5), (3, 5)], Left),
+            (vec![(4, 5), (4, 6)], Down),
+            (vec![(4, 5), (3, 4)], UpLeft),
+            (vec![(4, 5), (5, 6)], DownRight),
+        ] {
+            let mut snake = snake_at((5, 5), &body, Right, Right);
+            snake.reverse(WrapMode::Wrap);
+            assert_eq!(snake.dir, dir, "tail at {:?}", body[1]);
+        }
+    }
+
+    /// The way the snake went on each tick of one frame running several of them, as at a very
+    /// high tick rate, after `presses` steered it going right.
+    fn directions_over_one_frame(
+        input_mode: InputMode,
+        turn_drain: TurnDrain,
+        presses: &[Direction],
+    ) -> Vec<Direction> {
+        let ticks = 6;
+        let mut state = GameState::new_headless(Config {
+            speed: MAX_FPS,
+            input_mode,
+            turn_drain,
+            start_direction: Some(Direction::Right),
+            max_updates_per_frame: ticks,
+            seed: Some(200),
+            ..Config::default()
+        })
+        .unwrap();
+        // Out of the way, so nothing gets eaten.
+        state.food.pos = GridPosition::new(state.snake.head.pos.x + 15, 2);
+        for &dir in presses {
+            state.steer(dir);
+        }
+        let mut dirs = Vec::new();
+        let (updates, _) = state.catch_up(ticks, |state, _, _, _| {
+            assert!(!state.gameover);
+            dirs.push(state.snake.last_update_dir);
+        });
+        assert_eq!(updates, ticks);
+        dirs
+    }
+
+    #[test]
+    fn one_each_tick_spends_a_tick_on_every_queued_turn() {
+        use Direction::*;
+        let drain =
+            |presses| directions_over_one_frame(InputMode::Queued, TurnDrain::OneEachTick, presses);
+        assert_eq!(drain(&[Up, Left]), [Up, Left, Left, Left, Left, Left]);
+        assert_eq!(
+            drain(&[Down, Right]),
+            [Down, Right, Right, Right, Right, Right]
+        );
+        assert_eq!(drain(&[]), [Right; 6]);
+    }
+
+    #[test]
+    fn latest_skips_to_the_last_legal_turn() {
+        use Direction::*;
+        let drain =
+            |presses| directions_over_one_frame(InputMode::Queued, TurnDrain::Latest, presses);
+        assert_eq!(drain(&[Down, Right]), [Right; 6]);
+        assert_eq!(drain(&[Up, Down]), [Down; 6]);
+        // Left would be straight back into the neck, so the Up before it stays.
+        assert_eq!(drain(&[Up, Left]), [Up; 6]);
+        assert_eq!(drain(&[Up]), [Up; 6]);
+        assert_eq!(drain(&[]), [Right; 6]);
+    }
+
+    #[test]
+    fn turn_drain_leaves_immediate_input_alone() {
+        use Direction::*;
+        for turn_drain in [TurnDrain::OneEachTick, TurnDrain::Latest] {
+            let drain =
+                |presses| directions_over_one_frame(InputMode::Immediate, turn_drain, presses);
+            assert_eq!(drain(&[Down, Right]), [Right; 6], "{turn_drain:?}");
+            assert_eq!(drain(&[Up, Left]), [Up; 6], "{turn_drain:?}");
+        }
+    }
+
+    /// Records a game into the temp dir, presses turns the way the key handler does and runs
+    /// it until the snake hits a wall, which saves the replay. Returns where the game got to.
+    fn record_game(path: &std::path::Path) -> ReplayResult {
+        let mut state = GameState::new_headless(Config {
+            record: Some(path.to_path_buf()),
+            seed: Some(1234),
+            wrap_mode: WrapMode::Walls,
+            ..Config::default()
+        })
+        .unwrap();
+        let turns = [
+            (3, Direction::Up),
+            (6, Direction::Left),
+            (11, Direction::Down),
+            (12, Direction::Right),
+            (20, Direction::Up),
+        ];
+        while !state.gameover {
+            let tick = state.tick;
+            for (_, dir) in turns.iter().filter(|(at, _)| *at == tick) {
+                state.steer(*dir);
+                state.recorder.as_mut().unwrap().record(*dir);
+            }
+            state.step();
+        }
+        state.replay_result()
+    }
+
+    #[test]
+    fn recorded_games_play_back_the_same() {
+        let path = std::env::temp_dir().join(format!("snake-replay-{}.bin", std::process::id()));
+        let recorded = record_game(&path);
+        assert_eq!(recorded.outcome, Outcome::Died);
+
+        let file = ReplayFile::load(&path).unwrap();
+        assert_eq!(file.seed, 1234);
+        assert_eq!(file.inputs.len(), 5);
+        assert_eq!(file.result, Some(recorded));
+
+        let config = Config {
+            wrap_mode: WrapMode::Walls,
+            ..Config::default()
+        };
+        let mut replayed = GameState::new_headless(Config {
+            replay: Some(path.clone()),
+            ..config.clone()
+        })
+        .unwrap();
+        while !replayed.gameover {
+            replayed.step();
+        }
+        assert_eq!(replayed.replay_result(), recorded);
+        assert!(verify::run(config.clone(), &path).unwrap());
+
+        for other_rules in [
+            Config {
+                wrap_mode: WrapMode::Wrap,
+                ..config.clone()
+            },
+            Config {
+                input_mode: InputMode::Immediate,
+                ..config.clone()
+            },
+        ] {
+            let err = GameState::new_headless(Config {
+                replay: Some(path.clone()),
+                ..other_rules
+            });
+            assert!(err.is_err(), "played back under other rules");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn safe_start_wraps_on_grids_too_small_for_walls() {
+        let (head, dir) = safe_start(INITIAL_LENGTH, None, WrapMode::Wrap, (2, 1)).unwrap();
+        assert!(start_cells_within(head, dir, INITIAL_LENGTH, WrapMode::Wrap, (2, 1)).is_some());
+    }
+}
@@ -0,0 +1,149 @@
+//! The font, sounds and images are loaded one a frame after the window opens rather than
+//! all up front, with a loading screen showing how far along that is. ggez's context can't
+//! be handed to another thread, so the frames are what keeps the window responsive.
+//!
+//! An asset that won't load is left out with a warning, and the game does without it the
+//! way it would if the file had never been there.
+
+use crate::config::Config;
+use crate::sound::{Music, SoundOverlap, SoundPool};
+use crate::sprites::SpriteSheet;
+use crate::text::{self, TextStyle};
+use crate::{GameState, SCREEN_SIZE, TURN_SOUND_VOLUME};
+use ggez::audio::{self, SoundSource};
+use ggez::glam::Vec2;
+use ggez::graphics;
+use ggez::{Context, GameResult};
+use std::collections::VecDeque;
+
+const EAT_SOUND: &str = "/success.mp3";
+const BAR_SIZE: (f32, f32) = (240.0, 12.0);
+
+enum Asset {
+    Font { file: String, scale: f32 },
+    FoodSprites { file: String, fps: f32 },
+    EatSound(SoundOverlap),
+    TurnSound,
+    Music(String),
+}
+
+impl Asset {
+    fn name(&self) -> &str {
+        match self {
+            Asset::Font { file, .. } | Asset::FoodSprites { file, .. } => file,
+            Asset::EatSound(_) | Asset::TurnSound => EAT_SOUND,
+            Asset::Music(path) => path,
+        }
+    }
+}
+
+pub struct Loading {
+    pending: VecDeque<Asset>,
+    total: usize,
+}
+
+impl Loading {
+    /// Everything `config` asks for, in the order it's loaded. The font goes first so the
+    /// loading screen itself switches to it as soon as it can.
+    pub fn new(config: &Config) -> Self {
+        let mut pending = VecDeque::from([
+            Asset::Font {
+                file: config.font.clone(),
+                scale: config.text_scale,
+            },
+            Asset::FoodSprites {
+                file: config.food_sprites.clone(),
+                fps: config.food_fps,
+            },
+            Asset::EatSound(config.eat_sound_overlap),
+        ]);
+        if config.turn_sounds {
+            pending.push_back(Asset::TurnSound);
+        }
+        if let Some(path) = &config.music {
+            pending.push_back(Asset::Music(path.clone()));
+        }
+        let total = pending.len();
+        Loading { pending, total }
+    }
+
+    fn fraction(&self) -> f32 {
+        1.0 - self.pending.len() as f32 / self.total.max(1) as f32
+    }
+}
+
+impl GameState {
+    /// Loads the next asset, returning whether that was the last of them.
+    pub fn load_next_asset(&mut self, ctx: &mut Context) -> bool {
+        let Some(loading) = &mut self.loading else {
+            return true;
+        };
+        let Some(asset) = loading.pending.pop_front() else {
+            return true;
+        };
+        let done = loading.pending.is_empty();
+        if let Err(e) = self.load_asset(ctx, &asset) {
+            log::warn!("Could not load {}, going without: {e}", asset.name());
+        }
+        done
+    }
+
+    fn load_asset(&mut self, ctx: &mut Context, asset: &Asset) -> GameResult {
+        match asset {
+            Asset::Font { file, scale } => {
+                // Should the font not load, the text still comes out at the right scale.
+                self.text_style = TextStyle::plain(*scale);
+                self.text_style = TextStyle::load(ctx, file, *scale)?;
+            }
+            Asset::FoodSprites { file, fps } => {
+                self.food_sprites = SpriteSheet::load(ctx, file, *fps)?;
+            }
+            Asset::EatSound(overlap) => {
+                self.sound = Some(SoundPool::new(ctx, EAT_SOUND, *overlap)?);
+            }
+            Asset::TurnSound => {
+                let mut turn_sound = audio::Source::new(ctx, EAT_SOUND)?;
+                turn_sound.set_volume(TURN_SOUND_VOLUME);
+                self.turn_sound = Some(turn_sound);
+            }
+            Asset::Music(path) => self.music = Some(Music::new(ctx, path)?),
+        }
+        Ok(())
+    }
+
+    /// The loading screen in place of the board, with a bar filling up as the assets come in.
+    pub fn draw_loading(&self, canvas: &mut graphics::Canvas, screen: graphics::Rect) {
+        let Some(loading) = &self.loading else {
+            return;
+        };
+        canvas.set_screen_coordinates(screen);
+        let center = Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0);
+        let mut label = self.text_style.text("Loading...", text::DEFAULT_SIZE * 1.5);
+        label.set_layout(graphics::TextLayout {
+            h_align: graphics::TextAlign::Middle,
+            v_align: graphics::TextAlign::End,
+        });
+        canvas.draw(
+            &label,
+            graphics::DrawParam::new()
+                .dest(center - Vec2::new(0.0, BAR_SIZE.1))
+                .color(graphics::Color::WHITE),
+        );
+        let bar = graphics::Rect::new(
+            center.x - BAR_SIZE.0 / 2.0,
+            center.y,
+            BAR_SIZE.0,
+            BAR_SIZE.1,
+        );
+        let filled = graphics::Rect {
+            w: bar.w * loading.fraction(),
+            ..bar
+        };
+        for (rect, color) in [(bar, [0.3, 0.3, 0.3, 1.0]), (filled, [1.0, 1.0, 1.0, 1.0])] {
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new().dest_rect(rect).color(color),
+            );
+        }
+    }
+}
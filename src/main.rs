@@ -1,342 +1,3 @@
-//! Some of the details may need to be changed to scale the game.
-//! For example, if we needed to draw hundreds or thousands of shapes,
-//! a `SpriteBatch` is going to offer far better performance than the direct draw calls that this example uses.
-//!
-//! Author: @termhn
-//! Original repo: <https://github.com/termhn/ggez_snake>
-
-use ggez::audio;
-use ggez::audio::SoundSource;
-use ggez::glam::*;
-use ggez::input;
-use std::env;
-use std::path;
-use std::time::Duration;
-
-use ggez::{
-    event, graphics,
-    input::keyboard::{KeyCode, KeyInput},
-    Context, GameResult,
-};
-use oorandom::Rand32;
-use std::collections::VecDeque;
-
-const GRID_SIZE: (i16, i16) = (30, 20);
-const GRID_CELL_SIZE: (i16, i16) = (32, 32); // Pixels
-
-const SCREEN_SIZE: (f32, f32) = (
-    GRID_SIZE.0 as f32 * GRID_CELL_SIZE.0 as f32,
-    GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1 as f32,
-);
-
-const DESIRED_FPS: u32 = 8;
-
-/// we need them to be signed so that they work properly with our modulus arithmetic later.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-struct GridPosition {
-    x: i16,
-    y: i16,
-}
-
-impl GridPosition {
-    pub fn new(x: i16, y: i16) -> Self {
-        GridPosition { x, y }
-    }
-
-    pub fn random(rng: &mut Rand32, max_x: i16, max_y: i16) -> Self {
-        (
-            rng.rand_range(0..(max_x as u32)) as i16,
-            rng.rand_range(0..(max_y as u32)) as i16,
-        )
-            .into()
-    }
-
-    /// We'll make another helper function that takes one grid position and returns a new one after
-    /// making one move in the direction of `dir`.
-    /// We use the [`rem_euclid()`](https://doc.rust-lang.org/std/primitive.i16.html#method.rem_euclid)
-    /// API when crossing the top/left limits, as the standard remainder function (`%`) returns a
-    /// negative value when the left operand is negative.
-    /// Only the Up/Left cases require rem_euclid(); for consistency, it's used for all of them.
-    pub fn new_from_move(pos: GridPosition, dir: Direction) -> Self {
-        match dir {
-            Direction::Up => GridPosition::new(pos.x, (pos.y - 1).rem_euclid(GRID_SIZE.1)),
-            Direction::Down => GridPosition::new(pos.x, (pos.y + 1).rem_euclid(GRID_SIZE.1)),
-            Direction::Left => GridPosition::new((pos.x - 1).rem_euclid(GRID_SIZE.0), pos.y),
-            Direction::Right => GridPosition::new((pos.x + 1).rem_euclid(GRID_SIZE.0), pos.y),
-        }
-    }
-}
-
-impl From<GridPosition> for graphics::Rect {
-    fn from(pos: GridPosition) -> Self {
-        graphics::Rect::new_i32(
-            pos.x as i32 * GRID_CELL_SIZE.0 as i32,
-            pos.y as i32 * GRID_CELL_SIZE.1 as i32,
-            GRID_CELL_SIZE.0 as i32,
-            GRID_CELL_SIZE.1 as i32,
-        )
-    }
-}
-
-impl From<(i16, i16)> for GridPosition {
-    fn from(pos: (i16, i16)) -> Self {
-        GridPosition { x: pos.0, y: pos.1 }
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    pub fn inverse(self) -> Self {
-        match self {
-            Direction::Up => Direction::Down,
-            Direction::Down => Direction::Up,
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left,
-        }
-    }
-
-    pub fn from_keycode(key: KeyCode) -> Option<Direction> {
-        match key {
-            KeyCode::Up => Some(Direction::Up),
-            KeyCode::Down => Some(Direction::Down),
-            KeyCode::Left => Some(Direction::Left),
-            KeyCode::Right => Some(Direction::Right),
-            _ => None,
-        }
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-struct Segment {
-    pos: GridPosition,
-}
-
-impl Segment {
-    pub fn new(pos: GridPosition) -> Self {
-        Segment { pos }
-    }
-}
-
-struct Food {
-    pos: GridPosition,
-}
-
-impl Food {
-    pub fn new(pos: GridPosition) -> Self {
-        Food { pos }
-    }
-
-    /// Note: this method of drawing does not scale. If you need to render
-    /// a large number of shapes, use an `InstanceArray`.
-    fn draw(&self, canvas: &mut graphics::Canvas) {
-        let color = [0.0, 0.0, 1.0, 1.0];
-        canvas.draw(
-            &graphics::Quad,
-            graphics::DrawParam::new()
-                .dest_rect(self.pos.into())
-                .color(color),
-        );
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-enum Ate {
-    Itself,
-    Food,
-}
-
-struct Snake {
-    head: Segment,
-    dir: Direction,
-    body: VecDeque<Segment>,
-    ate: Option<Ate>,
-    last_update_dir: Direction,
-    /// Store the direction that will be used in the `update` after the next `update`
-    /// This is needed so a user can press two directions (eg. left then up)
-    /// before one `update` has happened. It sort of queues up key press input
-    next_dir: Option<Direction>,
-}
-
-impl Snake {
-    pub fn new(pos: GridPosition) -> Self {
-        let mut body = VecDeque::new();
-        body.push_back(Segment::new((pos.x - 1, pos.y).into()));
-        Snake {
-            head: Segment::new(pos),
-            dir: Direction::Right,
-            last_update_dir: Direction::Right,
-            body,
-            ate: None,
-            next_dir: None,
-        }
-    }
-
-    fn eats_food(&self, food: &Food) -> bool {
-        self.head.pos == food.pos
-    }
-
-    fn eats_self(&self) -> bool {
-        for seg in &self.body {
-            if self.head.pos == seg.pos {
-                return true;
-            }
-        }
-        false
-    }
-
-    fn update(&mut self, food: &Food) {
-        if self.last_update_dir == self.dir && self.next_dir.is_some() {
-            self.dir = self.next_dir.unwrap();
-            self.next_dir = None;
-        }
-
-        let new_head_pos = GridPosition::new_from_move(self.head.pos, self.dir);
-        let new_head = Segment::new(new_head_pos);
-        self.body.push_front(self.head);
-        self.head = new_head;
-
-        if self.eats_self() {
-            self.ate = Some(Ate::Itself);
-        } else if self.eats_food(food) {
-            self.ate = Some(Ate::Food);
-        } else {
-            self.ate = None;
-        }
-
-        if self.ate.is_none() {
-            self.body.pop_back();
-        }
-
-        self.last_update_dir = self.dir;
-    }
-
-    /// larger scale games will likely need a more optimized render path
-    /// using `InstanceArray` or something similar that batches draw calls.
-    fn draw(&self, canvas: &mut graphics::Canvas) {
-        for seg in &self.body {
-            canvas.draw(
-                &graphics::Quad,
-                graphics::DrawParam::new()
-                    .dest_rect(seg.pos.into())
-                    .color([0.3, 0.3, 0.0, 1.0]),
-            );
-        }
-
-        canvas.draw(
-            &graphics::Quad,
-            graphics::DrawParam::new()
-                .dest_rect(self.head.pos.into())
-                .color([1.0, 0.5, 0.0, 1.0]),
-        );
-    }
-}
-
-struct GameState {
-    snake: Snake,
-    food: Food,
-    gameover: bool,
-    rng: Rand32,
-    sound: audio::Source,
-}
-
-impl GameState {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        let snake_pos = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
-
-        let mut seed: [u8; 8] = [0; 8];
-        getrandom::getrandom(&mut seed[..]).expect("Could not create RNG seed");
-        let mut rng = Rand32::new(u64::from_ne_bytes(seed));
-        let food_pos = GridPosition::random(&mut rng, GRID_SIZE.0, GRID_SIZE.1);
-
-        let sound = audio::Source::new(ctx, "/success.mp3")?;
-
-        Ok(GameState {
-            snake: Snake::new(snake_pos),
-            food: Food::new(food_pos),
-            gameover: false,
-            rng,
-            sound,
-        })
-    }
-
-    fn play_sound(&mut self, ctx: &mut Context) {
-        let _ = self.sound.play(ctx);
-    }
-}
-
-impl event::EventHandler<ggez::GameError> for GameState {
-    /// Update will happen on every frame before it is drawn.
-    fn update(&mut self, ctx: &mut Context) -> GameResult {
-        while ctx.time.check_update_time(DESIRED_FPS) {
-            if !self.gameover {
-                self.snake.update(&self.food);
-                if let Some(ate) = self.snake.ate {
-                    match ate {
-                        Ate::Food => {
-                            self.play_sound(ctx);
-
-                            let new_food_pos =
-                                GridPosition::random(&mut self.rng, GRID_SIZE.0, GRID_SIZE.1);
-                            self.food.pos = new_food_pos;
-                        }
-                        Ate::Itself => {
-                            self.gameover = true;
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas =
-            graphics::Canvas::from_frame(ctx, graphics::Color::from([0.0, 1.0, 0.0, 1.0]));
-
-        self.snake.draw(&mut canvas);
-        self.food.draw(&mut canvas);
-
-        canvas.finish(ctx)?;
-
-        ggez::timer::yield_now();
-        Ok(())
-    }
-
-    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
-        if let Some(dir) = input.keycode.and_then(Direction::from_keycode) {
-            if self.snake.dir != self.snake.last_update_dir && dir.inverse() != self.snake.dir {
-                self.snake.next_dir = Some(dir);
-            } else if dir.inverse() != self.snake.last_update_dir {
-                self.snake.dir = dir;
-            }
-        }
-        Ok(())
-    }
-}
-
-fn main() -> GameResult {
-    let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
-        let mut path = path::PathBuf::from(manifest_dir);
-        path.push("resources");
-        path
-    } else {
-        path::PathBuf::from("./resources")
-    };
-
-    let (mut ctx, events_loop) = ggez::ContextBuilder::new("snake", "Gray Olson")
-        .add_resource_path(resource_dir)
-        .window_setup(ggez::conf::WindowSetup::default().title("Snake!"))
-        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
-        .build()?;
-
-    let state = GameState::new(&mut ctx)?;
-    event::run(ctx, events_loop, state)
+fn main() -> ggez::GameResult {
+    graphics::run()
 }
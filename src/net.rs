@@ -0,0 +1,233 @@
+//! `--host` and `--join`: two players race on the same board over the network. Each side
+//! runs both games, its own and its rival's, and since everything follows from the seed the
+//! host hands out, only the inputs have to be sent across.
+//!
+//! The games run in lockstep. On every tick each side sends the turns its player made and
+//! then waits for the other side's before going ahead, so neither ever gets ahead of the
+//! other. Everything on the wire is little-endian:
+//!
+//! ```text
+//! handshake, host to joiner: magic "SNKN" | version: u8 | seed: u64
+//! handshake, joiner to host: magic "SNKN" | version: u8
+//! every tick, both ways:     turn count: u8 | turns: (dx: i8, dy: i8)*
+//! ```
+//!
+//! Both sides are expected to be started with the same rules; only the seed is shared.
+
+use crate::config::Config;
+use crate::{random_seed, Direction, GameEvent, GameState};
+use ggez::{GameError, GameResult};
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+const MAGIC: [u8; 4] = *b"SNKN";
+const VERSION: u8 = 1;
+
+/// The connection to the other player.
+pub struct Peer {
+    stream: TcpStream,
+    /// The other side's turns, one message per tick, read off the socket by a thread of its
+    /// own so that waiting on them never blocks drawing.
+    inbox: Receiver<Vec<Direction>>,
+    connected: bool,
+}
+
+impl Peer {
+    /// Waits for someone to join on `port`, then tells them the seed.
+    pub fn host(port: u16, seed: u64) -> GameResult<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        println!("Waiting for the other player to join on port {port}...");
+        let (mut stream, addr) = listener.accept()?;
+        log::info!("event=peer_joined addr={addr}");
+        stream.write_all(&MAGIC)?;
+        stream.write_all(&[VERSION])?;
+        stream.write_all(&seed.to_le_bytes())?;
+        read_header(&mut stream)?;
+        Peer::new(stream)
+    }
+
+    /// Connects to a host, returning the seed of the game it's hosting.
+    pub fn join(addr: &str) -> GameResult<(Self, u64)> {
+        let mut stream = TcpStream::connect(addr)?;
+        read_header(&mut stream)?;
+        let seed = u64::from_le_bytes(read_bytes(&mut stream)?);
+        stream.write_all(&MAGIC)?;
+        stream.write_all(&[VERSION])?;
+        Ok((Peer::new(stream)?, seed))
+    }
+
+    fn new(stream: TcpStream) -> GameResult<Self> {
+        // Every tick is a handful of bytes, so don't let them sit around waiting for more.
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let (sender, inbox) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(turns) = read_turns(&mut reader) {
+                if sender.send(turns).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Peer {
+            stream,
+            inbox,
+            connected: true,
+        })
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Sends this side's turns for the current tick.
+    pub fn send(&mut self, turns: &[Direction]) {
+        let mut message = vec![turns.len() as u8];
+        for dir in turns {
+            let (dx, dy) = dir.offset();
+            message.extend([dx as i8 as u8, dy as i8 as u8]);
+        }
+        if let Err(e) = self.stream.write_all(&message) {
+            log::warn!("event=peer_lost error={e}");
+            self.connected = false;
+        }
+    }
+
+    /// The other side's turns for the current tick, if they've arrived yet.
+    pub fn receive(&mut self) -> Option<Vec<Direction>> {
+        match self.inbox.try_recv() {
+            Ok(turns) => Some(turns),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                if self.connected {
+                    log::warn!("event=peer_lost");
+                }
+                self.connected = false;
+                None
+            }
+        }
+    }
+}
+
+fn invalid(msg: &str) -> GameError {
+    GameError::CustomError(format!("can't play with the other side: {msg}"))
+}
+
+fn read_header(r: &mut impl Read) -> GameResult {
+    if read_bytes::<4>(r)? != MAGIC {
+        return Err(invalid("it isn't running this game"));
+    }
+    let [version] = read_bytes(r)?;
+    if version != VERSION {
+        return Err(invalid(&format!(
+            "it speaks a different version of the protocol ({version}, expected {VERSION})"
+        )));
+    }
+    Ok(())
+}
+
+fn read_turns(r: &mut impl Read) -> GameResult<Vec<Direction>> {
+    let [count] = read_bytes(r)?;
+    (0..count)
+        .map(|_| {
+            let [dx, dy] = read_bytes(r)?;
+            Direction::from_offset((dx as i8 as i16, dy as i8 as i16))
+                .ok_or_else(|| invalid("it sent an unknown direction"))
+        })
+        .collect()
+}
+
+fn read_bytes<const N: usize>(r: &mut impl Read) -> GameResult<[u8; N]> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Connects to the other player if `--host` or `--join` asked for it, and settles the seed
+/// both games are played with.
+pub fn connect(config: &mut Config) -> GameResult<Option<Peer>> {
+    if let Some(port) = config.host {
+        let seed = *config.seed.get_or_insert_with(random_seed);
+        return Ok(Some(Peer::host(port, seed)?));
+    }
+    if let Some(addr) = &config.join {
+        let (peer, seed) = Peer::join(addr)?;
+        config.seed = Some(seed);
+        return Ok(Some(peer));
+    }
+    Ok(None)
+}
+
+/// The other player's game, played out on this side from their inputs.
+pub struct Versus {
+    peer: Peer,
+    rival: Box<GameState>,
+    /// Turns made since the last tick, held back so both sides apply them on the same tick.
+    pending: Vec<Direction>,
+    /// Turns already sent for a tick that's waiting on the other side.
+    in_flight: Option<Vec<Direction>>,
+}
+
+impl Versus {
+    /// `config` needs the seed `connect` settled on.
+    pub fn new(peer: Peer, config: Config) -> GameResult<Self> {
+        Ok(Versus {
+            peer,
+            rival: Box::new(GameState::new_headless(Config {
+                record: None,
+                hashlog: None,
+                ..config
+            })?),
+            pending: Vec::new(),
+            in_flight: None,
+        })
+    }
+
+    pub fn connected(&self) -> bool {
+        self.peer.connected()
+    }
+
+    pub fn rival(&self) -> &GameState {
+        &self.rival
+    }
+
+    /// Holds on to a turn until the next tick. A tick's turns have to fit in the count byte,
+    /// anything past that is dropped.
+    pub fn turn(&mut self, dir: Direction) {
+        if self.pending.len() < u8::MAX as usize {
+            self.pending.push(dir);
+        }
+    }
+}
+
+impl GameState {
+    /// Runs the next tick of both games once the other side's turns for it are in, or returns
+    /// `None` while they're still on the way. Losing the connection pauses the game for good.
+    pub fn step_versus(&mut self) -> Option<Vec<GameEvent>> {
+        let versus = self.versus.as_mut()?;
+        if versus.in_flight.is_none() {
+            let turns = std::mem::take(&mut versus.pending);
+            versus.peer.send(&turns);
+            versus.in_flight = Some(turns);
+        }
+        let Some(remote) = versus.peer.receive() else {
+            if !versus.connected() {
+                self.paused = true;
+            }
+            return None;
+        };
+        for dir in remote {
+            versus.rival.steer(dir);
+        }
+        versus.rival.run_tick();
+
+        for dir in versus.in_flight.take().unwrap_or_default() {
+            self.steer(dir);
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(dir);
+            }
+        }
+        Some(self.step())
+    }
+}
@@ -0,0 +1,87 @@
+//! Short-lived cosmetic particles. They live entirely on the presentation side and never
+//! touch the game's RNG, so replays and snapshots don't know about them.
+
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color};
+use oorandom::Rand32;
+use std::time::Duration;
+
+/// Pixels per second squared, pulling everything down the screen.
+const GRAVITY: f32 = 600.0;
+
+struct Particle {
+    /// The center, in pixels.
+    pos: Vec2,
+    velocity: Vec2,
+    size: Vec2,
+    color: Color,
+    lifetime: Duration,
+    age: Duration,
+}
+
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: Rand32,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: Vec::new(),
+            rng: Rand32::new(0),
+        }
+    }
+
+    /// Throws a particle the size of `rect` upwards from where it is, in a random direction
+    /// with a random speed up to `speed`.
+    pub fn burst(
+        &mut self,
+        rect: graphics::Rect,
+        color: impl Into<Color>,
+        speed: f32,
+        lifetime: Duration,
+    ) {
+        let angle = -std::f32::consts::PI * self.rng.rand_float();
+        let speed = speed * (0.5 + 0.5 * self.rng.rand_float());
+        self.particles.push(Particle {
+            pos: rect.center().into(),
+            velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+            size: Vec2::new(rect.w, rect.h),
+            color: color.into(),
+            lifetime,
+            age: Duration::ZERO,
+        });
+    }
+
+    pub fn update(&mut self, dt: Duration) {
+        let secs = dt.as_secs_f32();
+        for particle in &mut self.particles {
+            particle.velocity.y += GRAVITY * secs;
+            particle.pos += particle.velocity * secs;
+            particle.age += dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    /// Particles shrink and fade out over their lifetime.
+    pub fn draw(&self, canvas: &mut graphics::Canvas) {
+        for particle in &self.particles {
+            let left = 1.0 - particle.age.as_secs_f32() / particle.lifetime.as_secs_f32();
+            let size = particle.size * (0.5 + 0.5 * left);
+            let mut color = particle.color;
+            color.a *= left;
+            canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new()
+                    .dest_rect(graphics::Rect::new(
+                        particle.pos.x - size.x / 2.0,
+                        particle.pos.y - size.y / 2.0,
+                        size.x,
+                        size.y,
+                    ))
+                    .color(color),
+            );
+        }
+    }
+}
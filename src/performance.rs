@@ -0,0 +1,83 @@
+//! Notices when the machine can't keep up with the tick rate. Past `MAX_UPDATES_PER_FRAME`
+//! the update loop drops ticks rather than catching up on them, so a slow machine doesn't
+//! fall behind so much as quietly run the game slower than asked. This counts the ticks that
+//! run against those that get dropped, and once too many are dropped for too long, suggests a
+//! tick rate the machine can actually manage.
+
+use std::time::Duration;
+
+/// How long each measurement runs for.
+const WINDOW: Duration = Duration::from_secs(2);
+/// The share of ticks that have to run for a window to count as keeping up.
+const KEEPING_UP: f32 = 0.9;
+/// How many slow windows in a row it takes before the speed is brought down.
+const SLOW_WINDOWS: u32 = 3;
+
+#[derive(Clone, Debug)]
+pub struct Performance {
+    window_start: Option<Duration>,
+    ran: u32,
+    dropped: u32,
+    slow_windows: u32,
+    /// The share of ticks that ran in the last full window, from `0.0` to `1.0`.
+    ratio: f32,
+    /// How many ticks a second actually ran in the last full window.
+    achieved_rate: f32,
+}
+
+impl Default for Performance {
+    fn default() -> Self {
+        Performance {
+            window_start: None,
+            ran: 0,
+            dropped: 0,
+            slow_windows: 0,
+            ratio: 1.0,
+            achieved_rate: 0.0,
+        }
+    }
+}
+
+impl Performance {
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    pub fn achieved_rate(&self) -> f32 {
+        self.achieved_rate
+    }
+
+    /// Counts one frame's worth of ticks. Returns the tick rate to drop to once the game has
+    /// been too slow for `SLOW_WINDOWS` windows running.
+    pub fn record(&mut self, now: Duration, ran: u32, dropped: u32) -> Option<u32> {
+        let start = *self.window_start.get_or_insert(now);
+        self.ran += ran;
+        self.dropped += dropped;
+        let elapsed = now - start;
+        if elapsed < WINDOW {
+            return None;
+        }
+
+        let total = self.ran + self.dropped;
+        self.ratio = if total == 0 {
+            1.0
+        } else {
+            self.ran as f32 / total as f32
+        };
+        self.achieved_rate = self.ran as f32 / elapsed.as_secs_f32();
+        self.window_start = Some(now);
+        self.ran = 0;
+        self.dropped = 0;
+
+        if self.ratio >= KEEPING_UP {
+            self.slow_windows = 0;
+            return None;
+        }
+        self.slow_windows += 1;
+        if self.slow_windows < SLOW_WINDOWS {
+            return None;
+        }
+        self.slow_windows = 0;
+        Some((self.achieved_rate.floor() as u32).max(1))
+    }
+}
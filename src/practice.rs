@@ -0,0 +1,67 @@
+//! `--practice`: study a recorded game tick by tick. The whole replay is simulated once up
+//! front, keeping a snapshot every `SNAPSHOT_INTERVAL` ticks. Seeking restores the closest
+//! snapshot before the target and steps forward from there, which makes rewinding cheap
+//! without holding on to every single tick.
+
+use crate::{GameState, Snapshot, GRID_SIZE};
+
+const SNAPSHOT_INTERVAL: u64 = 50;
+
+pub struct Practice {
+    snapshots: Vec<Snapshot>,
+    tick: u64,
+    last_tick: u64,
+}
+
+impl Practice {
+    /// Plays the replay in `state` through to its end and rewinds it to the start again.
+    pub fn new(state: &mut GameState) -> Self {
+        // A snake that's left going straight after the last input wraps around forever, so
+        // stop once it can't be doing anything but that.
+        let cells = GRID_SIZE.0 as u64 * GRID_SIZE.1 as u64;
+        let max_ticks = state
+            .player
+            .as_ref()
+            .map_or(0, |player| player.last_input_tick())
+            + cells;
+
+        let mut snapshots = Vec::new();
+        let mut tick = 0;
+        while !state.gameover && tick < max_ticks {
+            if tick % SNAPSHOT_INTERVAL == 0 {
+                snapshots.push(state.snapshot());
+            }
+            state.step();
+            tick += 1;
+        }
+        state.restore(&snapshots[0]);
+
+        Practice {
+            snapshots,
+            tick: 0,
+            last_tick: tick,
+        }
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn last_tick(&self) -> u64 {
+        self.last_tick
+    }
+
+    pub fn seek(&mut self, state: &mut GameState, tick: u64) {
+        let tick = tick.min(self.last_tick);
+        if tick == self.tick + 1 {
+            state.step();
+        } else {
+            let index = ((tick / SNAPSHOT_INTERVAL) as usize).min(self.snapshots.len() - 1);
+            state.restore(&self.snapshots[index]);
+            for _ in index as u64 * SNAPSHOT_INTERVAL..tick {
+                state.step();
+            }
+        }
+        self.tick = tick;
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{GridPosition, GRID_CELL_SIZE};
+use ggez::conf::NumSamples;
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color, DrawMode, Mesh, Rect};
+use ggez::{Context, GameResult};
+
+/// The MSAA sample counts `--msaa` takes. The graphics backend doesn't support all of them,
+/// see `samples`.
+pub const MSAA_COUNTS: [u8; 5] = [1, 2, 4, 8, 16];
+
+/// The most samples per pixel the backend supports, up to `requested`. Asking for more than
+/// it can do falls back to fewer rather than failing.
+pub fn samples(requested: u8) -> NumSamples {
+    let samples = MSAA_COUNTS
+        .into_iter()
+        .rev()
+        .filter(|&count| count <= requested)
+        .find_map(|count| NumSamples::try_from(count).ok())
+        .unwrap_or(NumSamples::One);
+    if samples as u8 != requested {
+        log::warn!(
+            "{requested}x MSAA isn't supported, using {}x instead",
+            samples as u8
+        );
+    }
+    samples
+}
+
+/// How the snake's segments are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderStyle {
+    #[default]
+    Quad,
+    RoundedRect,
+    /// A retro "caterpillar" look.
+    Circle,
+}
+
+impl RenderStyle {
+    const ALL: [RenderStyle; 3] = [
+        RenderStyle::Quad,
+        RenderStyle::RoundedRect,
+        RenderStyle::Circle,
+    ];
+
+    /// The style after this one, wrapping around at the end.
+    pub fn next(self) -> Self {
+        let index = RenderStyle::ALL
+            .iter()
+            .position(|&style| style == self)
+            .unwrap();
+        RenderStyle::ALL[(index + 1) % RenderStyle::ALL.len()]
+    }
+
+    /// The name it's given on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderStyle::Quad => "quad",
+            RenderStyle::RoundedRect => "rounded",
+            RenderStyle::Circle => "circle",
+        }
+    }
+}
+
+impl std::str::FromStr for RenderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quad" => Ok(RenderStyle::Quad),
+            "rounded" => Ok(RenderStyle::RoundedRect),
+            "circle" => Ok(RenderStyle::Circle),
+            _ => Err(format!("unknown render style `{s}`")),
+        }
+    }
+}
+
+/// How many cells out from its center `--warp` reaches.
+const WARP_RADIUS: f32 = 5.0;
+
+/// Draws cells in the active `RenderStyle`. The meshes are built once at cell size and then
+/// scaled into place, rather than tessellated again for every segment on every frame.
+pub struct CellPainter {
+    style: RenderStyle,
+    /// How much of each cell is left empty on every side, as a fraction of the cell, so
+    /// neighboring segments don't run together.
+    padding: f32,
+    rounded_rect: Option<Mesh>,
+    circle: Option<Mesh>,
+    /// How strongly cells near `warp_center` are magnified with `--warp`, `0.0` for not at all.
+    warp: f32,
+    warp_center: Vec2,
+}
+
+impl CellPainter {
+    pub fn new(style: RenderStyle, padding: f32) -> Self {
+        CellPainter {
+            style,
+            padding,
+            rounded_rect: None,
+            circle: None,
+            warp: 0.0,
+            warp_center: Vec2::ZERO,
+        }
+    }
+
+    /// Turns on `--warp` at `strength`, from `0.0` for none to `1.0` for cells up to twice
+    /// their size around the warp's center.
+    pub fn set_warp(&mut self, strength: f32) {
+        self.warp = strength;
+    }
+
+    /// Moves the middle of the warp, usually to the head, in playfield coordinates.
+    pub fn warp_around(&mut self, center: Vec2) {
+        self.warp_center = center;
+    }
+
+    pub fn warping(&self) -> bool {
+        self.warp > 0.0
+    }
+
+    /// Where `rect` is drawn with the warp, making cells within `WARP_RADIUS` of the center
+    /// larger the closer they are and pushing them out to make room, like a fisheye lens.
+    /// The center itself and everything past the radius stay put.
+    pub fn warp(&self, rect: Rect) -> Rect {
+        if !self.warping() {
+            return rect;
+        }
+        let center = Vec2::from(rect.center());
+        let away = center - self.warp_center;
+        let near = (1.0 - away.length() / (WARP_RADIUS * GRID_CELL_SIZE.0 as f32)).max(0.0);
+        let k = self.warp * near * near;
+        let center = center + away * k;
+        let (w, h) = (rect.w * (1.0 + k), rect.h * (1.0 + k));
+        Rect::new(center.x - w / 2.0, center.y - h / 2.0, w, h)
+    }
+
+    pub fn style(&self) -> RenderStyle {
+        self.style
+    }
+
+    /// Switches styles. The new style's mesh is built by the next `prepare`.
+    pub fn set_style(&mut self, style: RenderStyle) {
+        self.style = style;
+    }
+
+    /// Builds the mesh the current style needs, if it hasn't been built yet.
+    pub fn prepare(&mut self, ctx: &Context) -> GameResult {
+        let cell = Rect::new(0.0, 0.0, GRID_CELL_SIZE.0 as f32, GRID_CELL_SIZE.1 as f32);
+        match self.style {
+            RenderStyle::Quad => {}
+            RenderStyle::RoundedRect => {
+                if self.rounded_rect.is_none() {
+                    self.rounded_rect = Some(Mesh::new_rounded_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        cell,
+                        cell.w.min(cell.h) / 4.0,
+                        Color::WHITE,
+                    )?);
+                }
+            }
+            RenderStyle::Circle => {
+                if self.circle.is_none() {
+                    self.circle = Some(Mesh::new_circle(
+                        ctx,
+                        DrawMode::fill(),
+                        cell.center(),
+                        cell.w.min(cell.h) / 2.0,
+                        0.1,
+                        Color::WHITE,
+                    )?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `rect` with the padding taken off every side.
+    pub fn inset(&self, rect: Rect) -> Rect {
+        let (dx, dy) = (rect.w * self.padding, rect.h * self.padding);
+        Rect::new(
+            rect.x + dx,
+            rect.y + dy,
+            rect.w - 2.0 * dx,
+            rect.h - 2.0 * dy,
+        )
+    }
+
+    /// Draws a cell over `rect`, warped if the warp is on.
+    pub fn draw(&self, canvas: &mut graphics::Canvas, rect: Rect, color: impl Into<Color>) {
+        let rect = self.inset(self.warp(rect));
+        let mesh = match self.style {
+            RenderStyle::Quad => None,
+            RenderStyle::RoundedRect => self.rounded_rect.as_ref(),
+            RenderStyle::Circle => self.circle.as_ref(),
+        };
+        match mesh {
+            Some(mesh) => canvas.draw(
+                mesh,
+                graphics::DrawParam::new()
+                    .dest(Vec2::new(rect.x, rect.y))
+                    .scale(Vec2::new(
+                        rect.w / GRID_CELL_SIZE.0 as f32,
+                        rect.h / GRID_CELL_SIZE.1 as f32,
+                    ))
+                    .color(color),
+            ),
+            // Also covers a mesh that hasn't been prepared yet.
+            None => canvas.draw(
+                &graphics::Quad,
+                graphics::DrawParam::new().dest_rect(rect).color(color),
+            ),
+        }
+    }
+}
+
+/// Somewhere the board can be drawn a cell at a time: the window's canvas, or the terminal
+/// with `--tui`, see `GameState::paint_board`.
+pub trait Surface {
+    fn fill(&mut self, pos: GridPosition, color: [f32; 4]);
+}
+
+/// The window's canvas as a `Surface`, with the cells going through the painter's warp.
+pub struct CanvasCells<'a> {
+    pub canvas: &'a mut graphics::Canvas,
+    pub painter: &'a CellPainter,
+}
+
+impl Surface for CanvasCells<'_> {
+    fn fill(&mut self, pos: GridPosition, color: [f32; 4]) {
+        self.canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(self.painter.warp(pos.into()))
+                .color(color),
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Replays only store the RNG seed, the rules and the player's inputs; since the simulation
+//! is deterministic, feeding the same inputs back on the same ticks under the same rules
+//! reproduces the whole game.
+//!
+//! The on-disk format is a small versioned binary blob:
+//!
+//! ```text
+//! magic "SNKR" | version: u8 | seed: u64 | grid width: i16 | grid height: i16
+//! | rule count: varint | rules: (flag: string, value: string)* | input count: varint
+//! | inputs: varint* | result
+//! ```
+//!
+//! The rules are the options that change how the game plays out (see
+//! `Config::replay_rules`), each a command-line flag and its value as a varint length
+//! followed by UTF-8. A replay is only played back with the same ones. Version 3 files and
+//! older don't have them.
+//!
+//! Each input packs the number of ticks since the previous input together with the
+//! direction (`delta << 3 | direction`), so a long game costs a byte or two per turn.
+//! Version 1 files predate the diagonals and only spend two bits on the direction.
+//!
+//! The result is how the game ended, for `--verify` to check a replay against:
+//! `outcome: u8 | ticks: u64 | score: u32 | length: u32 | state hash: u64`. Version 2 files
+//! and older don't have one.
+
+use crate::{Direction, GRID_SIZE};
+use ggez::{GameError, GameResult};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: [u8; 4] = *b"SNKR";
+const VERSION: u8 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayInput {
+    /// How many logical updates had already happened when the key was pressed.
+    pub tick: u64,
+    pub dir: Direction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The window was closed with the game still going.
+    Quit,
+    Died,
+    Won,
+}
+
+/// Where the recorded game got to when the replay was saved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayResult {
+    pub outcome: Outcome,
+    pub ticks: u64,
+    pub score: u32,
+    pub length: u32,
+    /// See `GameState::state_hash`.
+    pub hash: u64,
+}
+
+impl ReplayResult {
+    fn read(reader: &mut impl Read) -> GameResult<Self> {
+        let outcome = match read_bytes::<1>(reader)?[0] {
+            0 => Outcome::Quit,
+            1 => Outcome::Died,
+            2 => Outcome::Won,
+            _ => return Err(invalid("unknown outcome")),
+        };
+        Ok(ReplayResult {
+            outcome,
+            ticks: u64::from_le_bytes(read_bytes(reader)?),
+            score: u32::from_le_bytes(read_bytes(reader)?),
+            length: u32::from_le_bytes(read_bytes(reader)?),
+            hash: u64::from_le_bytes(read_bytes(reader)?),
+        })
+    }
+
+    fn write(&self, writer: &mut impl Write) -> GameResult {
+        let outcome: u8 = match self.outcome {
+            Outcome::Quit => 0,
+            Outcome::Died => 1,
+            Outcome::Won => 2,
+        };
+        writer.write_all(&[outcome])?;
+        writer.write_all(&self.ticks.to_le_bytes())?;
+        writer.write_all(&self.score.to_le_bytes())?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(&self.hash.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayFile {
+    pub seed: u64,
+    pub grid_size: (i16, i16),
+    /// The flags the game was recorded with, if the replay is new enough to say.
+    pub rules: Option<Vec<(String, String)>>,
+    pub inputs: Vec<ReplayInput>,
+    /// How the game ended, if the replay is new enough to say.
+    pub result: Option<ReplayResult>,
+}
+
+impl ReplayFile {
+    pub fn new(seed: u64, rules: Vec<(String, String)>) -> Self {
+        ReplayFile {
+            seed,
+            grid_size: GRID_SIZE,
+            rules: Some(rules),
+            inputs: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> GameResult<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> GameResult {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read(reader: &mut impl Read) -> GameResult<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid("not a replay file"));
+        }
+        let version = read_bytes::<1>(reader)?[0];
+        let direction_bits = match version {
+            1 => 2,
+            2..=VERSION => 3,
+            _ => {
+                return Err(invalid(&format!(
+                    "unsupported replay version {version} (expected {VERSION} or older)"
+                )))
+            }
+        };
+
+        let seed = u64::from_le_bytes(read_bytes(reader)?);
+        let grid_size = (
+            i16::from_le_bytes(read_bytes(reader)?),
+            i16::from_le_bytes(read_bytes(reader)?),
+        );
+        let rules = match version {
+            VERSION => {
+                let count = read_varint(reader)?;
+                let mut rules = Vec::new();
+                for _ in 0..count {
+                    rules.push((read_string(reader)?, read_string(reader)?));
+                }
+                Some(rules)
+            }
+            _ => None,
+        };
+
+        let count = read_varint(reader)?;
+        let mut inputs = Vec::new();
+        let mut tick = 0u64;
+        for _ in 0..count {
+            let packed = read_varint(reader)?;
+            tick = tick
+                .checked_add(packed >> direction_bits)
+                .ok_or_else(|| invalid("input tick overflows"))?;
+            let dir = direction_from_bits((packed & ((1 << direction_bits) - 1)) as u8)
+                .ok_or_else(|| invalid("unknown direction"))?;
+            inputs.push(ReplayInput { tick, dir });
+        }
+        let result = match version {
+            3 | VERSION => Some(ReplayResult::read(reader)?),
+            _ => None,
+        };
+
+        Ok(ReplayFile {
+            seed,
+            grid_size,
+            rules,
+            inputs,
+            result,
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> GameResult {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&self.grid_size.0.to_le_bytes())?;
+        writer.write_all(&self.grid_size.1.to_le_bytes())?;
+
+        let rules = self.rules.as_deref().unwrap_or_default();
+        write_varint(writer, rules.len() as u64)?;
+        for (flag, value) in rules {
+            write_string(writer, flag)?;
+            write_string(writer, value)?;
+        }
+
+        write_varint(writer, self.inputs.len() as u64)?;
+        let mut last_tick = 0;
+        for input in &self.inputs {
+            let delta = input.tick - last_tick;
+            write_varint(writer, delta << 3 | direction_to_bits(input.dir) as u64)?;
+            last_tick = input.tick;
+        }
+        // Only a recording that's been saved has a result, and it always gets one.
+        let result = self
+            .result
+            .ok_or_else(|| invalid("the game has no result yet"))?;
+        result.write(writer)
+    }
+}
+
+/// Collects the inputs of the game being played so they can be saved as a replay.
+pub struct Recorder {
+    path: PathBuf,
+    file: ReplayFile,
+    tick: u64,
+}
+
+impl Recorder {
+    pub fn new(path: PathBuf, seed: u64, rules: Vec<(String, String)>) -> Self {
+        Recorder {
+            path,
+            file: ReplayFile::new(seed, rules),
+            tick: 0,
+        }
+    }
+
+    pub fn record(&mut self, dir: Direction) {
+        self.file.inputs.push(ReplayInput {
+            tick: self.tick,
+            dir,
+        });
+    }
+
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    /// Saves what's been recorded so far, ending with `result`.
+    pub fn save(&mut self, result: ReplayResult) -> GameResult {
+        self.file.result = Some(result);
+        self.file.save(&self.path)
+    }
+}
+
+/// Where a `Player` is in its input stream, so playback can be rewound by restoring it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cursor {
+    next: usize,
+    tick: u64,
+}
+
+/// Feeds the inputs of a recorded game back in on the ticks they originally happened.
+pub struct Player {
+    file: ReplayFile,
+    cursor: Cursor,
+}
+
+impl Player {
+    /// Refuses replays recorded on another grid or, where the file says, under rules other
+    /// than `rules`, since they would play out differently.
+    pub fn new(file: ReplayFile, rules: &[(String, String)]) -> GameResult<Self> {
+        if file.grid_size != GRID_SIZE {
+            return Err(invalid(&format!(
+                "replay was recorded on a {}x{} grid, but this game uses {}x{}",
+                file.grid_size.0, file.grid_size.1, GRID_SIZE.0, GRID_SIZE.1
+            )));
+        }
+        let recorded = file.rules.as_deref().unwrap_or(rules);
+        let mismatches: Vec<_> = recorded
+            .iter()
+            .filter_map(|(flag, value)| {
+                let ours = rules.iter().find(|(ours, _)| ours == flag);
+                match ours {
+                    Some((_, ours)) if ours == value => None,
+                    Some((_, ours)) => Some(format!(
+                        "replay was recorded with {flag} {value}, but this game uses {flag} {ours}"
+                    )),
+                    None => Some(format!(
+                        "replay was recorded with {flag} {value}, which this game doesn't know"
+                    )),
+                }
+            })
+            .collect();
+        if !mismatches.is_empty() {
+            return Err(invalid(&mismatches.join("; ")));
+        }
+        Ok(Player {
+            file,
+            cursor: Cursor::default(),
+        })
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.file.seed
+    }
+
+    /// The tick of the last recorded input; the game may carry on for a while after that.
+    pub fn last_input_tick(&self) -> u64 {
+        self.file.inputs.last().map_or(0, |input| input.tick)
+    }
+
+    pub fn cursor(&self) -> Cursor {
+        self.cursor
+    }
+
+    pub fn seek(&mut self, cursor: Cursor) {
+        self.cursor = cursor;
+    }
+
+    /// Returns the inputs that were pressed before the current tick, then moves on to the next one.
+    pub fn advance(&mut self) -> &[ReplayInput] {
+        let inputs = &self.file.inputs;
+        let Cursor { next, tick } = &mut self.cursor;
+        let start = *next;
+        while *next < inputs.len() && inputs[*next].tick <= *tick {
+            *next += 1;
+        }
+        *tick += 1;
+        &inputs[start..*next]
+    }
+}
+
+fn invalid(msg: &str) -> GameError {
+    GameError::ResourceLoadError(format!("invalid replay: {msg}"))
+}
+
+fn direction_to_bits(dir: Direction) -> u8 {
+    match dir {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+        Direction::UpLeft => 4,
+        Direction::UpRight => 5,
+        Direction::DownLeft => 6,
+        Direction::DownRight => 7,
+    }
+}
+
+fn direction_from_bits(bits: u8) -> Option<Direction> {
+    Some(match bits {
+        0 => Direction::Up,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        3 => Direction::Right,
+        4 => Direction::UpLeft,
+        5 => Direction::UpRight,
+        6 => Direction::DownLeft,
+        7 => Direction::DownRight,
+        _ => return None,
+    })
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> GameResult<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// LEB128: seven bits per byte, with the high bit set on every byte but the last.
+fn read_varint(reader: &mut impl Read) -> GameResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_bytes::<1>(reader)?[0];
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("varint is too long"))
+}
+
+fn read_string(reader: &mut impl Read) -> GameResult<String> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(invalid("string runs past the end of the file"));
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("string isn't UTF-8"))
+}
+
+fn write_string(writer: &mut impl Write, s: &str) -> GameResult {
+    write_varint(writer, s.len() as u64)?;
+    writer.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn write_varint(writer: &mut impl Write, mut value: u64) -> GameResult {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            writer.write_all(&[byte])?;
+            return Ok(());
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> Vec<ReplayInput> {
+        [
+            (0, Direction::Up),
+            (3, Direction::Left),
+            (3, Direction::Down),
+            (200, Direction::Right),
+        ]
+        .map(|(tick, dir)| ReplayInput { tick, dir })
+        .to_vec()
+    }
+
+    fn result() -> ReplayResult {
+        ReplayResult {
+            outcome: Outcome::Died,
+            ticks: 512,
+            score: 9,
+            length: 11,
+            hash: 0xdead_beef_cafe_f00d,
+        }
+    }
+
+    fn rules() -> Vec<(String, String)> {
+        [("--wrap-mode", "Walls"), ("--input-mode", "Queued")]
+            .map(|(flag, value)| (flag.to_string(), value.to_string()))
+            .to_vec()
+    }
+
+    /// A file from before the rules were saved, with `direction_bits` bits a direction.
+    /// Version 3 is the only one of those with a result.
+    fn old_file(version: u8, direction_bits: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(version);
+        bytes.extend(7u64.to_le_bytes());
+        bytes.extend(GRID_SIZE.0.to_le_bytes());
+        bytes.extend(GRID_SIZE.1.to_le_bytes());
+        let inputs = inputs();
+        write_varint(&mut bytes, inputs.len() as u64).unwrap();
+        let mut last_tick = 0;
+        for input in inputs {
+            let packed = (input.tick - last_tick) << direction_bits;
+            write_varint(&mut bytes, packed | direction_to_bits(input.dir) as u64).unwrap();
+            last_tick = input.tick;
+        }
+        if version == 3 {
+            result().write(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn round_trips_with_result() {
+        let file = ReplayFile {
+            inputs: inputs(),
+            result: Some(result()),
+            ..ReplayFile::new(42, rules())
+        };
+        let mut bytes = Vec::new();
+        file.write(&mut bytes).unwrap();
+        assert_eq!(ReplayFile::read(&mut bytes.as_slice()).unwrap(), file);
+    }
+
+    #[test]
+    fn reads_old_versions_without_result() {
+        for (version, direction_bits) in [(1, 2), (2, 3)] {
+            let file = ReplayFile::read(&mut old_file(version, direction_bits).as_slice())
+                .unwrap_or_else(|e| panic!("version {version}: {e}"));
+            assert_eq!(file.seed, 7);
+            assert_eq!(file.grid_size, GRID_SIZE);
+            assert_eq!(file.inputs, inputs(), "version {version}");
+            assert_eq!(file.rules, None);
+            assert_eq!(file.result, None);
+        }
+    }
+
+    #[test]
+    fn reads_version_3_with_result_but_no_rules() {
+        let file = ReplayFile::read(&mut old_file(3, 3).as_slice()).unwrap();
+        assert_eq!(file.inputs, inputs());
+        assert_eq!(file.rules, None);
+        assert_eq!(file.result, Some(result()));
+        assert!(Player::new(file, &rules()).is_ok());
+    }
+
+    #[test]
+    fn refuses_to_play_under_other_rules() {
+        assert!(Player::new(ReplayFile::new(1, rules()), &rules()).is_ok());
+
+        let mut ours = rules();
+        ours[0].1 = "Wrap".to_string();
+        let err = Player::new(ReplayFile::new(1, rules()), &ours)
+            .err()
+            .expect("mismatched wrap mode should be refused")
+            .to_string();
+        assert!(
+            err.contains("recorded with --wrap-mode Walls, but this game uses --wrap-mode Wrap"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn writing_needs_a_result() {
+        assert!(ReplayFile::new(1, rules()).write(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_magic_and_version() {
+        let mut bad_magic = old_file(2, 3);
+        bad_magic[0] = b'X';
+        assert!(ReplayFile::read(&mut bad_magic.as_slice()).is_err());
+
+        for version in [0, VERSION + 1] {
+            let mut bytes = old_file(2, 3);
+            bytes[MAGIC.len()] = version;
+            assert!(
+                ReplayFile::read(&mut bytes.as_slice()).is_err(),
+                "version {version}"
+            );
+        }
+    }
+}
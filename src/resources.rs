@@ -0,0 +1,45 @@
+//! Finding the `resources` directory, which holds the sounds, whether the game is run from a
+//! cargo checkout or installed somewhere else.
+
+use ggez::{GameError, GameResult};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Points at the resources directory, for when it isn't next to the executable.
+const RESOURCES_ENV: &str = "SNAKE_RESOURCES";
+
+/// The first of these that exists: `explicit` (from `--resources`), `$SNAKE_RESOURCES`,
+/// `resources` next to the executable, `resources` in the cargo manifest directory, and
+/// finally `./resources`.
+pub fn find(explicit: Option<&Path>) -> GameResult<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Some(dir) = explicit {
+        candidates.push(dir.to_path_buf());
+    }
+    if let Some(dir) = env::var_os(RESOURCES_ENV) {
+        candidates.push(PathBuf::from(dir));
+    }
+    if let Some(exe_dir) = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        candidates.push(exe_dir.join("resources"));
+    }
+    if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        candidates.push(PathBuf::from(manifest_dir).join("resources"));
+    }
+    candidates.push(PathBuf::from("./resources"));
+
+    if let Some(found) = candidates.iter().find(|dir| dir.is_dir()) {
+        return Ok(found.clone());
+    }
+    let tried: Vec<String> = candidates
+        .iter()
+        .map(|dir| dir.display().to_string())
+        .collect();
+    Err(GameError::ResourceLoadError(format!(
+        "could not find the resources directory, tried {}; pass it with `--resources <dir>` \
+         or ${RESOURCES_ENV}",
+        tried.join(", ")
+    )))
+}
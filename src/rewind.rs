@@ -0,0 +1,81 @@
+//! `--rewind-pellets CHANCE`: now and then the food is a cyan rewind pellet, and eating one
+//! puts the snake back where it was `REWIND_TICKS` moves before, to take back a bad turn.
+//! The pellet still counts as eaten, and the food turns up again as usual.
+//!
+//! Only eating a pellet ever rewinds. The snakes of the last few moves are kept for it, which
+//! goes into saves and practice snapshots along with the rest of the game.
+
+use crate::body::Body;
+use crate::{GameState, GridPosition, Snake};
+use std::collections::VecDeque;
+
+/// How many moves back eating a rewind pellet goes.
+pub const REWIND_TICKS: usize = 5;
+
+#[derive(Clone)]
+pub struct Rewind {
+    /// The chance, from `0.0` to `1.0`, that a piece of food that's nothing else is a pellet.
+    pub chance: f32,
+    /// The snakes as they were before each of the last `REWIND_TICKS` moves, oldest first,
+    /// the lead and then the rest of the swarm.
+    pub history: VecDeque<Vec<Snake>>,
+    /// Where the snakes were when they got rewound, only for drawing.
+    pub abandoned: Vec<GridPosition>,
+}
+
+impl Rewind {
+    pub fn new(chance: f32) -> Self {
+        Rewind {
+            chance,
+            history: VecDeque::with_capacity(REWIND_TICKS + 1),
+            abandoned: Vec::new(),
+        }
+    }
+
+    /// Keeps `snakes` as they were before a move, forgetting anything too far back.
+    pub fn record(&mut self, snakes: Vec<Snake>) {
+        self.history.push_back(snakes);
+        if self.history.len() > REWIND_TICKS {
+            self.history.pop_front();
+        }
+    }
+
+    /// The oldest snakes kept, and how many moves back that is, using them up. Early on
+    /// there's less to go back to, and none at all on the first move, so a rewind never
+    /// goes back past the start of the game.
+    pub fn take_oldest(&mut self) -> Option<(Vec<Snake>, usize)> {
+        let ticks = self.history.len();
+        let oldest = self.history.pop_front()?;
+        self.history.clear();
+        Some((oldest, ticks))
+    }
+}
+
+impl GameState {
+    /// Puts the snakes back as they were the oldest move kept, returning how many moves back
+    /// that was, or `None` if there's nothing to go back to. Food that decayed since could have
+    /// left an obstacle where a snake was, and then the snakes stay where they are.
+    pub fn rewind_snakes(&mut self) -> Option<usize> {
+        let abandoned: Vec<GridPosition> = self
+            .snakes()
+            .flat_map(|snake| {
+                [snake.head.pos]
+                    .into_iter()
+                    .chain(snake.body.iter().map(|seg| seg.pos))
+            })
+            .collect();
+        let rewind = self.rewind.as_mut()?;
+        let (mut snakes, ticks) = rewind.take_oldest()?;
+        let obstacles = &self.obstacles;
+        if snakes
+            .iter()
+            .any(|snake| obstacles.iter().any(|&pos| snake.occupies(pos)))
+        {
+            return None;
+        }
+        rewind.abandoned = abandoned;
+        self.swarm = snakes.split_off(1);
+        self.snake = snakes.pop()?;
+        Some(ticks)
+    }
+}
@@ -0,0 +1,456 @@
+//! `--resume`: quitting in the middle of a game saves it, and `--resume` picks it up again
+//! exactly where it was left, RNG included, so the food keeps turning up where it would have.
+//! A save can only be resumed once.
+//!
+//! Like replays, saves are a small versioned binary blob, little-endian throughout:
+//!
+//! ```text
+//! magic "SNKS" | version: u8 | rules | score and RNG | snakes | food | the rest of the board
+//! ```
+//!
+//! Only the game itself is saved. How it looks and sounds comes from the command line of the
+//! session that resumes it.
+
+use crate::body::{Body, RingBuffer};
+use crate::chain::FoodChain;
+use crate::combo::Combo;
+use crate::config::Config;
+use crate::gravity::Gravity;
+use crate::rewind::Rewind;
+use crate::scores::data_dir;
+use crate::{
+    Direction, EventPriority, Food, FoodKind, GameState, GridPosition, Rules, Scoring, Segment,
+    SelfPenalty, Snake, WrapMode, GRID_SIZE,
+};
+use ggez::{GameError, GameResult};
+use oorandom::Rand32;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+const MAGIC: [u8; 4] = *b"SNKS";
+const VERSION: u8 = 17;
+const SAVE_FILE: &str = "save.bin";
+
+pub fn path() -> PathBuf {
+    data_dir().join(SAVE_FILE)
+}
+
+impl GameState {
+    pub fn save_game(&self) -> GameResult {
+        let path = path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(&MAGIC)?;
+        w.write_all(&[VERSION])?;
+
+        let rules = self.rules;
+        let wrap_mode = match rules.wrap_mode {
+            WrapMode::Wrap => 0,
+            WrapMode::Walls => 1,
+            WrapMode::Bounce => 2,
+        };
+        w.write_all(&[
+            wrap_mode,
+            rules.wall_assist as u8,
+            rules.mirror as u8,
+            rules.reverse_kills as u8,
+            match rules.event_priority {
+                EventPriority::CollisionFirst => 0,
+                EventPriority::FoodFirst => 1,
+            },
+        ])?;
+        write_u32(&mut w, rules.wrap_cooldown)?;
+        write_u32(&mut w, rules.grace_period)?;
+        match self.scoring {
+            Scoring::Flat => write_u32(&mut w, 0)?,
+            Scoring::Distance { cells_per_point } => write_u32(&mut w, cells_per_point)?,
+        }
+        write_option_u32(&mut w, self.near_miss_bonus)?;
+        write_u32(&mut w, self.food_respawn_delay)?;
+        w.write_all(&self.bonus_food.to_le_bytes())?;
+        w.write_all(&self.power_pellets.to_le_bytes())?;
+
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&self.tick.to_le_bytes())?;
+        let (state, increment) = self.rng.state();
+        w.write_all(&state.to_le_bytes())?;
+        w.write_all(&increment.to_le_bytes())?;
+        write_u32(&mut w, self.score)?;
+        write_option_u32(&mut w, self.lives)?;
+        write_option_u32(&mut w, self.self_penalty.map(|penalty| penalty.points))?;
+        write_u32(
+            &mut w,
+            self.self_penalty.map_or(0, |penalty| penalty.segments),
+        )?;
+        write_u32(&mut w, self.max_len as u32)?;
+        write_pos(&mut w, self.last_food)?;
+        write_u32(&mut w, self.reverse_cooldown)?;
+        write_u32(&mut w, self.tail_bite_cooldown)?;
+        write_u32(&mut w, self.wrap_cooldown)?;
+        write_option_u32(
+            &mut w,
+            self.gravity.as_ref().map(|gravity| gravity.interval),
+        )?;
+        if let Some(gravity) = &self.gravity {
+            write_u32(&mut w, gravity.ticks_until_turn)?;
+            write_u32(&mut w, gravity.turns)?;
+        }
+        write_u32(&mut w, self.ticks_since_steer)?;
+        write_option_u32(&mut w, self.combo.as_ref().map(|combo| combo.window))?;
+        if let Some(combo) = &self.combo {
+            write_u32(&mut w, combo.ticks_left)?;
+            write_u32(&mut w, combo.streak)?;
+        }
+        write_option_u32(
+            &mut w,
+            self.rewind.as_ref().map(|rewind| rewind.chance.to_bits()),
+        )?;
+        if let Some(rewind) = &self.rewind {
+            write_u32(&mut w, rewind.history.len() as u32)?;
+            for snakes in &rewind.history {
+                write_u32(&mut w, snakes.len() as u32)?;
+                for snake in snakes {
+                    write_snake(&mut w, snake)?;
+                }
+            }
+        }
+
+        write_snake(&mut w, &self.snake)?;
+        write_u32(&mut w, self.swarm.len() as u32)?;
+        for snake in &self.swarm {
+            write_snake(&mut w, snake)?;
+        }
+
+        let food = &self.food;
+        write_pos(&mut w, food.pos)?;
+        w.write_all(&[food.kind as u8])?;
+        w.write_all(&food.velocity.to_le_bytes())?;
+        write_u32(&mut w, food.move_interval)?;
+        write_u32(&mut w, food.ticks_until_move)?;
+        write_positions(&mut w, food.trail.as_slice())?;
+        write_u32(&mut w, food.decay_time)?;
+        write_u32(&mut w, food.ticks_until_decay)?;
+        write_u32(&mut w, food.shrink_time)?;
+        write_u32(&mut w, food.age)?;
+        write_u32(&mut w, self.food_respawn_in)?;
+        write_option_u32(&mut w, self.chain.as_ref().map(|chain| chain.length))?;
+        if let Some(chain) = &self.chain {
+            write_u32(&mut w, chain.number)?;
+            write_positions(&mut w, &chain.upcoming.iter().copied().collect::<Vec<_>>())?;
+        }
+
+        write_positions(&mut w, &self.obstacles.iter().copied().collect::<Vec<_>>())?;
+        write_positions(
+            &mut w,
+            &self.food_sequence.iter().copied().collect::<Vec<_>>(),
+        )?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Sets up a game as `GameState::new_headless` would, then puts it back the way it was
+    /// saved. The save is removed once it's been read.
+    pub fn load_game(config: Config) -> GameResult<Self> {
+        let path = path();
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(GameError::ResourceLoadError(
+                    "there's no saved game to resume".to_string(),
+                ))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut state = GameState::new_headless(config)?;
+        state.read_save(&mut BufReader::new(file))?;
+        fs::remove_file(path)?;
+        log::info!("event=resume seed={} score={}", state.seed, state.score);
+        Ok(state)
+    }
+
+    fn read_save(&mut self, r: &mut impl Read) -> GameResult {
+        if read_bytes::<4>(r)? != MAGIC {
+            return Err(invalid("not a saved game"));
+        }
+        let version = read_u8(r)?;
+        if version != VERSION {
+            return Err(invalid(&format!(
+                "it was saved by a different version of the game (format {version}, \
+                 expected {VERSION})"
+            )));
+        }
+
+        let wrap_mode = match read_u8(r)? {
+            0 => WrapMode::Wrap,
+            1 => WrapMode::Walls,
+            2 => WrapMode::Bounce,
+            _ => return Err(invalid("unknown wrap mode")),
+        };
+        self.rules = Rules {
+            wrap_mode,
+            wall_assist: read_u8(r)? != 0,
+            mirror: read_u8(r)? != 0,
+            reverse_kills: read_u8(r)? != 0,
+            event_priority: match read_u8(r)? {
+                0 => EventPriority::CollisionFirst,
+                1 => EventPriority::FoodFirst,
+                _ => return Err(invalid("unknown event priority")),
+            },
+            wrap_cooldown: read_u32(r)?,
+            grace_period: read_u32(r)?,
+        };
+        self.scoring = match read_u32(r)? {
+            0 => Scoring::Flat,
+            cells_per_point => Scoring::Distance { cells_per_point },
+        };
+        self.near_miss_bonus = read_option_u32(r)?;
+        self.food_respawn_delay = read_u32(r)?;
+        self.bonus_food = f32::from_le_bytes(read_bytes(r)?);
+        self.power_pellets = f32::from_le_bytes(read_bytes(r)?);
+
+        self.seed = u64::from_le_bytes(read_bytes(r)?);
+        self.tick = u64::from_le_bytes(read_bytes(r)?);
+        let rng_state = u64::from_le_bytes(read_bytes(r)?);
+        let rng_increment = u64::from_le_bytes(read_bytes(r)?);
+        self.rng = Rand32::from_state((rng_state, rng_increment));
+        self.score = read_u32(r)?;
+        self.lives = read_option_u32(r)?;
+        let penalty_points = read_option_u32(r)?;
+        let penalty_segments = read_u32(r)?;
+        self.self_penalty = penalty_points.map(|points| SelfPenalty {
+            points,
+            segments: penalty_segments,
+        });
+        self.max_len = read_u32(r)? as usize;
+        self.last_food = read_pos(r)?;
+        self.reverse_cooldown = read_u32(r)?;
+        self.tail_bite_cooldown = read_u32(r)?;
+        self.wrap_cooldown = read_u32(r)?;
+        self.gravity = match read_option_u32(r)? {
+            Some(interval) => Some(Gravity {
+                interval,
+                ticks_until_turn: read_u32(r)?,
+                turns: read_u32(r)?,
+            }),
+            None => None,
+        };
+        self.ticks_since_steer = read_u32(r)?;
+        self.combo = match read_option_u32(r)? {
+            Some(window) => Some(Combo {
+                window,
+                ticks_left: read_u32(r)?,
+                streak: read_u32(r)?,
+            }),
+            None => None,
+        };
+        self.rewind = match read_option_u32(r)? {
+            Some(chance) => {
+                let mut rewind = Rewind::new(f32::from_bits(chance));
+                for _ in 0..read_u32(r)? {
+                    let count = read_u32(r)?;
+                    let snakes = (0..count)
+                        .map(|_| read_snake(r))
+                        .collect::<GameResult<_>>()?;
+                    rewind.history.push_back(snakes);
+                }
+                Some(rewind)
+            }
+            None => None,
+        };
+
+        self.snake = read_snake(r)?;
+        let swarm_len = read_u32(r)?;
+        self.swarm = (0..swarm_len)
+            .map(|_| read_snake(r))
+            .collect::<GameResult<_>>()?;
+
+        let pos = read_pos(r)?;
+        let kind = match read_u8(r)? {
+            0 => FoodKind::Standard,
+            1 => FoodKind::Bonus,
+            2 => FoodKind::Power,
+            3 => FoodKind::Rewind,
+            _ => return Err(invalid("unknown kind of food")),
+        };
+        let velocity = i16::from_le_bytes(read_bytes(r)?);
+        let move_interval = read_u32(r)?;
+        let ticks_until_move = read_u32(r)?;
+        let trail = read_positions(r)?.first().copied();
+        let decay_time = read_u32(r)?;
+        self.food = Food {
+            pos,
+            kind,
+            velocity,
+            move_interval,
+            ticks_until_move,
+            trail,
+            decay_time,
+            ticks_until_decay: read_u32(r)?,
+            shrink_time: read_u32(r)?,
+            age: read_u32(r)?,
+        };
+        self.food_respawn_in = read_u32(r)?;
+        self.chain = match read_option_u32(r)? {
+            Some(length) => Some(FoodChain {
+                length,
+                number: read_u32(r)?,
+                upcoming: read_positions(r)?.into(),
+            }),
+            None => None,
+        };
+
+        self.obstacles = read_positions(r)?.into_iter().collect::<HashSet<_>>();
+        self.food_sequence = read_positions(r)?.into();
+        Ok(())
+    }
+}
+
+fn invalid(msg: &str) -> GameError {
+    GameError::ResourceLoadError(format!("invalid saved game: {msg}"))
+}
+
+fn write_u32(w: &mut impl Write, value: u32) -> GameResult {
+    w.write_all(&value.to_le_bytes())?;
+    Ok(())
+}
+
+fn write_option_u32(w: &mut impl Write, value: Option<u32>) -> GameResult {
+    match value {
+        Some(value) => {
+            w.write_all(&[1])?;
+            write_u32(w, value)
+        }
+        None => {
+            w.write_all(&[0])?;
+            Ok(())
+        }
+    }
+}
+
+fn write_pos(w: &mut impl Write, pos: GridPosition) -> GameResult {
+    w.write_all(&pos.x.to_le_bytes())?;
+    w.write_all(&pos.y.to_le_bytes())?;
+    Ok(())
+}
+
+/// A count followed by that many positions.
+fn write_positions(w: &mut impl Write, positions: &[GridPosition]) -> GameResult {
+    write_u32(w, positions.len() as u32)?;
+    for &pos in positions {
+        write_pos(w, pos)?;
+    }
+    Ok(())
+}
+
+/// Directions are stored as their offset, one signed byte per axis.
+fn write_dir(w: &mut impl Write, dir: Direction) -> GameResult {
+    let (dx, dy) = dir.offset();
+    w.write_all(&[dx as i8 as u8, dy as i8 as u8])?;
+    Ok(())
+}
+
+fn write_snake(w: &mut impl Write, snake: &Snake) -> GameResult {
+    let cells: Vec<GridPosition> = [snake.head.pos]
+        .into_iter()
+        .chain(snake.body.iter().map(|seg| seg.pos))
+        .collect();
+    write_positions(w, &cells)?;
+    write_dir(w, snake.dir)?;
+    write_dir(w, snake.last_update_dir)?;
+    match snake.next_dir {
+        Some(dir) => {
+            w.write_all(&[1])?;
+            write_dir(w, dir)?;
+        }
+        None => w.write_all(&[0])?,
+    }
+    write_option_u32(w, snake.max_length.map(|max| max as u32))?;
+    w.write_all(&[snake.close_to_body as u8])?;
+    write_u32(w, snake.grace_ticks)?;
+    write_u32(w, snake.invulnerable_ticks)?;
+    write_u32(w, snake.powered_ticks)?;
+    write_u32(w, snake.stunned_ticks)?;
+    Ok(())
+}
+
+fn read_bytes<const N: usize>(r: &mut impl Read) -> GameResult<[u8; N]> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8(r: &mut impl Read) -> GameResult<u8> {
+    Ok(read_bytes::<1>(r)?[0])
+}
+
+fn read_u32(r: &mut impl Read) -> GameResult<u32> {
+    Ok(u32::from_le_bytes(read_bytes(r)?))
+}
+
+fn read_option_u32(r: &mut impl Read) -> GameResult<Option<u32>> {
+    Ok(match read_u8(r)? {
+        0 => None,
+        _ => Some(read_u32(r)?),
+    })
+}
+
+fn read_pos(r: &mut impl Read) -> GameResult<GridPosition> {
+    let x = i16::from_le_bytes(read_bytes(r)?);
+    let y = i16::from_le_bytes(read_bytes(r)?);
+    if !(0..GRID_SIZE.0).contains(&x) || !(0..GRID_SIZE.1).contains(&y) {
+        return Err(invalid(&format!("{x},{y} is off the grid")));
+    }
+    Ok(GridPosition::new(x, y))
+}
+
+fn read_positions(r: &mut impl Read) -> GameResult<Vec<GridPosition>> {
+    let count = read_u32(r)?;
+    (0..count).map(|_| read_pos(r)).collect()
+}
+
+fn read_snake(r: &mut impl Read) -> GameResult<Snake> {
+    let cells = read_positions(r)?;
+    let Some((&head, rest)) = cells.split_first() else {
+        return Err(invalid("the snake has no head"));
+    };
+    let mut body = RingBuffer::with_capacity(GRID_SIZE.0 as usize * GRID_SIZE.1 as usize);
+    for &pos in rest.iter().rev() {
+        Body::push_front(&mut body, Segment::new(pos));
+    }
+    let dir = read_dir(r)?;
+    let last_update_dir = read_dir(r)?;
+    let next_dir = match read_u8(r)? {
+        0 => None,
+        _ => Some(read_dir(r)?),
+    };
+    let max_length = read_option_u32(r)?.map(|max| max as usize);
+    Ok(Snake {
+        head: Segment::new(head),
+        dir,
+        body,
+        ate: None,
+        last_update_dir,
+        next_dir,
+        max_length,
+        grown_at: None,
+        close_to_body: read_u8(r)? != 0,
+        wrapped: false,
+        grace_ticks: read_u32(r)?,
+        invulnerable_ticks: read_u32(r)?,
+        powered_ticks: read_u32(r)?,
+        bitten: 0,
+        stunned_ticks: read_u32(r)?,
+        held: false,
+        last_tail: None,
+    })
+}
+
+fn read_dir(r: &mut impl Read) -> GameResult<Direction> {
+    let [dx, dy] = read_bytes(r)?;
+    Direction::from_offset((dx as i8 as i16, dy as i8 as i16))
+        .ok_or_else(|| invalid("unknown direction"))
+}
@@ -0,0 +1,222 @@
+//! `--scenario FILE`: practicing one tricky spot over and over. The game starts from the
+//! board the file sets up instead of a fresh one, and each time the snake dies it goes
+//! straight back to that board, food and random number generator included, so every attempt
+//! plays out the same until the player does something different.
+//!
+//! A scenario file has one thing per line, with blank lines and ones starting with `#`
+//! skipped:
+//!
+//! ```text
+//! # Boxed in under the top wall
+//! snake 5,1 4,1 3,1 3,2 3,3
+//! direction up
+//! food 8,0
+//! obstacles 6,0 6,1 6,2
+//! ```
+//!
+//! The snake is listed head first, each cell a move on from the one before. `direction` can
+//! be left out for the snake to carry on the way it's going, and `obstacles` can be split
+//! over as many lines as is handy.
+
+use crate::body::{Body, RingBuffer};
+use crate::{Direction, GameState, GridPosition, Segment, Snapshot, WrapMode, GRID_SIZE};
+use ggez::{GameError, GameResult};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+pub struct Scenario {
+    /// Head first.
+    snake: Vec<GridPosition>,
+    dir: Direction,
+    /// How the head got where it is, from the neck.
+    came_from: Direction,
+    food: GridPosition,
+    obstacles: HashSet<GridPosition>,
+}
+
+/// The board a scenario starts from, to go back to on dying.
+pub struct ScenarioStart {
+    start: Snapshot,
+    attempt: u32,
+}
+
+/// Reads a scenario, checking that it makes sense on a board with `wrap_mode`: the snake is
+/// in one piece, doesn't cross itself and isn't heading back into its own neck, and nothing
+/// else is on top of it.
+pub fn load(path: &Path, wrap_mode: WrapMode, diagonals: bool) -> GameResult<Scenario> {
+    let contents = fs::read_to_string(path)?;
+    let invalid = |line_no: usize, msg: String| {
+        GameError::ResourceLoadError(format!("{}:{line_no}: {msg}", path.display()))
+    };
+
+    let mut snake = None;
+    let mut dir = None;
+    let mut food = None;
+    let mut obstacles = HashSet::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let cells = || {
+            value
+                .split_whitespace()
+                .map(|cell| parse_cell(cell).map_err(|msg| invalid(line_no, msg)))
+                .collect::<GameResult<Vec<_>>>()
+        };
+        match key {
+            "snake" => snake = Some((line_no, cells()?)),
+            "direction" => {
+                let parsed: Direction =
+                    value.trim().parse().map_err(|msg| invalid(line_no, msg))?;
+                dir = Some((line_no, parsed));
+            }
+            "food" => match cells()?[..] {
+                [pos] => food = Some((line_no, pos)),
+                _ => {
+                    return Err(invalid(
+                        line_no,
+                        "expected one `x,y` for the food".to_string(),
+                    ))
+                }
+            },
+            "obstacles" => obstacles.extend(cells()?),
+            _ => return Err(invalid(line_no, format!("unknown line `{line}`"))),
+        }
+    }
+
+    let missing =
+        |what: &str| GameError::ResourceLoadError(format!("{}: no `{what}` line", path.display()));
+    let (snake_line, snake) = snake.ok_or_else(|| missing("snake"))?;
+    let (food_line, food) = food.ok_or_else(|| missing("food"))?;
+    if snake.len() < 2 {
+        return Err(invalid(
+            snake_line,
+            "the snake needs a head and at least one more cell".to_string(),
+        ));
+    }
+    let moves: Vec<Direction> = (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+        .filter_map(Direction::from_offset)
+        .filter(|dir| diagonals || !dir.is_diagonal())
+        .collect();
+    let step = |from: GridPosition, to: GridPosition| {
+        moves
+            .iter()
+            .copied()
+            .find(|&dir| GridPosition::moved(from, dir, wrap_mode) == Some(to))
+    };
+    for (i, pair) in snake.windows(2).enumerate() {
+        let (cell, next) = (pair[0], pair[1]);
+        if snake[..=i].contains(&next) {
+            return Err(invalid(
+                snake_line,
+                format!("the snake crosses itself at {},{}", next.x, next.y),
+            ));
+        }
+        if step(next, cell).is_none() {
+            return Err(invalid(
+                snake_line,
+                format!(
+                    "{},{} isn't a move on from {},{}, the snake is in pieces",
+                    next.x, next.y, cell.x, cell.y
+                ),
+            ));
+        }
+    }
+    let came_from = step(snake[1], snake[0]).expect("checked above");
+    let dir = match dir {
+        Some((line_no, dir)) => {
+            if dir.is_diagonal() && !diagonals {
+                return Err(invalid(
+                    line_no,
+                    "a diagonal direction needs `--diagonals`".to_string(),
+                ));
+            }
+            if GridPosition::moved(snake[0], dir, wrap_mode) == Some(snake[1]) {
+                return Err(invalid(
+                    line_no,
+                    "the snake can't head back into its own neck".to_string(),
+                ));
+            }
+            dir
+        }
+        None => came_from,
+    };
+    if snake.contains(&food) || obstacles.contains(&food) {
+        return Err(invalid(
+            food_line,
+            format!("the food at {},{} is on something else", food.x, food.y),
+        ));
+    }
+    if let Some(pos) = snake.iter().find(|pos| obstacles.contains(pos)) {
+        return Err(invalid(
+            snake_line,
+            format!(
+                "there's an obstacle at {},{}, where the snake is",
+                pos.x, pos.y
+            ),
+        ));
+    }
+    Ok(Scenario {
+        snake,
+        dir,
+        came_from,
+        food,
+        obstacles,
+    })
+}
+
+fn parse_cell(cell: &str) -> Result<GridPosition, String> {
+    let parsed = cell
+        .split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+    let Some((x, y)) = parsed else {
+        return Err(format!("expected `x,y`, found `{cell}`"));
+    };
+    if !(0..GRID_SIZE.0).contains(&x) || !(0..GRID_SIZE.1).contains(&y) {
+        return Err(format!(
+            "{x},{y} is off the {}x{} grid",
+            GRID_SIZE.0, GRID_SIZE.1
+        ));
+    }
+    Ok(GridPosition::new(x, y))
+}
+
+impl GameState {
+    /// Swaps the freshly laid out board for `scenario`'s, and keeps it to go back to.
+    pub fn set_up_scenario(&mut self, scenario: Scenario) {
+        let (head, rest) = scenario.snake.split_first().expect("checked on load");
+        let mut body = RingBuffer::with_capacity(GRID_SIZE.0 as usize * GRID_SIZE.1 as usize);
+        for &pos in rest.iter().rev() {
+            Body::push_front(&mut body, Segment::new(pos));
+        }
+        self.snake.head = Segment::new(*head);
+        self.snake.body = body;
+        self.snake.dir = scenario.dir;
+        self.snake.last_update_dir = scenario.came_from;
+        self.obstacles = scenario.obstacles;
+        self.food.respawn(scenario.food, self.food.kind);
+        self.last_food = *head;
+        self.max_len = self.snake.len();
+        self.lay_out_chain();
+        self.scenario = Some(ScenarioStart {
+            start: self.snapshot(),
+            attempt: 1,
+        });
+    }
+
+    /// Puts the board back the way the scenario started, returning which attempt this is
+    /// now, or `None` without a scenario.
+    pub fn retry_scenario(&mut self) -> Option<u32> {
+        let mut scenario = self.scenario.take()?;
+        self.restore(&scenario.start);
+        scenario.attempt += 1;
+        let attempt = scenario.attempt;
+        self.scenario = Some(scenario);
+        Some(attempt)
+    }
+}
@@ -0,0 +1,118 @@
+//! The persistent scoreboard, kept as a plain list of the best scores, one per line.
+
+use ggez::GameResult;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+const SCOREBOARD_FILE: &str = "scores.txt";
+pub const SCOREBOARD_SIZE: usize = 10;
+
+/// Where everything the game keeps between runs lives. `SNAKE_DATA_DIR` overrides it.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("SNAKE_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("snake")
+}
+
+/// The best scores so far, highest first.
+#[derive(Clone, Debug, Default)]
+pub struct Scoreboard {
+    scores: Vec<u32>,
+}
+
+impl Scoreboard {
+    fn path() -> PathBuf {
+        data_dir().join(SCOREBOARD_FILE)
+    }
+
+    /// A missing file is just an empty scoreboard; lines that don't parse are skipped.
+    pub fn load() -> GameResult<Self> {
+        let contents = match fs::read_to_string(Self::path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Scoreboard::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut scores: Vec<u32> = contents
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(SCOREBOARD_SIZE);
+        Ok(Scoreboard { scores })
+    }
+
+    pub fn save(&self) -> GameResult {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents: String = self
+            .scores
+            .iter()
+            .map(|score| format!("{score}\n"))
+            .collect();
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn best(&self) -> Option<u32> {
+        self.scores.first().copied()
+    }
+
+    /// The place `score` would take if it were added now, from 1, or `None` if it wouldn't
+    /// make the cut. A tie goes below the scores already there, as `insert` puts it.
+    pub fn rank(&self, score: u32) -> Option<usize> {
+        let rank = self.scores.partition_point(|&s| s >= score) + 1;
+        (rank <= SCOREBOARD_SIZE).then_some(rank)
+    }
+
+    /// Adds `score` if it makes the cut.
+    pub fn insert(&mut self, score: u32) {
+        let rank = self.scores.partition_point(|&s| s >= score);
+        self.scores.insert(rank, score);
+        self.scores.truncate(SCOREBOARD_SIZE);
+    }
+}
+
+/// `--reset-scores`: asks for confirmation, then deletes the scoreboard.
+/// Returns `false` only if the scores should have been cleared but couldn't be.
+pub fn reset_interactively() -> bool {
+    let path = Scoreboard::path();
+    if !path.exists() {
+        println!("There are no saved scores to clear.");
+        return true;
+    }
+
+    print!("Clear all saved scores in {}? [y/N] ", path.display());
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err()
+        || !answer.trim().eq_ignore_ascii_case("y")
+    {
+        println!("Kept the saved scores.");
+        return true;
+    }
+
+    match fs::remove_file(&path) {
+        Ok(()) => {
+            println!("Cleared the saved scores.");
+            true
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!("There are no saved scores to clear.");
+            true
+        }
+        Err(e) => {
+            println!("Could not clear the saved scores: {e}");
+            false
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! `--selftest`: let the autopilot play a headless game until the board is full, checking the
+//! snake's invariants after every tick. This runs collision, growth, food placement, win
+//! detection and the AI in one go, which makes it a handy regression check.
+
+use crate::config::Config;
+use crate::{GameEvent, GameState, GRID_SIZE};
+use ggez::GameResult;
+
+/// Filling the board takes at most one lap of the cycle per food.
+const MAX_TICKS: u64 = (GRID_SIZE.0 as u64 * GRID_SIZE.1 as u64).pow(2);
+
+/// Returns whether the autopilot managed to fill the board without breaking anything.
+pub fn run(mut config: Config) -> GameResult<bool> {
+    config.autopilot = true;
+    let mut state = GameState::new_headless(config)?;
+    let cells = GRID_SIZE.0 as usize * GRID_SIZE.1 as usize;
+
+    for tick in 1..=MAX_TICKS {
+        let prev_len = state.snake.len();
+        let events = state.step();
+
+        if let Err(violation) = check_invariants(&state, prev_len) {
+            println!("selftest: invariant violated at tick {tick}: {violation}");
+            return Ok(false);
+        }
+        if events.contains(&GameEvent::Died) {
+            println!(
+                "selftest: snake died at tick {tick} with length {}",
+                state.snake.len()
+            );
+            return Ok(false);
+        }
+        if events.contains(&GameEvent::Won) {
+            println!("selftest: filled all {cells} cells in {tick} ticks");
+            return Ok(true);
+        }
+    }
+
+    println!(
+        "selftest: autopilot deadlocked, length {} of {cells} after {MAX_TICKS} ticks",
+        state.snake.len()
+    );
+    Ok(false)
+}
+
+fn check_invariants(state: &GameState, prev_len: usize) -> Result<(), String> {
+    let snake = &state.snake;
+    let len = snake.len();
+    if len < prev_len || len > prev_len + 1 {
+        return Err(format!("length went from {prev_len} to {len}"));
+    }
+    if !state.gameover && snake.eats_self() {
+        return Err("head overlaps the body but the game goes on".to_string());
+    }
+    if let Some(food) = state.food_on_board() {
+        if snake.occupies(food.pos) {
+            return Err(format!("food at {:?} is under the snake", food.pos));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Preferences that carry over from one run to the next, kept as `key=value` lines next to the
+//! scoreboard. Unlike `Config` these are changed from inside the game rather than on the
+//! command line.
+
+use crate::hud::HudLayout;
+use crate::scores::data_dir;
+use crate::transition::TransitionStyle;
+use ggez::GameResult;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    /// How far the view is zoomed in on the playfield, `1.0` showing all of it.
+    pub zoom: f32,
+    /// Where the HUD goes. There's no way to change it in the game, only by editing the file.
+    pub hud: HudLayout,
+    /// Samples per pixel for anti-aliasing, as last picked with `--msaa`.
+    pub msaa: u8,
+    /// How the game switches to and from the game over screen, and how long that takes in
+    /// milliseconds, as last picked with `--transition` and `--transition-ms`.
+    pub transition: TransitionStyle,
+    pub transition_ms: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            zoom: 1.0,
+            hud: HudLayout::default(),
+            msaa: 1,
+            transition: TransitionStyle::default(),
+            transition_ms: 250,
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> PathBuf {
+        data_dir().join(SETTINGS_FILE)
+    }
+
+    /// A missing file means the defaults; unknown keys and values that don't parse are skipped.
+    pub fn load() -> GameResult<Self> {
+        let mut settings = Settings::default();
+        let contents = match fs::read_to_string(Self::path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(settings),
+            Err(e) => return Err(e.into()),
+        };
+        for (key, value) in contents.lines().filter_map(|line| line.split_once('=')) {
+            let (key, value) = (key.trim(), value.trim());
+            if settings.hud.set(key, value) {
+                continue;
+            }
+            match key {
+                "zoom" => settings.zoom = value.parse().unwrap_or(settings.zoom),
+                "msaa" => settings.msaa = value.parse().unwrap_or(settings.msaa),
+                "transition" => settings.transition = value.parse().unwrap_or(settings.transition),
+                "transition_ms" => {
+                    settings.transition_ms = value.parse().unwrap_or(settings.transition_ms)
+                }
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> GameResult {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            path,
+            format!(
+                "zoom={}\nmsaa={}\ntransition={}\ntransition_ms={}\n{}",
+                self.zoom,
+                self.msaa,
+                self.transition.name(),
+                self.transition_ms,
+                self.hud.lines()
+            ),
+        )?;
+        Ok(())
+    }
+}
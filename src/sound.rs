@@ -0,0 +1,202 @@
+use ggez::audio::{self, SoundSource};
+use ggez::{Context, GameResult};
+use std::time::Duration;
+
+/// Whether a sound that is triggered again while still playing starts over or plays on top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SoundOverlap {
+    /// Cut the running sound off and start it again.
+    #[default]
+    Restart,
+    /// Layer the sounds, up to `LAYERED_CHANNELS` at a time, so the mix doesn't clip when
+    /// eating rapidly at high speed.
+    Layered,
+}
+
+impl std::str::FromStr for SoundOverlap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "restart" => Ok(SoundOverlap::Restart),
+            "layered" => Ok(SoundOverlap::Layered),
+            _ => Err(format!("unknown sound overlap `{s}`")),
+        }
+    }
+}
+
+/// How eating is signalled: with the eat sound, with a flash of the screen for players who
+/// can't hear it, or both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EatCue {
+    #[default]
+    Sound,
+    Flash,
+    Both,
+}
+
+impl EatCue {
+    pub fn sound(self) -> bool {
+        self != EatCue::Flash
+    }
+
+    pub fn flash(self) -> bool {
+        self != EatCue::Sound
+    }
+}
+
+impl std::str::FromStr for EatCue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sound" => Ok(EatCue::Sound),
+            "flash" => Ok(EatCue::Flash),
+            "both" => Ok(EatCue::Both),
+            _ => Err(format!("unknown eat cue `{s}`")),
+        }
+    }
+}
+
+const LAYERED_CHANNELS: usize = 3;
+
+/// One sound loaded into a few channels, so it can play over itself a limited number of times.
+pub struct SoundPool {
+    channels: Vec<audio::Source>,
+    /// The channel to cut off when all of them are busy; this cycles so it's always the oldest.
+    next: usize,
+}
+
+impl SoundPool {
+    pub fn new(ctx: &mut Context, path: &str, overlap: SoundOverlap) -> GameResult<Self> {
+        let count = match overlap {
+            SoundOverlap::Restart => 1,
+            SoundOverlap::Layered => LAYERED_CHANNELS,
+        };
+        let channels = (0..count)
+            .map(|_| audio::Source::new(ctx, path))
+            .collect::<GameResult<_>>()?;
+        Ok(SoundPool { channels, next: 0 })
+    }
+
+    /// Plays the sound at `pitch` times its usual speed.
+    pub fn play(&mut self, ctx: &mut Context, pitch: f32) {
+        let channel = match self.channels.iter().position(|channel| !channel.playing()) {
+            Some(idle) => idle,
+            None => {
+                let oldest = self.next;
+                self.next = (self.next + 1) % self.channels.len();
+                oldest
+            }
+        };
+        self.channels[channel].set_pitch(pitch);
+        let _ = self.channels[channel].play(ctx);
+    }
+}
+
+/// How the music dips out of the way of the eat sound, see `Music::duck`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    /// How far the volume drops, from `0.0` (not at all) to `1.0` (silent).
+    pub depth: f32,
+    /// How long the volume takes to come back up once the bite's over.
+    pub recovery: Duration,
+}
+
+/// How long the music stays down for a bite, about as long as the eat sound.
+const DUCK_HOLD: Duration = Duration::from_millis(150);
+/// How long the music takes to go all the way down, quick but not so quick it clicks.
+const DUCK_ATTACK: Duration = Duration::from_millis(20);
+
+/// How much higher the music plays at full tension, see `Music`.
+const MAX_PITCH_RISE: f32 = 0.3;
+/// The pitch only changes in steps this big.
+const PITCH_STEP: f32 = 0.05;
+/// Roughly how long the pitch takes to follow a change in tension.
+const TENSION_SMOOTHING: Duration = Duration::from_millis(500);
+/// Fades the track back in after a change of pitch, so the restart doesn't click.
+const PITCH_CHANGE_FADE: Duration = Duration::from_millis(30);
+
+/// A track looping in the background. With tension on, it speeds up and rises in pitch the
+/// closer the snake gets to running into something.
+///
+/// A sound that's already playing can't change pitch, so at each new pitch the track starts
+/// again from where it had got to. That's why the pitch moves in `PITCH_STEP`s.
+pub struct Music {
+    source: audio::Source,
+    /// The tension the pitch is following, smoothed so it doesn't jump about every tick.
+    tension: f32,
+    pitch: f32,
+    /// How far into the track the last pitch change was.
+    position: Duration,
+    volume: f32,
+    ducking: Ducking,
+    /// How much longer the music stays ducked before it starts coming back up.
+    duck_hold: Duration,
+}
+
+impl Music {
+    pub fn new(ctx: &mut Context, path: &str) -> GameResult<Self> {
+        let mut source = audio::Source::new(ctx, path)?;
+        source.set_repeat(true);
+        source.play(ctx)?;
+        source.set_fade_in(PITCH_CHANGE_FADE);
+        Ok(Music {
+            source,
+            tension: 0.0,
+            pitch: 1.0,
+            position: Duration::ZERO,
+            volume: 1.0,
+            ducking: Ducking {
+                depth: 0.0,
+                recovery: Duration::ZERO,
+            },
+            duck_hold: Duration::ZERO,
+        })
+    }
+
+    /// Dips the volume for a moment to make room for another sound. Ducking again before the
+    /// music's back up holds it down for longer rather than taking it any lower.
+    pub fn duck(&mut self, ducking: Ducking) {
+        self.ducking = ducking;
+        self.duck_hold = DUCK_HOLD;
+    }
+
+    /// Moves the volume along the ducking envelope: down over `DUCK_ATTACK`, held there for
+    /// `DUCK_HOLD`, then back up over the recovery time.
+    fn update_volume(&mut self, delta: Duration) {
+        let Ducking { depth, recovery } = self.ducking;
+        let volume = if !self.duck_hold.is_zero() {
+            self.duck_hold = self.duck_hold.saturating_sub(delta);
+            let fall = depth * delta.as_secs_f32() / DUCK_ATTACK.as_secs_f32();
+            (self.volume - fall).max(1.0 - depth)
+        } else if recovery.is_zero() {
+            1.0
+        } else {
+            (self.volume + depth * delta.as_secs_f32() / recovery.as_secs_f32()).min(1.0)
+        };
+        if volume != self.volume {
+            self.volume = volume;
+            self.source.set_volume(volume);
+        }
+    }
+
+    /// Moves the volume along after any ducking, and the pitch towards `tension`, from `0.0`
+    /// (nothing nearby) to `1.0` (about to hit something), over `delta` since last time.
+    pub fn update(&mut self, ctx: &mut Context, tension: f32, delta: Duration) {
+        self.update_volume(delta);
+        let follow = 1.0 - (-delta.as_secs_f32() / TENSION_SMOOTHING.as_secs_f32()).exp();
+        self.tension += (tension - self.tension) * follow;
+        let pitch = 1.0 + (MAX_PITCH_RISE * self.tension / PITCH_STEP).round() * PITCH_STEP;
+        if (pitch - self.pitch).abs() < PITCH_STEP / 2.0 {
+            return;
+        }
+        self.position += self.source.elapsed().mul_f32(self.pitch);
+        self.pitch = pitch;
+        self.source.set_pitch(pitch);
+        self.source.set_start(self.position);
+        if let Err(e) = self.source.play(ctx) {
+            log::warn!("event=music_failed error={e}");
+        }
+    }
+}
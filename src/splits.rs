@@ -0,0 +1,145 @@
+//! `--splits N`: a speedrun timer that starts on the first turn and takes a split every `N`
+//! points, checked against the best run so far at the same milestone.
+//!
+//! The best run is kept per milestone in `splits-N.txt`, one split per line in milliseconds.
+//! A run beats it by reaching more milestones, or the same number sooner.
+
+use crate::scores::data_dir;
+use crate::text::{self, TextStyle};
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Color};
+use ggez::GameResult;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct Splits {
+    /// Points between splits.
+    milestone: u32,
+    started: Option<Duration>,
+    /// When the game ended and the clock stopped.
+    stopped: Option<Duration>,
+    /// Time since the start at each milestone reached so far.
+    times: Vec<Duration>,
+    best: Vec<Duration>,
+}
+
+impl Splits {
+    /// A timer splitting every `milestone` points, with the best run so far loaded.
+    pub fn new(milestone: u32) -> GameResult<Self> {
+        let path = Self::path(milestone);
+        let best = match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .map(Duration::from_millis)
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Splits {
+            milestone,
+            started: None,
+            stopped: None,
+            times: Vec::new(),
+            best,
+        })
+    }
+
+    fn path(milestone: u32) -> PathBuf {
+        data_dir().join(format!("splits-{milestone}.txt"))
+    }
+
+    /// Starts the clock, unless it's already running.
+    pub fn start(&mut self, now: Duration) {
+        self.started.get_or_insert(now);
+    }
+
+    /// Takes a split for every milestone `score` has passed since the last one.
+    pub fn record(&mut self, now: Duration, score: u32) {
+        let Some(started) = self.started.filter(|_| self.stopped.is_none()) else {
+            return;
+        };
+        while score >= self.milestone * (self.times.len() as u32 + 1) {
+            self.times.push(now - started);
+        }
+    }
+
+    /// Stops the clock, and saves this run as the best if it beat the last one.
+    pub fn finish(&mut self, now: Duration) -> GameResult {
+        if self.started.is_none() || self.stopped.is_some() {
+            return Ok(());
+        }
+        self.stopped = Some(now);
+        let beats_best = match self.times.len().cmp(&self.best.len()) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => self.times.last() < self.best.last(),
+            std::cmp::Ordering::Less => false,
+        };
+        if !beats_best {
+            return Ok(());
+        }
+        let path = Self::path(self.milestone);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents: String = self
+            .times
+            .iter()
+            .map(|time| format!("{}\n", time.as_millis()))
+            .collect();
+        fs::write(path, contents)?;
+        self.best = self.times.clone();
+        Ok(())
+    }
+
+    /// The running time, then each split with how far ahead (green) or behind (red) of the
+    /// best run it was, down the right-hand edge starting at `top_right`.
+    pub fn draw(
+        &self,
+        canvas: &mut graphics::Canvas,
+        style: &TextStyle,
+        now: Duration,
+        top_right: Vec2,
+    ) {
+        let elapsed = self.started.map_or(Duration::ZERO, |started| {
+            self.stopped.unwrap_or(now) - started
+        });
+        let mut lines = vec![(format_time(elapsed), Color::BLACK)];
+        for (i, &time) in self.times.iter().enumerate() {
+            let points = self.milestone * (i as u32 + 1);
+            let (delta, color) = match self.best.get(i) {
+                Some(&best) if time <= best => (
+                    format!("-{}", format_time(best - time)),
+                    Color::from_rgb(0, 130, 0),
+                ),
+                Some(&best) => (
+                    format!("+{}", format_time(time - best)),
+                    Color::from_rgb(200, 0, 0),
+                ),
+                None => (String::new(), Color::BLACK),
+            };
+            lines.push((format!("{points}: {} {delta}", format_time(time)), color));
+        }
+        for (i, (line, color)) in lines.into_iter().enumerate() {
+            let mut text = style.text(line, text::DEFAULT_SIZE);
+            text.set_layout(graphics::TextLayout {
+                h_align: graphics::TextAlign::End,
+                v_align: graphics::TextAlign::Begin,
+            });
+            canvas.draw(
+                &text,
+                graphics::DrawParam::new()
+                    .dest(top_right + Vec2::new(0.0, i as f32 * text::DEFAULT_SIZE * 1.25))
+                    .color(color),
+            );
+        }
+    }
+}
+
+/// `m:ss.t`.
+pub fn format_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
@@ -0,0 +1,54 @@
+//! The food's animation: a sprite sheet of square frames side by side, played on a loop. The
+//! frames are as tall as the sheet, so a sheet 64 pixels high and 256 wide has four.
+
+use ggez::graphics::{self, Image};
+use ggez::{Context, GameResult};
+use std::time::Duration;
+
+pub struct SpriteSheet {
+    image: Image,
+    frames: u32,
+    /// Frames shown a second.
+    fps: f32,
+}
+
+impl SpriteSheet {
+    /// Loads `file` from the resources, or `None` if there's no such file so the food can be
+    /// drawn plain. A file that's there but isn't an image is an error.
+    pub fn load(ctx: &mut Context, file: &str, fps: f32) -> GameResult<Option<Self>> {
+        let path = format!("/{file}");
+        if !ctx.fs.exists(&path) {
+            log::info!("{file} is not in the resources, drawing the food plain");
+            return Ok(None);
+        }
+        let image = Image::from_path(ctx, &path)?;
+        let frames = (image.width() / image.height().max(1)).max(1);
+        Ok(Some(SpriteSheet { image, frames, fps }))
+    }
+
+    /// The part of the sheet to show `now`, in the fractions of it `DrawParam::src` takes.
+    fn frame(&self, now: Duration) -> graphics::Rect {
+        let frame = (now.as_secs_f32() * self.fps) as u64 % self.frames as u64;
+        let width = 1.0 / self.frames as f32;
+        graphics::Rect::new(frame as f32 * width, 0.0, width, 1.0)
+    }
+
+    /// Draws the frame for `now` stretched over `dest`, tinted by `color`.
+    pub fn draw(
+        &self,
+        canvas: &mut graphics::Canvas,
+        dest: graphics::Rect,
+        color: impl Into<graphics::Color>,
+        now: Duration,
+    ) {
+        let size = self.image.height() as f32;
+        canvas.draw(
+            &self.image,
+            graphics::DrawParam::new()
+                .src(self.frame(now))
+                .dest(dest.point())
+                .scale([dest.w / size, dest.h / size])
+                .color(color),
+        );
+    }
+}
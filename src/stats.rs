@@ -0,0 +1,126 @@
+//! A history of every finished game, appended to a file one line per game, and the `--stats`
+//! summary computed from it.
+
+use crate::scores::data_dir;
+use crate::WrapMode;
+use ggez::{GameError, GameResult};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const HISTORY_FILE: &str = "history.txt";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    /// When the game ended, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub seed: u64,
+    pub score: u32,
+    pub max_length: usize,
+    pub duration: Duration,
+    pub mode: WrapMode,
+}
+
+impl GameRecord {
+    /// Space separated, in field order, with the duration in seconds.
+    pub fn serialize(&self) -> String {
+        format!(
+            "{} {} {} {} {:.3} {}",
+            self.timestamp,
+            self.seed,
+            self.score,
+            self.max_length,
+            self.duration.as_secs_f64(),
+            self.mode.name()
+        )
+    }
+
+    pub fn deserialize(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let record = GameRecord {
+            timestamp: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+            score: fields.next()?.parse().ok()?,
+            max_length: fields.next()?.parse().ok()?,
+            duration: Duration::try_from_secs_f64(fields.next()?.parse().ok()?).ok()?,
+            mode: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(record)
+    }
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join(HISTORY_FILE)
+}
+
+pub fn append(record: &GameRecord) -> GameResult {
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.serialize())?;
+    Ok(())
+}
+
+/// Every game on record. A missing history just means no games have been played yet.
+pub fn load() -> GameResult<Vec<GameRecord>> {
+    let contents = match fs::read_to_string(history_path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            GameRecord::deserialize(line).ok_or_else(|| {
+                GameError::ResourceLoadError(format!(
+                    "{}:{}: malformed game record",
+                    history_path().display(),
+                    i + 1
+                ))
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub average_score: f64,
+    pub best_score: u32,
+    pub longest: usize,
+    pub total_time: Duration,
+}
+
+pub fn summarize(records: &[GameRecord]) -> Option<Summary> {
+    if records.is_empty() {
+        return None;
+    }
+    let total_score: u64 = records.iter().map(|record| record.score as u64).sum();
+    Some(Summary {
+        games: records.len(),
+        average_score: total_score as f64 / records.len() as f64,
+        best_score: records.iter().map(|record| record.score).max()?,
+        longest: records.iter().map(|record| record.max_length).max()?,
+        total_time: records.iter().map(|record| record.duration).sum(),
+    })
+}
+
+/// `--stats`: prints the summary instead of playing.
+pub fn print() -> GameResult {
+    match summarize(&load()?) {
+        None => println!("No games played yet."),
+        Some(summary) => {
+            println!("Games played:  {}", summary.games);
+            println!("Average score: {:.1}", summary.average_score);
+            println!("Best score:    {}", summary.best_score);
+            println!("Longest snake: {}", summary.longest);
+            println!("Time played:   {:.0}s", summary.total_time.as_secs_f64());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! `--stress N`: a debugging aid for seeing how drawing holds up with far more on screen than
+//! a game ever has. `N` decorative snakes wander the board at random, going straight through
+//! each other and everything else, and are all drawn as a single `InstanceArray`. The HUD
+//! shows how many instances that is and how long frames are taking.
+//!
+//! They have a random number generator of their own and never touch the game, so a stress
+//! run plays out the same as it would without them.
+
+use crate::{Direction, GridPosition, WrapMode, GRID_SIZE};
+use ggez::graphics;
+use ggez::Context;
+use oorandom::Rand32;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many cells long each of them is.
+const LENGTH: usize = 8;
+/// The chance each step that one of them turns.
+const TURN_CHANCE: f32 = 0.25;
+/// How much of each new frame time goes into the average shown, the rest being the old one.
+const SMOOTHING: f32 = 0.05;
+
+struct Wanderer {
+    /// Head first.
+    cells: VecDeque<GridPosition>,
+    dir: Direction,
+    color: [f32; 4],
+}
+
+pub struct Stress {
+    wanderers: Vec<Wanderer>,
+    rng: Rand32,
+    cells: Option<graphics::InstanceArray>,
+    /// Frame times averaged over the last few dozen frames.
+    frame_time: Duration,
+}
+
+impl Stress {
+    pub fn new(count: usize) -> Self {
+        let mut rng = Rand32::new(count as u64);
+        let wanderers = (0..count)
+            .map(|_| {
+                let head = GridPosition::new(
+                    rng.rand_range(0..GRID_SIZE.0 as u32) as i16,
+                    rng.rand_range(0..GRID_SIZE.1 as u32) as i16,
+                );
+                Wanderer {
+                    cells: VecDeque::from([head]),
+                    dir: Direction::CARDINAL[rng.rand_range(0..4) as usize],
+                    color: [rng.rand_float(), rng.rand_float(), rng.rand_float(), 0.8],
+                }
+            })
+            .collect();
+        Stress {
+            wanderers,
+            rng,
+            cells: None,
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    /// Moves every one of them a cell, now and then turning.
+    pub fn step(&mut self) {
+        for wanderer in &mut self.wanderers {
+            if self.rng.rand_float() < TURN_CHANCE {
+                let turns = wanderer.dir.perpendicular();
+                wanderer.dir = turns[self.rng.rand_range(0..turns.len() as u32) as usize];
+            }
+            let head = wanderer.cells[0];
+            if let Some(next) = GridPosition::moved(head, wanderer.dir, WrapMode::Wrap) {
+                wanderer.cells.push_front(next);
+            }
+            wanderer.cells.truncate(LENGTH);
+        }
+    }
+
+    pub fn record_frame(&mut self, delta: Duration) {
+        self.frame_time = if self.frame_time.is_zero() {
+            delta
+        } else {
+            self.frame_time.mul_f32(1.0 - SMOOTHING) + delta.mul_f32(SMOOTHING)
+        };
+    }
+
+    fn instances(&self) -> usize {
+        self.wanderers
+            .iter()
+            .map(|wanderer| wanderer.cells.len())
+            .sum()
+    }
+
+    pub fn draw(&mut self, ctx: &Context, canvas: &mut graphics::Canvas) {
+        let cells = self
+            .cells
+            .get_or_insert_with(|| graphics::InstanceArray::new(ctx, None));
+        cells.set(self.wanderers.iter().flat_map(|wanderer| {
+            wanderer.cells.iter().map(|&pos| {
+                graphics::DrawParam::new()
+                    .dest_rect(pos.into())
+                    .color(wanderer.color)
+            })
+        }));
+        canvas.draw(cells, graphics::DrawParam::new());
+    }
+
+    /// The HUD's line for it.
+    pub fn status(&self) -> String {
+        format!(
+            "Stress: {} snakes, {} instances, {:.1} ms a frame",
+            self.wanderers.len(),
+            self.instances(),
+            self.frame_time.as_secs_f32() * 1000.0
+        )
+    }
+}
@@ -0,0 +1,96 @@
+//! `--swarm N`: steering `N` snakes at once. Every turn goes to all of them, they share the
+//! food and the score, and the game is over as soon as any one of them runs into a wall,
+//! itself or another snake of the swarm.
+//!
+//! The first snake is the usual `GameState::snake`, which whatever follows a single snake,
+//! like the autopilot and the ghost hint, keeps going by. The others are `GameState::swarm`.
+
+use crate::{start_cells, Ate, Direction, Food, GameState, GridPosition, Rules, Snake, WrapMode};
+use crate::{GRID_SIZE, INITIAL_LENGTH};
+use ggez::{GameError, GameResult};
+use std::collections::HashSet;
+
+/// The most snakes a swarm can have, before they no longer fit side by side.
+pub const MAX_SNAKES: usize = 4;
+
+/// Where each of `count` snakes facing `dir` starts, the first at `lead`. They're spread
+/// evenly across the board side by side, so they run alongside each other until they turn.
+pub fn spread(
+    lead: GridPosition,
+    dir: Direction,
+    count: usize,
+    wrap_mode: WrapMode,
+) -> GameResult<Vec<GridPosition>> {
+    if count <= 1 {
+        return Ok(vec![lead]);
+    }
+    let (_, dy) = dir.offset();
+    let nth = |i: usize, size: i16| (size as usize * (2 * i + 1) / (2 * count)) as i16;
+    (0..count)
+        .map(|i| {
+            let pos = if dy == 0 {
+                GridPosition::new(lead.x, nth(i, GRID_SIZE.1))
+            } else {
+                GridPosition::new(nth(i, GRID_SIZE.0), lead.y)
+            };
+            start_cells(pos, dir, INITIAL_LENGTH, wrap_mode)
+                .map(|_| pos)
+                .ok_or_else(|| {
+                    GameError::ConfigError(format!(
+                        "{count} snakes facing {dir:?} don't fit side by side on a {}x{} grid \
+                         in {wrap_mode:?} mode",
+                        GRID_SIZE.0, GRID_SIZE.1
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Moves the rest of the swarm once `lead` has moved, and returns what that came to: running
+/// into another snake, lead included, counts as running into itself, then a snake that died
+/// on its own, then one that ate the food.
+pub fn update(
+    swarm: &mut [Snake],
+    lead: &Snake,
+    food: Option<&Food>,
+    obstacles: &HashSet<GridPosition>,
+    rules: Rules,
+    wrap_blocked: bool,
+) -> Option<Ate> {
+    if swarm.is_empty() {
+        return None;
+    }
+    for snake in swarm.iter_mut() {
+        snake.update(food, obstacles, rules, wrap_blocked);
+    }
+    let snakes: Vec<&Snake> = std::iter::once(lead).chain(swarm.iter()).collect();
+    let collided = snakes.iter().enumerate().any(|(i, snake)| {
+        snakes
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && other.occupies(snake.head.pos))
+    });
+    if collided {
+        return Some(Ate::Itself);
+    }
+    let died = swarm
+        .iter()
+        .find_map(|snake| snake.ate.filter(|ate| !matches!(ate, Ate::Food)));
+    died.or_else(|| swarm.iter().find_map(|snake| snake.ate))
+}
+
+impl GameState {
+    /// The lead and then the rest of the swarm, or just the snake without `--swarm`.
+    pub fn snakes(&self) -> impl Iterator<Item = &Snake> {
+        std::iter::once(&self.snake).chain(self.swarm.iter())
+    }
+
+    pub fn snakes_mut(&mut self) -> impl Iterator<Item = &mut Snake> {
+        std::iter::once(&mut self.snake).chain(self.swarm.iter_mut())
+    }
+
+    /// How long all the snakes are put together.
+    pub fn swarm_len(&self) -> usize {
+        self.snakes().map(Snake::len).sum()
+    }
+}
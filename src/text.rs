@@ -0,0 +1,60 @@
+//! Every bit of text on screen is made here, so the HUD and overlays all share one font and
+//! one scale.
+
+use ggez::graphics::{self, FontData, TextFragment};
+use ggez::{Context, GameResult};
+
+/// The size ggez gives text that doesn't ask for one.
+pub const DEFAULT_SIZE: f32 = 16.0;
+/// What the custom font is registered as with ggez.
+const FONT_NAME: &str = "custom";
+
+#[derive(Clone, Debug)]
+pub struct TextStyle {
+    /// The custom font, or `None` for ggez's built-in one.
+    font: Option<String>,
+    /// Applied on top of the size each piece of text asks for.
+    scale: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            font: None,
+            scale: 1.0,
+        }
+    }
+}
+
+impl TextStyle {
+    /// The built-in font at `scale`.
+    pub fn plain(scale: f32) -> Self {
+        TextStyle { font: None, scale }
+    }
+
+    /// Loads `file` from the resources as the font. If there's no such file the built-in
+    /// font is used instead; a file that's there but isn't a font is an error.
+    pub fn load(ctx: &mut Context, file: &str, scale: f32) -> GameResult<Self> {
+        let path = format!("/{file}");
+        if !ctx.fs.exists(&path) {
+            log::info!("{file} is not in the resources, using the default font");
+            return Ok(TextStyle::plain(scale));
+        }
+        let font = FontData::from_path(ctx, &path)?;
+        ctx.gfx.add_font(FONT_NAME, font);
+        Ok(TextStyle {
+            font: Some(FONT_NAME.to_string()),
+            scale,
+        })
+    }
+
+    /// `contents` in the shared font, `size` pixels high before scaling.
+    pub fn text(&self, contents: impl Into<TextFragment>, size: f32) -> graphics::Text {
+        let mut text = graphics::Text::new(contents);
+        text.set_scale(size * self.scale);
+        if let Some(font) = &self.font {
+            text.set_font(font);
+        }
+        text
+    }
+}
@@ -0,0 +1,118 @@
+//! The wipe or fade between playing and the game over screen, either way round, and from the
+//! loading screen onto the game. The new screen is uncovered over the length of the
+//! transition, and input waits until it's done so a key held from the old screen doesn't land
+//! on the new one.
+//!
+//! The style and length are kept in the settings, and `none` or a length of `0` switches
+//! straight over as before.
+
+use ggez::graphics;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionStyle {
+    /// Switch screens at once.
+    None,
+    /// Fade in from black.
+    #[default]
+    Fade,
+    /// A black curtain drawn back from left to right.
+    Wipe,
+}
+
+impl TransitionStyle {
+    pub fn name(self) -> &'static str {
+        match self {
+            TransitionStyle::None => "none",
+            TransitionStyle::Fade => "fade",
+            TransitionStyle::Wipe => "wipe",
+        }
+    }
+}
+
+impl std::str::FromStr for TransitionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(TransitionStyle::None),
+            "fade" => Ok(TransitionStyle::Fade),
+            "wipe" => Ok(TransitionStyle::Wipe),
+            _ => Err(format!("unknown transition `{s}`")),
+        }
+    }
+}
+
+pub struct Transition {
+    pub style: TransitionStyle,
+    pub duration: Duration,
+    started: Option<Duration>,
+    /// Whether the screen being shown is the game over one, to notice when that changes.
+    gameover: bool,
+}
+
+impl Transition {
+    pub fn new(style: TransitionStyle, duration: Duration) -> Self {
+        Transition {
+            style,
+            duration,
+            started: None,
+            gameover: false,
+        }
+    }
+
+    /// Starts a transition if the game has gone over to or back from the game over screen
+    /// since the last call.
+    pub fn follow(&mut self, gameover: bool, now: Duration) {
+        if gameover == self.gameover {
+            return;
+        }
+        self.gameover = gameover;
+        self.start(now);
+    }
+
+    /// Starts a transition onto whatever screen is showing now.
+    pub fn start(&mut self, now: Duration) {
+        if self.style != TransitionStyle::None && !self.duration.is_zero() {
+            self.started = Some(now);
+        }
+    }
+
+    /// How far through the transition it is `now`, from `0.0` to `1.0`, or `None` when there
+    /// isn't one going.
+    fn progress(&self, now: Duration) -> Option<f32> {
+        let elapsed = now.saturating_sub(self.started?);
+        (elapsed < self.duration).then(|| elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+
+    pub fn active(&self, now: Duration) -> bool {
+        self.progress(now).is_some()
+    }
+
+    /// Covers what of `screen` hasn't been uncovered yet.
+    pub fn draw(&self, canvas: &mut graphics::Canvas, screen: graphics::Rect, now: Duration) {
+        let Some(progress) = self.progress(now) else {
+            return;
+        };
+        let (cover, alpha) = match self.style {
+            TransitionStyle::None => return,
+            TransitionStyle::Fade => (screen, 1.0 - progress),
+            TransitionStyle::Wipe => {
+                let uncovered = screen.w * progress;
+                let cover = graphics::Rect::new(
+                    screen.x + uncovered,
+                    screen.y,
+                    screen.w - uncovered,
+                    screen.h,
+                );
+                (cover, 1.0)
+            }
+        };
+        canvas.draw(
+            &graphics::Quad,
+            graphics::DrawParam::new()
+                .dest_rect(cover)
+                .color(graphics::Color::new(0.0, 0.0, 0.0, alpha)),
+        );
+    }
+}
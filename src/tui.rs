@@ -0,0 +1,236 @@
+//! `--tui`: playing in the terminal instead of a window. The board is drawn in colored
+//! blocks, two characters to a cell so they come out roughly square, through the same
+//! `Surface` the window draws its cells with, and the keys are read straight off stdin.
+//!
+//! There's no sound, and none of the window's extras like the HUD corners or the animations,
+//! just the board and a status line under it. It needs a terminal with 24-bit color and
+//! `stty`, which rules out the Windows console.
+
+use crate::body::Body;
+use crate::config::Config;
+use crate::render::Surface;
+use crate::{Direction, GameState, GridPosition, GRID_SIZE, SNAKE_BODY_COLOR, SNAKE_HEAD_COLOR};
+use ggez::{GameError, GameResult};
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const BACKGROUND: [f32; 4] = [0.0, 0.6, 0.0, 1.0];
+/// How long to wait for a key between ticks before checking again.
+const KEY_POLL: Duration = Duration::from_millis(5);
+
+enum Key {
+    Steer(Direction),
+    Pause,
+    Quit,
+}
+
+/// Turns the bytes coming in on stdin into keys: the arrows, WASD and HJKL to steer, `P` or
+/// space to pause, and `Q` or Ctrl+C to quit. Escape is left alone as it starts the arrows.
+fn keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let key = match &bytes[i..] {
+            [0x1b, b'[', arrow, ..] => {
+                i += 2;
+                match arrow {
+                    b'A' => Some(Key::Steer(Direction::Up)),
+                    b'B' => Some(Key::Steer(Direction::Down)),
+                    b'C' => Some(Key::Steer(Direction::Right)),
+                    b'D' => Some(Key::Steer(Direction::Left)),
+                    _ => None,
+                }
+            }
+            [b'w' | b'W' | b'k' | b'K', ..] => Some(Key::Steer(Direction::Up)),
+            [b's' | b'S' | b'j' | b'J', ..] => Some(Key::Steer(Direction::Down)),
+            [b'a' | b'A' | b'h' | b'H', ..] => Some(Key::Steer(Direction::Left)),
+            [b'd' | b'D' | b'l' | b'L', ..] => Some(Key::Steer(Direction::Right)),
+            [b'p' | b'P' | b' ', ..] => Some(Key::Pause),
+            [b'q' | b'Q' | 0x03, ..] => Some(Key::Quit),
+            _ => None,
+        };
+        keys.extend(key);
+        i += 1;
+    }
+    keys
+}
+
+/// The terminal switched over to reading keys as they're pressed, without echoing them, and
+/// with the cursor hidden. Everything is put back as it was when this is dropped.
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    fn new() -> GameResult<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        print!("\x1b[?25l\x1b[2J");
+        io::stdout().flush()?;
+        Ok(RawTerminal {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h");
+        let _ = io::stdout().flush();
+        if let Err(e) = stty(&[&self.saved]) {
+            eprintln!("Could not put the terminal back, try `stty sane`: {e}");
+        }
+    }
+}
+
+/// Runs `stty` on the terminal, returning what it printed.
+fn stty(args: &[&str]) -> GameResult<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| GameError::CustomError(format!("`--tui` needs `stty`: {e}")))?;
+    if !output.status.success() {
+        return Err(GameError::CustomError(
+            "`--tui` needs to be run in a terminal".to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads stdin on a thread of its own, so the game can go on while no key is pressed.
+fn read_keys() -> Receiver<Key> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0; 16];
+        while let Ok(n @ 1..) = io::stdin().read(&mut buf) {
+            for key in keys(&buf[..n]) {
+                if tx.send(key).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// One frame's worth of cells.
+struct Frame {
+    cells: Vec<[f32; 4]>,
+}
+
+impl Frame {
+    fn new() -> Self {
+        Frame {
+            cells: vec![BACKGROUND; GRID_SIZE.0 as usize * GRID_SIZE.1 as usize],
+        }
+    }
+
+    /// The frame as escape codes, drawn over the last one from the top left corner.
+    fn render(&self, status: &str) -> String {
+        let mut out = String::from("\x1b[H");
+        for row in self.cells.chunks(GRID_SIZE.0 as usize) {
+            let mut last = None;
+            for &color in row {
+                if last != Some(color) {
+                    let [r, g, b] = [color[0], color[1], color[2]].map(|c| (c * 255.0) as u8);
+                    let _ = write!(out, "\x1b[48;2;{r};{g};{b}m");
+                    last = Some(color);
+                }
+                out.push_str("  ");
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        let _ = writeln!(out, "\x1b[K{status}");
+        out
+    }
+}
+
+impl Surface for Frame {
+    fn fill(&mut self, pos: GridPosition, color: [f32; 4]) {
+        let index = pos.y as usize * GRID_SIZE.0 as usize + pos.x as usize;
+        if let Some(cell) = self.cells.get_mut(index) {
+            *cell = color;
+        }
+    }
+}
+
+impl GameState {
+    /// The board as plain cells: the obstacles, the food and the snakes, without any of the
+    /// window's animation.
+    pub fn paint_board(&self, surface: &mut impl Surface, now: Duration) {
+        self.paint_obstacles(surface);
+        if let Some(food) = self.food_on_board() {
+            surface.fill(food.pos, food.color());
+        }
+        let body_visible = self.body_visible(now);
+        for snake in self.snakes() {
+            if body_visible {
+                for seg in snake.body.iter() {
+                    surface.fill(seg.pos, SNAKE_BODY_COLOR);
+                }
+            }
+            surface.fill(snake.head.pos, SNAKE_HEAD_COLOR);
+        }
+    }
+
+    fn tui_status(&self) -> String {
+        let state = if self.won {
+            "You won! Press Q to quit"
+        } else if self.gameover {
+            "Game over! Press Q to quit"
+        } else if self.paused {
+            "Paused, P to carry on"
+        } else {
+            "Arrows to steer, P to pause, Q to quit"
+        };
+        format!(
+            "Score: {}  Length: {}  {state}",
+            self.score,
+            self.swarm_len()
+        )
+    }
+}
+
+/// Plays a game in the terminal until it's quit, redrawing whenever a tick or a key changes
+/// something.
+pub fn run(config: Config) -> GameResult {
+    let mut state = GameState::new_headless(config)?;
+    let _terminal = RawTerminal::new()?;
+    let keys = read_keys();
+    let started = Instant::now();
+    let mut next_tick = started;
+    let mut changed = true;
+    loop {
+        for key in keys.try_iter() {
+            match key {
+                Key::Quit => return Ok(()),
+                Key::Pause if !state.gameover => state.paused = !state.paused,
+                Key::Steer(dir) if !state.gameover && !state.paused => state.steer(dir),
+                _ => continue,
+            }
+            changed = true;
+        }
+        let now = Instant::now();
+        if now >= next_tick && !state.gameover {
+            state.step();
+            state.follow_speed_curve();
+            // After falling behind, carry on from now rather than rushing to catch up.
+            next_tick =
+                (next_tick + Duration::from_secs_f64(1.0 / state.tick_rate.max(1) as f64)).max(now);
+            changed = true;
+        }
+        if changed {
+            let mut frame = Frame::new();
+            state.paint_board(&mut frame, now - started);
+            print!("{}", frame.render(&state.tui_status()));
+            io::stdout().flush()?;
+            changed = false;
+        }
+        thread::sleep(KEY_POLL);
+    }
+}
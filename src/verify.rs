@@ -0,0 +1,79 @@
+//! `--verify FILE`: plays a replay back headlessly and checks the game ends the way the
+//! recording did, with the same score, length and state hash on the same tick. A replay that
+//! plays out differently means the format or the simulation has drifted, so any mismatch
+//! exits non-zero, for running in CI.
+//!
+//! Replays store the rules they were recorded with and refuse to play under any others, so the
+//! check has to be run with the same options the game was recorded with. Replays from before
+//! that are played under whatever options are given.
+
+use crate::config::Config;
+use crate::replay::{Outcome, ReplayFile};
+use crate::GameState;
+use ggez::GameResult;
+use std::path::Path;
+
+/// Returns whether the replay played out just as it was recorded.
+pub fn run(mut config: Config, path: &Path) -> GameResult<bool> {
+    let Some(expected) = ReplayFile::load(path)?.result else {
+        println!(
+            "verify: {} was saved before replays kept their result, there's nothing to check",
+            path.display()
+        );
+        return Ok(false);
+    };
+    config.replay = Some(path.to_path_buf());
+    let mut state = GameState::new_headless(config)?;
+    while !state.gameover && state.tick < expected.ticks {
+        state.step();
+    }
+    let actual = state.replay_result();
+    if actual == expected {
+        println!(
+            "verify: {}: {} after {} ticks with {} points, as recorded",
+            path.display(),
+            match actual.outcome {
+                Outcome::Quit => "the game was quit",
+                Outcome::Died => "the snake died",
+                Outcome::Won => "the snake filled the board",
+            },
+            actual.ticks,
+            actual.score,
+        );
+        return Ok(true);
+    }
+    println!("verify: {} played out differently", path.display());
+    let fields = [
+        (
+            "outcome",
+            format!("{:?}", expected.outcome),
+            format!("{:?}", actual.outcome),
+        ),
+        (
+            "ticks",
+            expected.ticks.to_string(),
+            actual.ticks.to_string(),
+        ),
+        (
+            "score",
+            expected.score.to_string(),
+            actual.score.to_string(),
+        ),
+        (
+            "length",
+            expected.length.to_string(),
+            actual.length.to_string(),
+        ),
+        (
+            "hash",
+            format!("{:016x}", expected.hash),
+            format!("{:016x}", actual.hash),
+        ),
+    ];
+    for (name, expected, actual) in fields {
+        if expected != actual {
+            println!("  {name}: recorded {expected}, replayed {actual}");
+        }
+    }
+    Ok(false)
+}